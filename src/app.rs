@@ -1,8 +1,9 @@
 use std::{
+    borrow::Cow,
     collections::HashSet,
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{self, Seek, SeekFrom, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use arboard::Clipboard;
@@ -11,11 +12,15 @@ use ratatui::{Frame, layout::Rect, widgets::ListState};
 
 use crate::{
     config::*,
+    disasm::DisasmView,
     editor::*,
+    formats::{self, Binary},
     global::calculator::Calculator,
-    hex::{hex_view::HexView, strings::FoundString},
+    hex::{comment::Comment, hex_view::HexView, strings::FoundString},
     input_history::InputHistory,
+    piece_table::{Piece, PieceTable},
     reader::Reader,
+    save::{self, FileStamp},
     themes::*,
 };
 
@@ -28,6 +33,10 @@ pub struct FileInfo {
     pub r#type: &'static str,
     pub size: usize,
     pub mmap: Option<MemoryMappedFile>,
+    pub pieces: PieceTable,
+    pub stamp: Option<FileStamp>,
+    pub backed_up: bool,
+    pub binary: Option<Binary>,
 }
 
 impl FileInfo {
@@ -36,13 +45,55 @@ impl FileInfo {
     /// This slice appears to have all file, but beware it is just a mapping from it and every
     /// time you access a page that is not mapped it will load from disk to memory by the OS,
     /// which also takes care of unloading it if memory constrained.
-    pub fn get_buffer(&mut self) -> &[u8] {
-        if let Some(mmap) = self.mmap.as_mut() {
-            return mmap.as_slice(0, self.size as u64).unwrap();
+    pub fn get_buffer(&self) -> &[u8] {
+        if let Some(mmap) = self.mmap.as_ref() {
+            return mmap.as_slice(0, mmap.len()).unwrap();
         }
 
         &[]
     }
+
+    /// Get the byte at `offset` with insertions and deletions applied.
+    pub fn byte_at(&self, offset: usize) -> Option<u8> {
+        self.pieces.byte_at(self.get_buffer(), offset)
+    }
+
+    /// Read up to `len` bytes from `offset` with insertions and deletions applied.
+    pub fn read(&self, offset: usize, len: usize) -> Vec<u8> {
+        self.pieces.read(self.get_buffer(), offset, len)
+    }
+
+    /// Read up to `len` bytes from `offset` like `read`, but borrowing them
+    /// from the memory mapped buffer while no bytes are inserted or deleted.
+    pub fn slice(&self, offset: usize, len: usize) -> Cow<'_, [u8]> {
+        self.pieces.slice(self.get_buffer(), offset, len)
+    }
+
+    /// Get the whole buffer with insertions and deletions applied. While no
+    /// bytes are inserted or deleted, this is the memory mapped buffer itself.
+    pub fn contents(&self) -> Cow<'_, [u8]> {
+        self.pieces.contents(self.get_buffer())
+    }
+
+    /// Insert `bytes` at `offset`. The file itself is only changed when saved.
+    pub fn insert(&mut self, offset: usize, bytes: &[u8]) {
+        self.pieces.insert(offset, bytes);
+        self.size = self.pieces.len();
+    }
+
+    /// Delete `len` bytes from `offset` and return the pieces that held
+    /// them. The file itself is only changed when saved.
+    pub fn delete(&mut self, offset: usize, len: usize) -> Vec<Piece> {
+        let removed = self.pieces.delete(offset, len);
+        self.size = self.pieces.len();
+        removed
+    }
+
+    /// Put deleted pieces back at `offset`
+    pub fn restore(&mut self, offset: usize, pieces: &[Piece]) {
+        self.pieces.restore(offset, pieces);
+        self.size = self.pieces.len();
+    }
 }
 
 #[derive(Debug)]
@@ -66,6 +117,7 @@ pub struct App {
     pub config: Config,
     pub dialog_2nd_renderer: Option<fn(&mut App, &mut Frame)>,
     pub dialog_renderer: Option<fn(&mut App, &mut Frame)>,
+    pub disasm_view: DisasmView,
     pub editor_view: AppView,
     pub file_info: FileInfo,
     pub hex_view: HexView,
@@ -90,20 +142,25 @@ impl App {
             command_area: Rect::default(),
             command_input: InputHistory::default(),
             config: Config {
+                addr_mode: AddrMode::Offset,
+                backup: false,
                 database: true,
                 dim_control_chars: false,
                 dim_zeroes: true,
+                endian: Endian::Little,
                 hex_mode_bytes_per_line: 16,
                 hex_mode_bytes_per_line_auto: false,
                 hex_mode_non_graphic_char: '.',
                 maximum_strings_to_show: 3000,
                 minimum_string_length: 4,
+                save_jumps: false,
                 search_wrap: true,
                 theme: DARK,
                 // hex_mode_dword_separator: '-',
                 // text_mode_tab_spaces: 4,
             },
             dialog_renderer: None,
+            disasm_view: DisasmView::default(),
             dialog_2nd_renderer: None,
             editor_view: AppView::Hex,
             file_info: FileInfo::default(),
@@ -133,14 +190,47 @@ impl App {
         }
     }
 
-    /// this function tries to identify a file type; this is a boilerplate implementation.
+    /// this function tries to identify a file type by parsing its headers
     fn id_file(&mut self) {
         let buffer = self.file_info.get_buffer();
-        self.file_info.r#type = match buffer[0] {
-            0x7f => "ELF",
-            0xca | 0xcf => "Mach-O",
-            0x4d => "PE",
-            _ => "",
+        let binary = formats::parse(buffer);
+        self.file_info.r#type = match &binary {
+            Some(bin) => bin.format,
+            // fat Mach-O files share this magic and were handled above
+            None if buffer.starts_with(&[0xca, 0xfe, 0xba, 0xbe]) => "Java class",
+            // headers too damaged to parse still tell the type by the magic
+            None => match buffer.first() {
+                Some(0x7f) => "ELF",
+                Some(0xca | 0xcf) => "Mach-O",
+                Some(0x4d) => "PE",
+                _ => "",
+            },
+        };
+        self.file_info.binary = binary;
+    }
+
+    /// parse another architecture of a fat binary
+    pub fn select_slice(&mut self, slice: usize) {
+        self.file_info.binary = formats::parse_slice(self.file_info.get_buffer(), slice);
+        if let Some(bin) = &self.file_info.binary {
+            self.file_info.r#type = bin.format;
+        }
+        self.load_symbols();
+    }
+
+    /// add the symbols found by the file parser to the Names list, replacing
+    /// the ones added before
+    pub fn load_symbols(&mut self) {
+        self.hex_view.comment_name_list.retain(|c| !c.symbol);
+
+        if let Some(bin) = &self.file_info.binary {
+            self.hex_view
+                .comment_name_list
+                .extend(bin.symbols.iter().map(|s| Comment {
+                    offset: s.offset,
+                    comment: s.name.clone(),
+                    symbol: true,
+                }));
         }
     }
 
@@ -151,6 +241,23 @@ impl App {
         initial_offset: usize,
         read_only: bool,
     ) -> io::Result<()> {
+        self.open_file(filepath, read_only)?;
+
+        if initial_offset != 0 {
+            self.goto(0);
+        }
+        self.goto(initial_offset);
+
+        // try to load a database for this file, but continue otherwise
+        if self.config.database {
+            let _ = self.load_database();
+        }
+        self.load_symbols();
+        Ok(())
+    }
+
+    /// open and map a file, replacing the current one if any
+    fn open_file(&mut self, filepath: &str, read_only: bool) -> io::Result<()> {
         let path = Path::new(&filepath);
 
         if let Some(f) = path.file_name()
@@ -160,17 +267,46 @@ impl App {
             self.file_info.path = String::from(filepath);
         }
 
+        let meta = path.metadata()?;
+        self.map_file(path, read_only)?;
+
+        self.file_info.size = meta.len() as usize;
+        self.file_info.pieces = PieceTable::new(self.file_info.size);
+        self.file_info.stamp = FileStamp::read(path).ok();
+        self.file_info.backed_up = false;
+
+        self.file_info.binary = None;
+        self.file_info.r#type = "";
+        if self.file_info.size > 0 {
+            self.id_file();
+        }
+        self.load_symbols();
+
+        self.log(format!(
+            "filesize: {} (0x{:x})",
+            self.file_info.size, self.file_info.size
+        ));
+        Ok(())
+    }
+
+    /// open the file, for writing if possible, and map it
+    fn map_file(&mut self, path: &Path, read_only: bool) -> io::Result<()> {
         let meta = path.metadata()?;
 
         // We try to open file readwrite to use this later for saving
+        self.file_info.file = None;
+        self.file_info.is_read_only = false;
         if !read_only && let Ok(file) = OpenOptions::new().read(true).write(true).open(path) {
             self.file_info.file = Some(file);
         } else {
             self.file_info.is_read_only = true;
         }
 
-        // We map it on memory readonly as changed to mapped memory also changes it on disk
-        if let Ok(mmap) = MemoryMappedFile::builder(path)
+        // We map it on memory readonly as changed to mapped memory also changes it on disk.
+        // Zero-length files can't be mapped, but bytes can still be inserted in them.
+        if meta.len() == 0 {
+            self.file_info.mmap = None;
+        } else if let Ok(mmap) = MemoryMappedFile::builder(path)
             .mode(MmapMode::ReadOnly)
             .open()
         {
@@ -178,168 +314,229 @@ impl App {
         } else {
             return Err(std::io::Error::other("could not open file"));
         }
+        Ok(())
+    }
 
-        self.file_info.size = meta.len() as usize;
-
-        if self.file_info.size > 0 {
-            self.id_file();
+    /// the whole buffer as it will be written, pending changes included
+    pub fn edited_contents(&self) -> Vec<u8> {
+        let mut data = self.file_info.contents().into_owned();
+        for (k, v) in &self.hex_view.changed_bytes {
+            if let Ok(b) = u8::from_str_radix(v, 16)
+                && let Some(byte) = data.get_mut(*k)
+            {
+                *byte = b;
+            }
         }
+        data
+    }
 
-        self.log(format!(
-            "filesize: {} (0x{:x})",
-            self.file_info.size, self.file_info.size
-        ));
+    /// write the pending changes over the same offsets in `f`
+    fn write_changes(&self, f: &mut File) -> io::Result<usize> {
+        let mut total_written = 0;
 
-        if initial_offset != 0 {
-            self.goto(0);
+        // changed bytes are not necessairily contiguous, so we
+        // loop through them when writing to file
+        for (k, v) in &self.hex_view.changed_bytes {
+            f.seek(SeekFrom::Start(*k as u64))?;
+            if let Ok(b) = u8::from_str_radix(v, 16) {
+                let buf = vec![b];
+                let written = f.write(&buf)?;
+                if written != 1 {
+                    return Err(io::Error::other("could not write to file"));
+                }
+                total_written += written;
+            }
         }
-        self.goto(initial_offset);
+        Ok(total_written)
+    }
 
-        // try to load a database for this file, but continue otherwise
-        if self.config.database {
-            let _ = self.load_database();
-        }
-        Ok(())
+    /// Returns true if there are bytes changed, inserted or deleted that
+    /// weren't written yet
+    pub fn has_unsaved_changes(&self) -> bool {
+        !self.hex_view.changed_bytes.is_empty() || self.file_info.pieces.is_modified()
     }
 
-    pub fn reload_file(&mut self) {
-        let fp = self.file_info.path.clone();
-        self.load_file(&fp, self.hex_view.offset, self.file_info.is_read_only)
-            .expect("could not reload the file");
+    /// Returns true if another process changed the file since we read it
+    pub fn changed_on_disk(&self) -> bool {
+        match &self.file_info.stamp {
+            Some(stamp) => FileStamp::read(Path::new(&self.file_info.path))
+                .map_or(true, |current| current != *stamp),
+            None => false,
+        }
     }
 
-    /// write what's cached to the actual file
-    pub fn write_to_file(&mut self) -> io::Result<()> {
+    /// write what's cached to the actual file. Unless `force` is set, nothing
+    /// is written if the file was changed by another process.
+    pub fn write_to_file(&mut self, force: bool) -> io::Result<()> {
+        if self.file_info.is_read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is read-only; use :saveas to write it elsewhere",
+            ));
+        }
         if self.file_info.file.is_none() {
             return Err(io::Error::other("file not open"));
         }
 
+        if !force && self.changed_on_disk() {
+            return Err(io::Error::other(
+                "file changed on disk since it was read; use :w! to write anyway",
+            ));
+        }
+
+        let path = PathBuf::from(&self.file_info.path);
+
+        if self.config.backup && !self.file_info.backed_up {
+            save::backup(&path)?;
+            self.file_info.backed_up = true;
+        }
+
         let mut total_written = 0;
 
-        if let Some(f) = &mut self.file_info.file {
-            // changed bytes are not necessairily contiguous, so we
-            // loop through them when writing to file
-            for (k, v) in &self.hex_view.changed_bytes {
-                f.seek(SeekFrom::Start(*k as u64))?;
-                if let Ok(b) = u8::from_str_radix(v, 16) {
-                    let buf = vec![b];
-                    let written = f.write(&buf)?;
-                    if written != 1 {
-                        return Err(io::Error::other("could not write to file"));
-                    }
-                    total_written += written;
-                }
+        // inserted or deleted bytes shift everything after them, so the
+        // whole buffer is written to a new file that replaces the old one
+        if self.file_info.pieces.is_modified() {
+            let data = self.edited_contents();
+
+            // an open file can't be replaced on every system
+            self.file_info.mmap = None;
+            self.file_info.file = None;
+            if let Err(e) = save::atomic_write(&path, None, |f| f.write_all(&data)) {
+                // the file is as it was, so are the offsets of the pieces
+                self.map_file(&path, false)?;
+                return Err(e);
             }
+            total_written = data.len();
+
+            App::log(self, format!("{} bytes written to file", total_written));
+            self.hex_view.changed_bytes.clear();
+            self.hex_view.history.clear();
+
+            // the file was replaced, so open it again
+            let offset = self.hex_view.offset;
+            let backed_up = self.file_info.backed_up;
+            self.open_file(&self.file_info.path.clone(), false)?;
+            self.file_info.backed_up = backed_up;
+            self.goto(offset.min(self.file_info.size.saturating_sub(1)));
+            return Ok(());
+        }
+
+        if let Some(mut f) = self.file_info.file.take() {
+            let result = self.write_changes(&mut f);
+            self.file_info.file = Some(f);
+            total_written = result?;
         }
 
         App::log(self, format!("{} bytes written to file", total_written));
         self.hex_view.changed_bytes.clear();
+        self.hex_view.history.clear();
+        self.file_info.stamp = FileStamp::read(&path).ok();
         Ok(())
     }
 
-    pub fn read_u8(&mut self, offset: usize) -> Option<u8> {
-        if offset >= self.file_info.size {
-            return None;
+    /// write the buffer, pending changes included, to another file
+    /// and continue editing that one
+    pub fn write_to_path(&mut self, filepath: &str, force: bool) -> io::Result<()> {
+        // writing to the opened file itself
+        if let Ok(target) = fs::canonicalize(filepath)
+            && let Ok(current) = fs::canonicalize(&self.file_info.path)
+            && target == current
+        {
+            return self.write_to_file(force);
         }
 
-        let buffer = self.file_info.get_buffer();
-        Some(buffer[offset])
+        let target = Path::new(filepath);
+        let total_written = if self.file_info.pieces.is_modified() {
+            let data = self.edited_contents();
+            save::atomic_write(target, None, |f| f.write_all(&data))?;
+            data.len()
+        } else {
+            // copy the original file and apply changes over it
+            let mut total_written = 0;
+            let source = PathBuf::from(&self.file_info.path);
+            save::atomic_write(target, Some(&source), |f| {
+                total_written = self.write_changes(f)?;
+                Ok(())
+            })?;
+            total_written
+        };
+
+        App::log(
+            self,
+            format!("{} bytes written to {}", total_written, filepath),
+        );
+        self.hex_view.changed_bytes.clear();
+        self.hex_view.history.clear();
+
+        // bookmarks and comments are kept, so the database follows the new file
+        let offset = self.hex_view.offset;
+        self.open_file(filepath, false)?;
+        self.goto(offset.min(self.file_info.size.saturating_sub(1)));
+        Ok(())
     }
 
-    pub fn read_i8(&mut self, offset: usize) -> Option<i8> {
+    pub fn read_u8(&mut self, offset: usize) -> Option<u8> {
         if offset >= self.file_info.size {
             return None;
         }
 
-        let buffer = self.file_info.get_buffer();
-        Some(buffer[offset] as i8)
+        self.file_info.byte_at(offset)
     }
 
-    pub fn read_u16(&mut self, offset: usize) -> Option<u16> {
-        if offset + 1 >= self.file_info.size {
-            return None;
+    /// Read a byte as shown in the hex view, that is, with its pending change applied
+    pub fn read_changed_u8(&mut self, offset: usize) -> Option<u8> {
+        if let Some(s) = self.hex_view.changed_bytes.get(&offset)
+            && let Ok(b) = u8::from_str_radix(s, 16)
+        {
+            return Some(b);
         }
 
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-
-        Some(u16::from_le_bytes([b1, b2]))
+        self.read_u8(offset)
     }
 
-    pub fn read_i16(&mut self, offset: usize) -> Option<i16> {
-        if offset + 1 >= self.file_info.size {
+    pub fn read_i8(&mut self, offset: usize) -> Option<i8> {
+        if offset >= self.file_info.size {
             return None;
         }
 
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-
-        Some(i16::from_le_bytes([b1, b2]))
+        self.file_info.byte_at(offset).map(|b| b as i8)
     }
 
-    pub fn read_u32(&mut self, offset: usize) -> Option<u32> {
-        if offset + 3 >= self.file_info.size {
+    // `N` bytes at `offset`, in the byte order set by the user, so that
+    // they can be read with `from_le_bytes`
+    fn read_ordered<const N: usize>(&mut self, offset: usize) -> Option<[u8; N]> {
+        if offset + N > self.file_info.size {
             return None;
         }
 
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-        let b3 = buffer[offset + 2];
-        let b4 = buffer[offset + 3];
+        let mut bytes: [u8; N] = self.file_info.read(offset, N).try_into().ok()?;
+        if self.config.endian == Endian::Big {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
 
-        Some(u32::from_le_bytes([b1, b2, b3, b4]))
+    pub fn read_u16(&mut self, offset: usize) -> Option<u16> {
+        self.read_ordered(offset).map(u16::from_le_bytes)
     }
 
-    pub fn read_i32(&mut self, offset: usize) -> Option<i32> {
-        if offset + 3 >= self.file_info.size {
-            return None;
-        }
+    pub fn read_i16(&mut self, offset: usize) -> Option<i16> {
+        self.read_ordered(offset).map(i16::from_le_bytes)
+    }
 
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-        let b3 = buffer[offset + 2];
-        let b4 = buffer[offset + 3];
+    pub fn read_u32(&mut self, offset: usize) -> Option<u32> {
+        self.read_ordered(offset).map(u32::from_le_bytes)
+    }
 
-        Some(i32::from_le_bytes([b1, b2, b3, b4]))
+    pub fn read_i32(&mut self, offset: usize) -> Option<i32> {
+        self.read_ordered(offset).map(i32::from_le_bytes)
     }
 
     pub fn read_u64(&mut self, offset: usize) -> Option<u64> {
-        if offset + 7 >= self.file_info.size {
-            return None;
-        }
-
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-        let b3 = buffer[offset + 2];
-        let b4 = buffer[offset + 3];
-        let b5 = buffer[offset + 4];
-        let b6 = buffer[offset + 5];
-        let b7 = buffer[offset + 6];
-        let b8 = buffer[offset + 7];
-
-        Some(u64::from_le_bytes([b1, b2, b3, b4, b5, b6, b7, b8]))
+        self.read_ordered(offset).map(u64::from_le_bytes)
     }
 
     pub fn read_i64(&mut self, offset: usize) -> Option<i64> {
-        if offset + 7 >= self.file_info.size {
-            return None;
-        }
-
-        let buffer = self.file_info.get_buffer();
-        let b1 = buffer[offset];
-        let b2 = buffer[offset + 1];
-        let b3 = buffer[offset + 2];
-        let b4 = buffer[offset + 3];
-        let b5 = buffer[offset + 4];
-        let b6 = buffer[offset + 5];
-        let b7 = buffer[offset + 6];
-        let b8 = buffer[offset + 7];
-
-        Some(i64::from_le_bytes([b1, b2, b3, b4, b5, b6, b7, b8]))
+        self.read_ordered(offset).map(i64::from_le_bytes)
     }
 }
@@ -1,4 +1,9 @@
-use crate::{app::App, util::parse_offset, widgets::MessageType};
+use crate::{
+    app::App,
+    config::{AddrMode, Endian},
+    util::parse_offset,
+    widgets::MessageType,
+};
 use ratatui::{
     Frame,
     widgets::{Clear, Paragraph},
@@ -7,9 +12,11 @@ use ratatui::{
 use crate::{editor::UIState, widgets::Message};
 
 use crate::app::Dz6Error;
+use crate::disasm::Arch;
+use crate::patch::{self, PatchFormat};
 use clap::{Parser, Subcommand};
 use ratatui::crossterm::event::{Event, KeyCode};
-use std::io::Result;
+use std::io::{self, Result};
 use tui_input::backend::crossterm::EventHandler;
 
 pub struct Commands;
@@ -17,9 +24,39 @@ pub struct Commands;
 #[derive(Subcommand, Debug)]
 enum Command {
     Q,
-    W,
-    Wq,
+    W {
+        path: Option<String>,
+    },
+    #[command(name = "w!")]
+    WForce {
+        path: Option<String>,
+    },
+    Wq {
+        path: Option<String>,
+    },
+    #[command(name = "wq!")]
+    WqForce {
+        path: Option<String>,
+    },
     X,
+    Saveas {
+        path: String,
+    },
+    ExportPatch {
+        format: String,
+        path: String,
+    },
+    ApplyPatch {
+        path: String,
+    },
+    Va {
+        addr: String,
+    },
+    #[command(alias = "nohlsearch")]
+    Noh,
+    Rva {
+        addr: String,
+    },
     Cmt {
         offset: String,
         comment: String,
@@ -65,11 +102,15 @@ fn try_goto(app: &mut App, offset: &str) {
             ofs = app.hex_view.offset.saturating_add(ofs);
         } else if offset_direction == OffsetType::Backward {
             ofs = app.hex_view.offset.saturating_sub(ofs);
+        } else if app.addr_mode() != AddrMode::Offset {
+            // absolute values are addresses, as shown in the offsets column
+            goto_addr(app, new_offset, app.addr_mode());
+            return;
         }
         if ofs < app.file_info.size {
             app.dialog_renderer = None;
             app.state = UIState::Normal;
-            app.goto(ofs);
+            app.jump(ofs);
         } else {
             app.last_error = Dz6Error {
                 message: format!(
@@ -88,6 +129,51 @@ fn try_goto(app: &mut App, offset: &str) {
     }
 }
 
+// Go to a VA or an RVA
+fn goto_addr(app: &mut App, addr: &str, mode: AddrMode) {
+    let result = parse_offset(addr)
+        .map_err(|_| format!("Invalid address: {}", addr))
+        .and_then(|addr| app.addr_to_offset(addr as u64, mode));
+
+    match result {
+        Ok(ofs) => {
+            app.dialog_renderer = None;
+            app.jump(ofs);
+        }
+        Err(message) => {
+            app.last_error = Dz6Error { message };
+            app.dialog_renderer = Some(command_error_draw);
+        }
+    }
+    app.state = UIState::Normal;
+}
+
+// Write to the opened file or, if `path` is set, to another one.
+// Returns false if the file could not be written.
+fn write(app: &mut App, path: Option<&str>, force: bool) -> bool {
+    let result = match path {
+        Some(path) => app.write_to_path(path, force),
+        None => app.write_to_file(force),
+    };
+
+    if let Err(e) = result {
+        app.last_error = Dz6Error {
+            message: match path {
+                Some(path) => format!("{}: {}", path, e),
+                None => e.to_string(),
+            },
+        };
+        app.dialog_renderer = Some(command_error_draw);
+        return false;
+    }
+
+    if app.config.database {
+        let _ = app.save_database();
+    }
+    app.dialog_renderer = None;
+    true
+}
+
 pub fn parse_command(app: &mut App, cmdline: &str) {
     if cmdline.is_empty() {
         app.state = UIState::Normal;
@@ -107,24 +193,81 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
         Ok(cli) => match cli.command {
             // quit
             Some(Command::Q) => app.running = false,
-            // write to file
-            Some(Command::W) => {
-                let _ = app.write_to_file();
-                if app.config.database {
-                    let _ = app.save_database();
-                }
-                app.dialog_renderer = None;
+            // write to file, or to another file and continue editing it
+            Some(Command::W { path }) => {
+                write(app, path.as_deref(), false);
+                app.state = UIState::Normal;
+            }
+            Some(Command::WForce { path }) => {
+                write(app, path.as_deref(), true);
+                app.state = UIState::Normal;
+            }
+            Some(Command::Saveas { path }) => {
+                write(app, Some(&path), false);
                 app.state = UIState::Normal;
             }
             // write and quit
-            Some(Command::Wq) | Some(Command::X) => {
-                let _ = app.write_to_file();
+            Some(Command::Wq { path }) => {
+                app.running = !write(app, path.as_deref(), false);
+                app.state = UIState::Normal;
+            }
+            Some(Command::WqForce { path }) => {
+                app.running = !write(app, path.as_deref(), true);
+                app.state = UIState::Normal;
+            }
+            // like :wq, but only writing if there's something to write
+            Some(Command::X) if app.has_unsaved_changes() => {
+                app.running = !write(app, None, false);
+                app.state = UIState::Normal;
+            }
+            Some(Command::X) => {
                 if app.config.database {
                     let _ = app.save_database();
                 }
-                app.dialog_renderer = None;
                 app.running = false;
             }
+            // export-patch <ips|bps|text> <path>
+            Some(Command::ExportPatch { format, path }) => {
+                let result = match PatchFormat::from_name(&format) {
+                    Some(format) => patch::export(app, format, &path),
+                    None => Err(io::Error::other(format!(
+                        "Invalid patch format: {}; use ips, bps or text",
+                        format
+                    ))),
+                };
+                match result {
+                    Ok(_) => {
+                        App::log(app, format!("patch written to {}", path));
+                        app.dialog_renderer = None;
+                    }
+                    Err(e) => {
+                        app.last_error = Dz6Error {
+                            message: format!("{}: {}", path, e),
+                        };
+                        app.dialog_renderer = Some(command_error_draw);
+                    }
+                }
+                app.state = UIState::Normal;
+            }
+            // apply-patch <path>
+            Some(Command::ApplyPatch { path }) => {
+                match patch::apply(app, &path) {
+                    Ok(changed) => {
+                        App::log(app, format!("{} bytes changed by {}", changed, path));
+                        app.dialog_renderer = None;
+                    }
+                    Err(e) => {
+                        app.last_error = Dz6Error {
+                            message: format!("{}: {}", path, e),
+                        };
+                        app.dialog_renderer = Some(command_error_draw);
+                    }
+                }
+                app.state = UIState::Normal;
+            }
+            // va <address> and rva <address>
+            Some(Command::Va { addr }) => goto_addr(app, &addr, AddrMode::Va),
+            Some(Command::Rva { addr }) => goto_addr(app, &addr, AddrMode::Rva),
             // comment <offset> <comment>
             Some(Command::Cmt { offset, comment }) => {
                 if let Ok(mut ofs) = parse_offset(&offset) {
@@ -159,10 +302,11 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
                     "byteline" => {
                         if let Some(val) = value {
                             if let Ok(bpl) = val.parse::<usize>() {
-                                // Bound user typed value by `max` - 1
+                                // Bound user typed value by what fits on screen
                                 if app.screen.width > 0 {
-                                    let max = ((app.screen.width - 9) / 4) as usize;
-                                    app.config.hex_mode_bytes_per_line = bpl.min(max - 1);
+                                    let max =
+                                        app.hex_view.inspector.bytes_per_line(app.screen.width);
+                                    app.config.hex_mode_bytes_per_line = bpl.min(max);
                                 } else {
                                     app.config.hex_mode_bytes_per_line = bpl.min(64);
                                 }
@@ -172,8 +316,8 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
                             {
                                 app.config.hex_mode_bytes_per_line_auto = true;
                                 if app.screen.width > 0 {
-                                    let max = ((app.screen.width - 9) / 4) as usize;
-                                    app.config.hex_mode_bytes_per_line = max - 1;
+                                    app.config.hex_mode_bytes_per_line =
+                                        app.hex_view.inspector.bytes_per_line(app.screen.width);
                                 }
                             }
                         }
@@ -194,10 +338,28 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
                         app.config.database = true;
                         app.dialog_renderer = None;
                     }
+                    // copy the file to <filename>.bak before writing to it
+                    "backup" => {
+                        app.config.backup = true;
+                        app.dialog_renderer = None;
+                    }
+                    "nobackup" => {
+                        app.config.backup = false;
+                        app.dialog_renderer = None;
+                    }
                     "nodb" => {
                         app.config.database = false;
                         app.dialog_renderer = None;
                     }
+                    // keep the jump list in the database too
+                    "jumpdb" => {
+                        app.config.save_jumps = true;
+                        app.dialog_renderer = None;
+                    }
+                    "nojumpdb" => {
+                        app.config.save_jumps = false;
+                        app.dialog_renderer = None;
+                    }
                     // dim (gray out) control bytes
                     "dimctrl" => {
                         app.config.dim_control_chars = true;
@@ -235,6 +397,58 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
                             }
                         }
                     }
+                    // architecture of the disassembly view
+                    "arch" => match value.as_deref().and_then(Arch::from_name) {
+                        Some(arch) => {
+                            app.disasm_view.arch = Some(arch);
+                            app.dialog_renderer = None;
+                        }
+                        None => {
+                            app.last_error = Dz6Error {
+                                message: "Invalid architecture; use x86-16, x86, x86-64 or arm64"
+                                    .to_string(),
+                            };
+                            app.dialog_renderer = Some(command_error_draw);
+                        }
+                    },
+                    // what the offsets column shows
+                    "addr" => match value.as_deref() {
+                        Some("offset") => {
+                            app.config.addr_mode = AddrMode::Offset;
+                            app.dialog_renderer = None;
+                        }
+                        Some("va") => {
+                            app.config.addr_mode = AddrMode::Va;
+                            app.dialog_renderer = None;
+                        }
+                        Some("rva") => {
+                            app.config.addr_mode = AddrMode::Rva;
+                            app.dialog_renderer = None;
+                        }
+                        _ => {
+                            app.last_error = Dz6Error {
+                                message: "Invalid address mode; use offset, va or rva".to_string(),
+                            };
+                            app.dialog_renderer = Some(command_error_draw);
+                        }
+                    },
+                    // byte order of the values read from the file
+                    "endian" => match value.as_deref() {
+                        Some("little" | "le") => {
+                            app.config.endian = Endian::Little;
+                            app.dialog_renderer = None;
+                        }
+                        Some("big" | "be") => {
+                            app.config.endian = Endian::Big;
+                            app.dialog_renderer = None;
+                        }
+                        _ => {
+                            app.last_error = Dz6Error {
+                                message: "Invalid endianness; use little or big".to_string(),
+                            };
+                            app.dialog_renderer = Some(command_error_draw);
+                        }
+                    },
                     // saarch wrap
                     "wrapscan" => {
                         app.config.search_wrap = true;
@@ -250,6 +464,11 @@ pub fn parse_command(app: &mut App, cmdline: &str) {
                 }
                 app.state = UIState::Normal;
             }
+            // stop highlighting the matches of find all
+            Some(Command::Noh) => {
+                app.hex_view.find_all.clear();
+                app.state = UIState::Normal;
+            }
             Some(Command::Sel { start, length }) => {
                 app.state = UIState::HexSelection;
                 app.dialog_renderer = None;
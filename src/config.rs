@@ -3,15 +3,42 @@ use crate::themes::*;
 // command input history size
 pub const CMD_INPUT_HIST_SIZE: usize = 50;
 
+// jump list size
+pub const JUMP_LIST_SIZE: usize = 100;
+
+// most matches listed by find all, and how much of the file is searched for
+// them between key presses
+pub const FIND_ALL_LIMIT: usize = 10_000;
+pub const FIND_ALL_BLOCK: usize = 4 * 1024 * 1024;
+
+// what the offsets column and the status bar show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddrMode {
+    Offset,
+    Va,
+    Rva,
+}
+
+// byte order of the values read from the file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
 pub struct Config {
+    pub addr_mode: AddrMode,
+    pub backup: bool,
     pub database: bool,
     pub dim_control_chars: bool,
     pub dim_zeroes: bool,
+    pub endian: Endian,
     pub hex_mode_bytes_per_line: usize,
     pub hex_mode_bytes_per_line_auto: bool,
     pub hex_mode_non_graphic_char: char,
     pub maximum_strings_to_show: usize,
     pub minimum_string_length: usize,
+    pub save_jumps: bool,
     pub search_wrap: bool,
     pub theme: Theme,
     // pub hex_mode_dword_separator: char,
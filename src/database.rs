@@ -3,10 +3,15 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::app::App;
+use crate::hex::hex_view::HexView;
 
 impl App {
     pub fn save_database(&self) -> Result<(), Box<dyn Error>> {
-        let toml_string = toml::to_string_pretty(&self.hex_view)?;
+        let mut table = toml::Table::try_from(&self.hex_view)?;
+        if !self.config.save_jumps {
+            table.remove("jumps");
+        }
+        let toml_string = toml::to_string_pretty(&table)?;
         let target_dir: &Path = Path::new(&self.file_info.path)
             .parent()
             .unwrap_or(Path::new("."));
@@ -14,7 +19,11 @@ impl App {
         let target_db: PathBuf = target_dir.join(&cwd_db);
 
         // if there's nothing to be saved, delete any existing db files and return
-        if self.hex_view.bookmarks.is_empty() && self.hex_view.comment_name_list.is_empty() {
+        if self.hex_view.bookmarks.is_empty()
+            && self.hex_view.comment_name_list.iter().all(|c| c.symbol)
+            && self.hex_view.marks.is_empty()
+            && (!self.config.save_jumps || self.hex_view.jumps.entries.is_empty())
+        {
             let _ = fs::remove_file(target_db);
             let _ = fs::remove_file(cwd_db);
             return Ok(());
@@ -33,10 +42,13 @@ impl App {
         let target_db: PathBuf = target_dir.join(&cwd_db);
         let data = fs::read_to_string(&cwd_db).or_else(|_| fs::read_to_string(&target_db))?;
 
-        // TODO: Although we're only interested in comments and bookmarks, other fields might
-        // be loaded if they are defined in the TOML file. How to prevent that?
-        self.hex_view = toml::from_str(&data)?;
-        self.hex_view.editing_hex = true; // otherwise it defaults to false if a .dz6 file exists for the target
+        // only the saved fields are taken, so the rest of the view is kept
+        let saved: HexView = toml::from_str(&data)?;
+        self.hex_view.bookmarks = saved.bookmarks;
+        self.hex_view.comment_name_list = saved.comment_name_list;
+        self.hex_view.comments = saved.comments;
+        self.hex_view.jumps = saved.jumps;
+        self.hex_view.marks = saved.marks;
         Ok(())
     }
 }
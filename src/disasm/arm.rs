@@ -0,0 +1,524 @@
+// A small decoder for 32-bit ARM code, in both the ARM (A32) and Thumb
+// instruction sets. It covers the same ground as the AArch64 one: branches,
+// moves, arithmetic, logical operations, loads, stores, push and pop.
+// Anything else is shown as a `.word`, or a `.short` for 16-bit Thumb.
+
+use super::arm64::{CONDITIONS, SHIFTS, bits, imm, signed, target};
+
+const DATA_OPS: [&str; 16] = [
+    "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc", "tst", "teq", "cmp", "cmn", "orr",
+    "mov", "bic", "mvn",
+];
+const THUMB_OPS: [&str; 16] = [
+    "ands", "eors", "lsls", "lsrs", "asrs", "adcs", "sbcs", "rors", "tst", "rsbs", "cmp", "cmn",
+    "orrs", "muls", "bics", "mvns",
+];
+const HINTS: [&str; 5] = ["nop", "yield", "wfe", "wfi", "sev"];
+
+fn reg(n: u32) -> String {
+    match n {
+        13 => "sp".to_string(),
+        14 => "lr".to_string(),
+        15 => "pc".to_string(),
+        n => format!("r{}", n),
+    }
+}
+
+// `{r4, r5, lr}` from a mask with one bit per register
+fn reg_list(mask: u32) -> String {
+    let regs: Vec<String> = (0..16).filter(|n| mask >> n & 1 == 1).map(reg).collect();
+    format!("{{{}}}", regs.join(", "))
+}
+
+// condition suffix of an ARM instruction, empty for "always"
+fn cond(c: u32) -> &'static str {
+    if c == 14 { "" } else { CONDITIONS[c as usize] }
+}
+
+// register `rm` shifted by an immediate amount
+fn shifted(rm: u32, kind: u32, amount: u32) -> String {
+    match (kind, amount) {
+        (0, 0) => reg(rm),
+        (3, 0) => format!("{}, rrx", reg(rm)),
+        // lsr and asr encode a shift by 32 as 0
+        (_, 0) => format!("{}, {} #32", reg(rm), SHIFTS[kind as usize]),
+        _ => format!("{}, {} #{}", reg(rm), SHIFTS[kind as usize], amount),
+    }
+}
+
+// memory operand with base `rn`; `offset` is left out when empty
+fn address(rn: u32, offset: &str, pre: bool, writeback: bool) -> String {
+    let wb = if writeback { "!" } else { "" };
+    match (pre, offset.is_empty()) {
+        (true, true) => format!("[{}]{}", reg(rn), wb),
+        (true, false) => format!("[{}, {}]{}", reg(rn), offset, wb),
+        (false, _) => format!("[{}], {}", reg(rn), offset),
+    }
+}
+
+// immediate offset, left out when it's 0 and applied before the access
+fn offset(value: i64, pre: bool) -> String {
+    if value == 0 && pre {
+        String::new()
+    } else {
+        imm(value)
+    }
+}
+
+fn word(w: u32) -> (String, Option<u64>) {
+    (format!(".word 0x{:08X}", w), None)
+}
+
+/// Decode the ARM instruction `w` found at address `pc`. Returns its text
+/// and, for branches and PC-relative loads, the address it refers to.
+pub fn decode(w: u32, pc: u64) -> (String, Option<u64>) {
+    let c = cond(bits(w, 28, 4));
+    let rn = bits(w, 16, 4);
+    let rd = bits(w, 12, 4);
+    let rm = bits(w, 0, 4);
+    let (pre, up, writeback, load) = (
+        bits(w, 24, 1) == 1,
+        bits(w, 23, 1) == 1,
+        bits(w, 21, 1) == 1,
+        bits(w, 20, 1) == 1,
+    );
+    // the PC reads two instructions ahead
+    let pc = pc.wrapping_add(8);
+
+    let text = match w {
+        // blx (immediate), the only unconditional instruction decoded here
+        _ if w & 0xfe000000 == 0xfa000000 => {
+            let t = target(
+                pc,
+                signed(bits(w, 0, 24), 24) * 4 + bits(w, 24, 1) as i64 * 2,
+            );
+            return (format!("blx 0x{:X}", t), Some(t));
+        }
+        _ if bits(w, 28, 4) == 15 => return word(w),
+        _ if w & 0x0fffffff == 0x0320f000 => format!("nop{}", c),
+        _ if w & 0x0ffffff0 == 0x012fff10 => format!("bx{} {}", c, reg(rm)),
+        _ if w & 0x0ffffff0 == 0x012fff30 => format!("blx{} {}", c, reg(rm)),
+        _ if w & 0xfff000f0 == 0xe1200070 => {
+            format!("bkpt {}", imm((bits(w, 8, 12) << 4 | rm) as i64))
+        }
+        // mul, mla: the destination is in bits 16-19 and the accumulator in
+        // bits 12-15
+        _ if w & 0x0fc000f0 == 0x00000090 => {
+            let s = if load { "s" } else { "" };
+            let (rs, ra) = (reg(bits(w, 8, 4)), reg(rd));
+            if writeback {
+                format!("mla{}{} {}, {}, {}, {}", s, c, reg(rn), reg(rm), rs, ra)
+            } else {
+                format!("mul{}{} {}, {}, {}", s, c, reg(rn), reg(rm), rs)
+            }
+        }
+        // ldrh, strh, ldrsb, ldrsh
+        _ if w & 0x0e000090 == 0x00000090 && bits(w, 5, 2) != 0 => {
+            let name = match (bits(w, 5, 2), load) {
+                (1, false) => "strh",
+                (1, true) => "ldrh",
+                (2, true) => "ldrsb",
+                (3, true) => "ldrsh",
+                _ => return word(w),
+            };
+            let ofs = if bits(w, 22, 1) == 1 {
+                let value = (bits(w, 8, 4) << 4 | rm) as i64;
+                offset(if up { value } else { -value }, pre)
+            } else {
+                format!("{}{}", if up { "" } else { "-" }, reg(rm))
+            };
+            let address = address(rn, &ofs, pre, writeback);
+            format!("{}{} {}, {}", name, c, reg(rd), address)
+        }
+        // movw, movt
+        _ if w & 0x0fb00000 == 0x03000000 => {
+            let name = if bits(w, 22, 1) == 1 { "movt" } else { "movw" };
+            let value = (rn << 12 | bits(w, 0, 12)) as i64;
+            format!("{}{} {}, {}", name, c, reg(rd), imm(value))
+        }
+        // data processing, with an immediate or a shifted register
+        _ if w & 0x0c000000 == 0 && (bits(w, 25, 1) == 1 || w & 0x90 != 0x90) => {
+            let op = bits(w, 21, 4) as usize;
+            let value = bits(w, 0, 8).rotate_right(bits(w, 8, 4) * 2) as i64;
+            let operand = if bits(w, 25, 1) == 1 {
+                imm(value)
+            } else if bits(w, 4, 1) == 1 {
+                let rs = reg(bits(w, 8, 4));
+                format!("{}, {} {}", reg(rm), SHIFTS[bits(w, 5, 2) as usize], rs)
+            } else {
+                shifted(rm, bits(w, 5, 2), bits(w, 7, 5))
+            };
+            let s = if load { "s" } else { "" };
+            match op {
+                // without S, these encodings are other instructions
+                8..=11 if !load => return word(w),
+                8..=11 => format!("{}{} {}, {}", DATA_OPS[op], c, reg(rn), operand),
+                13 | 15 => format!("{}{}{} {}, {}", DATA_OPS[op], s, c, reg(rd), operand),
+                // an address computed from the PC
+                2 | 4 if rn == 15 && bits(w, 25, 1) == 1 && !load => {
+                    let t = target(pc, if op == 2 { -value } else { value });
+                    return (format!("adr{} {}, 0x{:X}", c, reg(rd), t), Some(t));
+                }
+                _ => {
+                    let (rd, rn) = (reg(rd), reg(rn));
+                    format!("{}{}{} {}, {}, {}", DATA_OPS[op], s, c, rd, rn, operand)
+                }
+            }
+        }
+        // ldr, str, ldrb, strb
+        _ if w & 0x0c000000 == 0x04000000 && w & 0x02000010 != 0x02000010 => {
+            let name = ["str", "ldr", "strb", "ldrb"]
+                [bits(w, 20, 1) as usize + bits(w, 22, 1) as usize * 2];
+            let ofs = if bits(w, 25, 1) == 0 {
+                let value = bits(w, 0, 12) as i64;
+                let value = if up { value } else { -value };
+                if rn == 15 && pre && !writeback {
+                    let t = target(pc, value);
+                    return (format!("{}{} {}, 0x{:X}", name, c, reg(rd), t), Some(t));
+                }
+                offset(value, pre)
+            } else {
+                let sign = if up { "" } else { "-" };
+                format!("{}{}", sign, shifted(rm, bits(w, 5, 2), bits(w, 7, 5)))
+            };
+            let address = address(rn, &ofs, pre, writeback);
+            format!("{}{} {}, {}", name, c, reg(rd), address)
+        }
+        // ldm, stm, push, pop
+        _ if w & 0x0e000000 == 0x08000000 => {
+            let list = reg_list(bits(w, 0, 16));
+            let mode = bits(w, 23, 2);
+            match (load, mode, rn, writeback) {
+                (true, 1, 13, true) => format!("pop{} {}", c, list),
+                (false, 2, 13, true) => format!("push{} {}", c, list),
+                _ => {
+                    let name = if load { "ldm" } else { "stm" };
+                    let mode = ["da", "", "db", "ib"][mode as usize];
+                    let wb = if writeback { "!" } else { "" };
+                    format!("{}{}{} {}{}, {}", name, mode, c, reg(rn), wb, list)
+                }
+            }
+        }
+        // b, bl
+        _ if w & 0x0e000000 == 0x0a000000 => {
+            let t = target(pc, signed(bits(w, 0, 24), 24) * 4);
+            let name = if pre { "bl" } else { "b" };
+            return (format!("{}{} 0x{:X}", name, c, t), Some(t));
+        }
+        _ if w & 0x0f000000 == 0x0f000000 => format!("svc{} {}", c, imm(bits(w, 0, 24) as i64)),
+        _ => return word(w),
+    };
+    (text, None)
+}
+
+/// Whether the Thumb halfword `hw` starts a 32-bit instruction
+pub fn is_thumb32(hw: u16) -> bool {
+    hw >> 11 >= 0x1d
+}
+
+/// Decode the 16-bit Thumb instruction `hw` found at address `pc`
+pub fn decode_thumb(hw: u16, pc: u64) -> (String, Option<u64>) {
+    let w = hw as u32;
+    let (rd, rn, rm) = (bits(w, 0, 3), bits(w, 3, 3), bits(w, 6, 3));
+    // the PC reads 4 bytes ahead, rounded down to a word for loads
+    let pc = pc.wrapping_add(4);
+    let literal = pc & !3;
+
+    let text = match w {
+        _ if w & 0xff0f == 0xbf00 => match bits(w, 4, 4) as usize {
+            n if n < HINTS.len() => HINTS[n].to_string(),
+            _ => format!(".short 0x{:04X}", w),
+        },
+        // it, itt, ite...: a t or an e for each instruction after the first
+        _ if w & 0xff00 == 0xbf00 => {
+            let (first, mask) = (bits(w, 4, 4), bits(w, 0, 4));
+            let suffix: String = (mask.trailing_zeros() + 1..4)
+                .rev()
+                .map(|i| if mask >> i & 1 == first & 1 { 't' } else { 'e' })
+                .collect();
+            format!("it{} {}", suffix, CONDITIONS[first as usize])
+        }
+        // lsls, lsrs, asrs (immediate)
+        _ if w >> 13 == 0 && bits(w, 11, 2) != 3 => match (bits(w, 11, 2), bits(w, 6, 5)) {
+            (0, 0) => format!("movs {}, {}", reg(rd), reg(rn)),
+            (kind, amount) => {
+                let amount = if amount == 0 { 32 } else { amount };
+                let name = SHIFTS[kind as usize];
+                format!("{}s {}, {}, #{}", name, reg(rd), reg(rn), amount)
+            }
+        },
+        // adds, subs with three registers or a 3-bit immediate
+        _ if w >> 11 == 3 => {
+            let name = if bits(w, 9, 1) == 1 { "subs" } else { "adds" };
+            let operand = if bits(w, 10, 1) == 1 {
+                imm(rm as i64)
+            } else {
+                reg(rm)
+            };
+            format!("{} {}, {}, {}", name, reg(rd), reg(rn), operand)
+        }
+        // movs, cmp, adds, subs with an 8-bit immediate
+        _ if w >> 13 == 1 => {
+            let name = ["movs", "cmp", "adds", "subs"][bits(w, 11, 2) as usize];
+            format!(
+                "{} {}, {}",
+                name,
+                reg(bits(w, 8, 3)),
+                imm(bits(w, 0, 8) as i64)
+            )
+        }
+        // data processing between low registers
+        _ if w >> 10 == 0x10 => match bits(w, 6, 4) {
+            9 => format!("rsbs {}, {}, #0", reg(rd), reg(rn)),
+            13 => format!("muls {}, {}, {}", reg(rd), reg(rn), reg(rd)),
+            op => format!("{} {}, {}", THUMB_OPS[op as usize], reg(rd), reg(rn)),
+        },
+        // add, cmp, mov with high registers; bx, blx
+        _ if w >> 10 == 0x11 => {
+            let (rd, rm) = (reg(bits(w, 7, 1) << 3 | rd), reg(bits(w, 3, 4)));
+            match bits(w, 8, 2) {
+                0 => format!("add {}, {}", rd, rm),
+                1 => format!("cmp {}, {}", rd, rm),
+                2 => format!("mov {}, {}", rd, rm),
+                _ if bits(w, 7, 1) == 1 => format!("blx {}", rm),
+                _ => format!("bx {}", rm),
+            }
+        }
+        // ldr (literal)
+        _ if w >> 11 == 9 => {
+            let t = target(literal, bits(w, 0, 8) as i64 * 4);
+            return (format!("ldr {}, 0x{:X}", reg(bits(w, 8, 3)), t), Some(t));
+        }
+        // loads and stores with a register offset
+        _ if w >> 12 == 5 => {
+            let names = [
+                "str", "strh", "strb", "ldrsb", "ldr", "ldrh", "ldrb", "ldrsh",
+            ];
+            let name = names[bits(w, 9, 3) as usize];
+            format!("{} {}, [{}, {}]", name, reg(rd), reg(rn), reg(rm))
+        }
+        // loads and stores with an immediate offset
+        _ if w >> 13 == 3 || w >> 12 == 8 => {
+            let forms = [
+                ("str", 4),
+                ("ldr", 4),
+                ("strb", 1),
+                ("ldrb", 1),
+                ("strh", 2),
+                ("ldrh", 2),
+            ];
+            let (name, scale) = forms[(w >> 11) as usize - 12];
+            let ofs = offset(bits(w, 6, 5) as i64 * scale, true);
+            format!("{} {}, {}", name, reg(rd), address(rn, &ofs, true, false))
+        }
+        // loads and stores relative to sp
+        _ if w >> 12 == 9 => {
+            let name = if bits(w, 11, 1) == 1 { "ldr" } else { "str" };
+            let ofs = offset(bits(w, 0, 8) as i64 * 4, true);
+            format!(
+                "{} {}, {}",
+                name,
+                reg(bits(w, 8, 3)),
+                address(13, &ofs, true, false)
+            )
+        }
+        // adr, add to sp
+        _ if w >> 12 == 10 => {
+            let (rd, value) = (reg(bits(w, 8, 3)), bits(w, 0, 8) as i64 * 4);
+            if bits(w, 11, 1) == 1 {
+                format!("add {}, sp, {}", rd, imm(value))
+            } else {
+                let t = target(literal, value);
+                return (format!("adr {}, 0x{:X}", rd, t), Some(t));
+            }
+        }
+        _ if w & 0xff00 == 0xb000 => {
+            let name = if bits(w, 7, 1) == 1 { "sub" } else { "add" };
+            format!("{} sp, sp, {}", name, imm(bits(w, 0, 7) as i64 * 4))
+        }
+        // cbz, cbnz
+        _ if w & 0xf500 == 0xb100 => {
+            let t = target(pc, (bits(w, 9, 1) << 6 | bits(w, 3, 5) << 1) as i64);
+            let name = if bits(w, 11, 1) == 1 { "cbnz" } else { "cbz" };
+            return (format!("{} {}, 0x{:X}", name, reg(rd), t), Some(t));
+        }
+        _ if w & 0xff00 == 0xb200 => {
+            let name = ["sxth", "sxtb", "uxth", "uxtb"][bits(w, 6, 2) as usize];
+            format!("{} {}, {}", name, reg(rd), reg(rn))
+        }
+        _ if w & 0xfe00 == 0xb400 => {
+            format!("push {}", reg_list(bits(w, 0, 8) | bits(w, 8, 1) << 14))
+        }
+        _ if w & 0xfe00 == 0xbc00 => {
+            format!("pop {}", reg_list(bits(w, 0, 8) | bits(w, 8, 1) << 15))
+        }
+        _ if w & 0xff00 == 0xba00 && bits(w, 6, 2) != 2 => {
+            let name = ["rev", "rev16", "", "revsh"][bits(w, 6, 2) as usize];
+            format!("{} {}, {}", name, reg(rd), reg(rn))
+        }
+        _ if w & 0xff00 == 0xbe00 => format!("bkpt {}", imm(bits(w, 0, 8) as i64)),
+        // stm, ldm; ldm doesn't write the base back when it loads it
+        _ if w >> 12 == 12 => {
+            let (rn, list) = (bits(w, 8, 3), bits(w, 0, 8));
+            if bits(w, 11, 1) == 0 {
+                format!("stm {}!, {}", reg(rn), reg_list(list))
+            } else if list >> rn & 1 == 1 {
+                format!("ldm {}, {}", reg(rn), reg_list(list))
+            } else {
+                format!("ldm {}!, {}", reg(rn), reg_list(list))
+            }
+        }
+        _ if w >> 8 == 0xde => format!("udf {}", imm(bits(w, 0, 8) as i64)),
+        _ if w >> 8 == 0xdf => format!("svc {}", imm(bits(w, 0, 8) as i64)),
+        // b<cond>
+        _ if w >> 12 == 13 => {
+            let t = target(pc, signed(bits(w, 0, 8), 8) * 2);
+            let cond = CONDITIONS[bits(w, 8, 4) as usize];
+            return (format!("b{} 0x{:X}", cond, t), Some(t));
+        }
+        _ if w >> 11 == 0x1c => {
+            let t = target(pc, signed(bits(w, 0, 11), 11) * 2);
+            return (format!("b 0x{:X}", t), Some(t));
+        }
+        _ => format!(".short 0x{:04X}", w),
+    };
+    (text, None)
+}
+
+/// Decode the 32-bit Thumb instruction `w`, whose first halfword is in the
+/// high bits, found at address `pc`
+pub fn decode_thumb32(w: u32, pc: u64) -> (String, Option<u64>) {
+    let rt = reg(bits(w, 12, 4));
+    let s = bits(w, 26, 1);
+    let (j1, j2) = (bits(w, 13, 1), bits(w, 11, 1));
+    let pc = pc.wrapping_add(4);
+
+    let text = match w {
+        // bl, blx, b.w: the top bits of the offset are mixed with the sign
+        _ if w & 0xf8008000 == 0xf0008000 && w & 0x5000 != 0 => {
+            let (i1, i2) = (!(j1 ^ s) & 1, !(j2 ^ s) & 1);
+            let value = s << 24 | i1 << 23 | i2 << 22 | bits(w, 16, 10) << 12 | bits(w, 0, 11) << 1;
+            let delta = signed(value, 25);
+            let (name, t) = match (bits(w, 14, 1), bits(w, 12, 1)) {
+                (1, 1) => ("bl", target(pc, delta)),
+                (1, _) => ("blx", target(pc & !3, delta)),
+                _ => ("b.w", target(pc, delta)),
+            };
+            return (format!("{} 0x{:X}", name, t), Some(t));
+        }
+        // b<cond>.w
+        _ if w & 0xf800d000 == 0xf0008000 && bits(w, 22, 4) < 14 => {
+            let value = s << 20 | j2 << 19 | j1 << 18 | bits(w, 16, 6) << 12 | bits(w, 0, 11) << 1;
+            let t = target(pc, signed(value, 21));
+            let cond = CONDITIONS[bits(w, 22, 4) as usize];
+            return (format!("b{}.w 0x{:X}", cond, t), Some(t));
+        }
+        // movw, movt
+        _ if w & 0xfb708000 == 0xf2400000 => {
+            let name = if bits(w, 23, 1) == 1 { "movt" } else { "movw" };
+            let value = bits(w, 16, 4) << 12 | s << 11 | bits(w, 12, 3) << 8 | bits(w, 0, 8);
+            format!("{} {}, {}", name, reg(bits(w, 8, 4)), imm(value as i64))
+        }
+        _ if w >> 16 == 0xe92d => format!("push.w {}", reg_list(bits(w, 0, 16))),
+        _ if w >> 16 == 0xe8bd => format!("pop.w {}", reg_list(bits(w, 0, 16))),
+        // ldr.w (literal)
+        _ if w & 0xff7f0000 == 0xf85f0000 => {
+            let value = bits(w, 0, 12) as i64;
+            let value = if bits(w, 23, 1) == 1 { value } else { -value };
+            let t = target(pc & !3, value);
+            return (format!("ldr.w {}, 0x{:X}", rt, t), Some(t));
+        }
+        // ldr.w, str.w with a 12-bit offset
+        _ if w & 0xffe00000 == 0xf8c00000 => {
+            let name = if bits(w, 20, 1) == 1 {
+                "ldr.w"
+            } else {
+                "str.w"
+            };
+            let ofs = offset(bits(w, 0, 12) as i64, true);
+            format!(
+                "{} {}, {}",
+                name,
+                rt,
+                address(bits(w, 16, 4), &ofs, true, false)
+            )
+        }
+        _ => return word(w),
+    };
+    (text, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_test() {
+        let pc = 0x8000;
+        let cases: [(u32, &str); 18] = [
+            (0xe320f000, "nop"),
+            (0xe12fff1e, "bx lr"),
+            (0xeb000002, "bl 0x8010"),
+            (0x0afffffe, "beq 0x8000"),
+            (0xe92d4010, "push {r4, lr}"),
+            (0xe8bd8010, "pop {r4, pc}"),
+            (0xe52de004, "str lr, [sp, #-0x4]!"),
+            (0xe3a00001, "mov r0, #0x1"),
+            (0xe1a01002, "mov r1, r2"),
+            (0xe1a00080, "mov r0, r0, lsl #1"),
+            (0xe2800004, "add r0, r0, #0x4"),
+            (0xe3500000, "cmp r0, #0x0"),
+            (0xe0000291, "mul r0, r1, r2"),
+            (0xe30c0afe, "movw r0, #0xCAFE"),
+            (0xe59f0008, "ldr r0, 0x8010"),
+            (0xe5901004, "ldr r1, [r0, #0x4]"),
+            (0xe1d010b2, "ldrh r1, [r0, #0x2]"),
+            (0xef000000, "svc #0x0"),
+        ];
+        for (w, text) in cases {
+            assert_eq!(decode(w, pc).0, text, "{:08X}", w);
+        }
+        assert_eq!(decode(0xeb000002, pc).1, Some(0x8010));
+        assert_eq!(decode(0xffffffff, pc).0, ".word 0xFFFFFFFF");
+    }
+
+    #[test]
+    fn decode_thumb_test() {
+        let pc = 0x8000;
+        let cases: [(u16, &str); 16] = [
+            (0xbf00, "nop"),
+            (0xbf08, "it eq"),
+            (0xbf0c, "ite eq"),
+            (0xb510, "push {r4, lr}"),
+            (0xbd10, "pop {r4, pc}"),
+            (0x2001, "movs r0, #0x1"),
+            (0x1840, "adds r0, r0, r1"),
+            (0x4288, "cmp r0, r1"),
+            (0xb082, "sub sp, sp, #0x8"),
+            (0x6841, "ldr r1, [r0, #0x4]"),
+            (0x4800, "ldr r0, 0x8004"),
+            (0x4770, "bx lr"),
+            (0xd0fe, "beq 0x8000"),
+            (0xe7fe, "b 0x8000"),
+            (0xb118, "cbz r0, 0x800A"),
+            (0xdf00, "svc #0x0"),
+        ];
+        for (hw, text) in cases {
+            assert_eq!(decode_thumb(hw, pc).0, text, "{:04X}", hw);
+        }
+        assert_eq!(decode_thumb(0xb118, pc).1, Some(0x800a));
+
+        let cases: [(u32, &str); 5] = [
+            (0xf000f802, "bl 0x8008"),
+            (0xf7fffffe, "bl 0x8000"),
+            (0xe92d4010, "push.w {r4, lr}"),
+            (0xf2412034, "movw r0, #0x1234"),
+            (0xf8d01004, "ldr.w r1, [r0, #0x4]"),
+        ];
+        for (w, text) in cases {
+            assert!(is_thumb32((w >> 16) as u16));
+            assert_eq!(decode_thumb32(w, pc).0, text, "{:08X}", w);
+        }
+        assert!(!is_thumb32(0xb510));
+    }
+}
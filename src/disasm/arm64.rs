@@ -0,0 +1,462 @@
+// A small AArch64 decoder covering the instructions most often seen when
+// browsing code: branches, address generation, moves, arithmetic, logical
+// operations, loads and stores. Anything else is shown as a `.word`.
+
+pub(super) const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "hs", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "al", "nv",
+];
+pub(super) const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+
+pub(super) fn bits(w: u32, lo: u32, len: u32) -> u32 {
+    (w >> lo) & ((1 << len) - 1)
+}
+
+// sign-extend the `len` lowest bits of `v`
+pub(super) fn signed(v: u32, len: u32) -> i64 {
+    let shift = 64 - len;
+    ((v as i64) << shift) >> shift
+}
+
+/// Register name. Number 31 is the zero register, or the stack pointer
+/// where the instruction allows it.
+fn reg(n: u32, is64: bool, sp: bool) -> String {
+    match (n, is64, sp) {
+        (31, true, true) => "sp".to_string(),
+        (31, false, true) => "wsp".to_string(),
+        (31, true, false) => "xzr".to_string(),
+        (31, false, false) => "wzr".to_string(),
+        (n, true, _) => format!("x{}", n),
+        (n, false, _) => format!("w{}", n),
+    }
+}
+
+pub(super) fn imm(v: i64) -> String {
+    if v < 0 {
+        format!("#-0x{:X}", -v)
+    } else {
+        format!("#0x{:X}", v)
+    }
+}
+
+pub(super) fn target(pc: u64, delta: i64) -> u64 {
+    pc.wrapping_add(delta as u64)
+}
+
+/// Decode the instruction `w` found at address `pc`. Returns its text and,
+/// for branches and PC-relative loads, the address it refers to.
+pub fn decode(w: u32, pc: u64) -> (String, Option<u64>) {
+    let rd = bits(w, 0, 5);
+    let rn = bits(w, 5, 5);
+    let sf = bits(w, 31, 1) == 1;
+
+    match w {
+        0xd503201f => ("nop".to_string(), None),
+        _ if w & 0xfffffc1f == 0xd65f0000 => {
+            if rn == 30 {
+                ("ret".to_string(), None)
+            } else {
+                (format!("ret {}", reg(rn, true, false)), None)
+            }
+        }
+        _ if w & 0xfffffc1f == 0xd61f0000 => (format!("br {}", reg(rn, true, false)), None),
+        _ if w & 0xfffffc1f == 0xd63f0000 => (format!("blr {}", reg(rn, true, false)), None),
+        _ if w & 0xffe0001f == 0xd4000001 => (format!("svc {}", imm(bits(w, 5, 16) as i64)), None),
+        _ if w & 0xffe0001f == 0xd4200000 => (format!("brk {}", imm(bits(w, 5, 16) as i64)), None),
+        // b, bl
+        _ if w & 0x7c000000 == 0x14000000 => {
+            let t = target(pc, signed(bits(w, 0, 26), 26) * 4);
+            let name = if sf { "bl" } else { "b" };
+            (format!("{} 0x{:X}", name, t), Some(t))
+        }
+        // b.cond
+        _ if w & 0xff000010 == 0x54000000 => {
+            let t = target(pc, signed(bits(w, 5, 19), 19) * 4);
+            let cond = CONDITIONS[bits(w, 0, 4) as usize];
+            (format!("b.{} 0x{:X}", cond, t), Some(t))
+        }
+        // cbz, cbnz
+        _ if w & 0x7e000000 == 0x34000000 => {
+            let t = target(pc, signed(bits(w, 5, 19), 19) * 4);
+            let name = if bits(w, 24, 1) == 1 { "cbnz" } else { "cbz" };
+            (
+                format!("{} {}, 0x{:X}", name, reg(rd, sf, false), t),
+                Some(t),
+            )
+        }
+        // tbz, tbnz
+        _ if w & 0x7e000000 == 0x36000000 => {
+            let t = target(pc, signed(bits(w, 5, 14), 14) * 4);
+            let bit = (bits(w, 31, 1) << 5) | bits(w, 19, 5);
+            let name = if bits(w, 24, 1) == 1 { "tbnz" } else { "tbz" };
+            let text = format!("{} {}, #{}, 0x{:X}", name, reg(rd, bit > 31, false), bit, t);
+            (text, Some(t))
+        }
+        // adr, adrp
+        _ if w & 0x1f000000 == 0x10000000 => {
+            let value = signed((bits(w, 5, 19) << 2) | bits(w, 29, 2), 21);
+            let (name, t) = if sf {
+                ("adrp", target(pc & !0xfff, value << 12))
+            } else {
+                ("adr", target(pc, value))
+            };
+            (
+                format!("{} {}, 0x{:X}", name, reg(rd, true, false), t),
+                Some(t),
+            )
+        }
+        // add/sub (immediate)
+        _ if w & 0x1f800000 == 0x11000000 => {
+            let value = (bits(w, 10, 12) as i64) << (12 * bits(w, 22, 1));
+            let (sub, flags) = (bits(w, 30, 1) == 1, bits(w, 29, 1) == 1);
+            let text = match (sub, flags) {
+                (false, false) if value == 0 && (rd == 31 || rn == 31) => {
+                    format!("mov {}, {}", reg(rd, sf, true), reg(rn, sf, true))
+                }
+                (true, true) if rd == 31 => format!("cmp {}, {}", reg(rn, sf, true), imm(value)),
+                (false, true) if rd == 31 => format!("cmn {}, {}", reg(rn, sf, true), imm(value)),
+                _ => {
+                    let name = ["add", "adds", "sub", "subs"][(sub as usize) * 2 + flags as usize];
+                    let rd = reg(rd, sf, !flags);
+                    format!("{} {}, {}, {}", name, rd, reg(rn, sf, true), imm(value))
+                }
+            };
+            (text, None)
+        }
+        // movn, movz, movk
+        _ if w & 0x1f800000 == 0x12800000 && bits(w, 29, 2) != 1 => {
+            let shift = bits(w, 21, 2) * 16;
+            let value = bits(w, 5, 16) as i64;
+            let name = ["movn", "", "movz", "movk"][bits(w, 29, 2) as usize];
+            let mut text = format!("{} {}, {}", name, reg(rd, sf, false), imm(value));
+            if shift != 0 {
+                text.push_str(&format!(", lsl #{}", shift));
+            }
+            (text, None)
+        }
+        // logical (shifted register)
+        _ if w & 0x1f000000 == 0x0a000000 => {
+            let rm = bits(w, 16, 5);
+            let amount = bits(w, 10, 6);
+            let opc = bits(w, 29, 2) as usize;
+            let negate = bits(w, 21, 1) as usize;
+            let text = if opc == 1 && negate == 0 && rn == 31 && amount == 0 {
+                format!("mov {}, {}", reg(rd, sf, false), reg(rm, sf, false))
+            } else {
+                let name =
+                    [["and", "orr", "eor", "ands"], ["bic", "orn", "eon", "bics"]][negate][opc];
+                let mut text = format!(
+                    "{} {}, {}, {}",
+                    name,
+                    reg(rd, sf, false),
+                    reg(rn, sf, false),
+                    reg(rm, sf, false)
+                );
+                if amount != 0 {
+                    text.push_str(&format!(
+                        ", {} #{}",
+                        SHIFTS[bits(w, 22, 2) as usize],
+                        amount
+                    ));
+                }
+                text
+            };
+            (text, None)
+        }
+        // add/sub (shifted register)
+        _ if w & 0x1f200000 == 0x0b000000 => {
+            let rm = bits(w, 16, 5);
+            let amount = bits(w, 10, 6);
+            let (sub, flags) = (bits(w, 30, 1) == 1, bits(w, 29, 1) == 1);
+            let mut text = if sub && flags && rd == 31 {
+                format!("cmp {}, {}", reg(rn, sf, false), reg(rm, sf, false))
+            } else {
+                let name = ["add", "adds", "sub", "subs"][(sub as usize) * 2 + flags as usize];
+                format!(
+                    "{} {}, {}, {}",
+                    name,
+                    reg(rd, sf, false),
+                    reg(rn, sf, false),
+                    reg(rm, sf, false)
+                )
+            };
+            if amount != 0 {
+                text.push_str(&format!(
+                    ", {} #{}",
+                    SHIFTS[bits(w, 22, 2) as usize],
+                    amount
+                ));
+            }
+            (text, None)
+        }
+        // ldr (literal)
+        _ if w & 0x3f000000 == 0x18000000 && bits(w, 30, 2) != 3 => {
+            let t = target(pc, signed(bits(w, 5, 19), 19) * 4);
+            let (name, is64) = match bits(w, 30, 2) {
+                0 => ("ldr", false),
+                1 => ("ldr", true),
+                _ => ("ldrsw", true),
+            };
+            (
+                format!("{} {}, 0x{:X}", name, reg(rd, is64, false), t),
+                Some(t),
+            )
+        }
+        // ldr/str (unsigned immediate)
+        _ if w & 0x3f000000 == 0x39000000 => {
+            let size = bits(w, 30, 2);
+            let opc = bits(w, 22, 2);
+            let (name, is64) = match (size, opc) {
+                (3, 0) => ("str", true),
+                (3, 1) => ("ldr", true),
+                (2, 0) => ("str", false),
+                (2, 1) => ("ldr", false),
+                (2, 2) => ("ldrsw", true),
+                (1, 0) => ("strh", false),
+                (1, 1) => ("ldrh", false),
+                (1, 2) => ("ldrsh", true),
+                (1, 3) => ("ldrsh", false),
+                (0, 0) => ("strb", false),
+                (0, 1) => ("ldrb", false),
+                (0, 2) => ("ldrsb", true),
+                (0, 3) => ("ldrsb", false),
+                _ => return (format!(".word 0x{:08X}", w), None),
+            };
+            let offset = (bits(w, 10, 12) << size) as i64;
+            let address = if offset == 0 {
+                format!("[{}]", reg(rn, true, true))
+            } else {
+                format!("[{}, {}]", reg(rn, true, true), imm(offset))
+            };
+            (
+                format!("{} {}, {}", name, reg(rd, is64, false), address),
+                None,
+            )
+        }
+        // ldp/stp
+        _ if w & 0x3a000000 == 0x28000000 && bits(w, 23, 2) != 0 && bits(w, 30, 2) != 3 => {
+            let load = bits(w, 22, 1) == 1;
+            let (name, is64, scale) = match (bits(w, 30, 2), load) {
+                (0, false) => ("stp", false, 2),
+                (0, true) => ("ldp", false, 2),
+                (1, true) => ("ldpsw", true, 2),
+                (2, false) => ("stp", true, 3),
+                (2, true) => ("ldp", true, 3),
+                _ => return (format!(".word 0x{:08X}", w), None),
+            };
+            let offset = imm(signed(bits(w, 15, 7), 7) << scale);
+            let base = reg(rn, true, true);
+            let address = match bits(w, 23, 2) {
+                1 => format!("[{}], {}", base, offset),
+                2 => format!("[{}, {}]", base, offset),
+                _ => format!("[{}, {}]!", base, offset),
+            };
+            let rt2 = reg(bits(w, 10, 5), is64, false);
+            (
+                format!("{} {}, {}, {}", name, reg(rd, is64, false), rt2, address),
+                None,
+            )
+        }
+        _ => (format!(".word 0x{:08X}", w), None),
+    }
+}
+
+// register number and width from a name; `sp` and `wsp` are 31 like the
+// zero registers
+fn parse_reg(name: &str) -> Option<(u32, bool)> {
+    match name {
+        "sp" | "xzr" => return Some((31, true)),
+        "wsp" | "wzr" => return Some((31, false)),
+        "lr" => return Some((30, true)),
+        "fp" => return Some((29, true)),
+        _ => {}
+    }
+    let is64 = match name.chars().next()? {
+        'x' => true,
+        'w' => false,
+        _ => return None,
+    };
+    let n: u32 = name[1..].parse().ok()?;
+    (n < 31).then_some((n, is64))
+}
+
+fn parse_imm(s: &str) -> Option<i64> {
+    crate::disasm::asm::parse_number(s.strip_prefix('#').unwrap_or(s))
+}
+
+// offset from `pc` to the address in `s`, in instructions, if it fits in
+// `len` signed bits
+fn branch_offset(s: &str, pc: u64, len: u32) -> Result<u32, String> {
+    let addr = parse_imm(s).ok_or_else(|| format!("Invalid address: {}", s))?;
+    let delta = (addr as u64).wrapping_sub(pc) as i64;
+    let limit = 1i64 << (len + 1);
+    if delta % 4 != 0 || delta < -limit || delta >= limit {
+        return Err(format!("Address out of range: {}", s));
+    }
+    Ok(((delta >> 2) as u32) & ((1 << len) - 1))
+}
+
+/// Encode the instruction in `text`, found at address `pc`. Covers
+/// branches, moves of registers and immediates, and add/sub/cmp with an
+/// immediate.
+pub fn assemble(text: &str, pc: u64) -> Result<u32, String> {
+    let text = text.trim().to_lowercase();
+    let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+    let ops: Vec<&str> = rest
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let invalid = || format!("Invalid operands for {}", name);
+    let reg = |i: usize| ops.get(i).and_then(|s| parse_reg(s)).ok_or_else(invalid);
+    let imm16 = |i: usize| {
+        ops.get(i)
+            .and_then(|s| parse_imm(s))
+            .filter(|v| (0..=0xffff).contains(v))
+            .map(|v| v as u32)
+            .ok_or_else(invalid)
+    };
+
+    match (name, ops.len()) {
+        ("nop", 0) => Ok(0xd503201f),
+        ("ret", 0) => Ok(0xd65f03c0),
+        ("ret", 1) => Ok(0xd65f0000 | reg(0)?.0 << 5),
+        ("br", 1) => Ok(0xd61f0000 | reg(0)?.0 << 5),
+        ("blr", 1) => Ok(0xd63f0000 | reg(0)?.0 << 5),
+        ("svc", 1) => Ok(0xd4000001 | imm16(0)? << 5),
+        ("brk", 1) => Ok(0xd4200000 | imm16(0)? << 5),
+        ("b", 1) => Ok(0x14000000 | branch_offset(ops[0], pc, 26)?),
+        ("bl", 1) => Ok(0x94000000 | branch_offset(ops[0], pc, 26)?),
+        ("cbz" | "cbnz", 2) => {
+            let (rt, is64) = reg(0)?;
+            let op = if name == "cbnz" {
+                0x35000000
+            } else {
+                0x34000000
+            };
+            Ok((is64 as u32) << 31 | op | branch_offset(ops[1], pc, 19)? << 5 | rt)
+        }
+        (_, 1) if name.starts_with("b.") => {
+            let cond = CONDITIONS
+                .iter()
+                .position(|c| *c == &name[2..])
+                .ok_or_else(|| format!("Unknown condition: {}", &name[2..]))?;
+            Ok(0x54000000 | branch_offset(ops[0], pc, 19)? << 5 | cond as u32)
+        }
+        ("movz" | "movn" | "movk" | "mov", 2 | 4) if ops[1].starts_with('#') => {
+            let (rd, is64) = reg(0)?;
+            let value = parse_imm(ops[1]).ok_or_else(invalid)?;
+            let shift = match ops.get(2..) {
+                Some([lsl, amount]) if *lsl == "lsl" => parse_imm(amount).ok_or_else(invalid)?,
+                _ => 0,
+            };
+            let (opc, value) = match name {
+                "movn" => (0, value),
+                "movk" => (3, value),
+                "mov" if value < 0 => (0, !value),
+                _ => (2, value),
+            };
+            let max_shift = if is64 { 48 } else { 16 };
+            if !(0..=0xffff).contains(&value)
+                || shift % 16 != 0
+                || !(0..=max_shift).contains(&shift)
+            {
+                return Err(invalid());
+            }
+            let hw = (shift / 16) as u32;
+            Ok((is64 as u32) << 31 | opc << 29 | 0x12800000 | hw << 21 | (value as u32) << 5 | rd)
+        }
+        // mov between registers is an orr with the zero register, or an add
+        // when the stack pointer is involved
+        ("mov", 2) => {
+            let ((rd, is64), (rm, _)) = (reg(0)?, reg(1)?);
+            if ops[0].ends_with("sp") || ops[1].ends_with("sp") {
+                Ok((is64 as u32) << 31 | 0x11000000 | rm << 5 | rd)
+            } else {
+                Ok((is64 as u32) << 31 | 0x2a0003e0 | rm << 16 | rd)
+            }
+        }
+        ("add" | "adds" | "sub" | "subs" | "cmp" | "cmn", _) => {
+            let (rd, rn, value) = match (name, ops.len()) {
+                ("cmp" | "cmn", 2) => ((31, reg(0)?.1), reg(0)?, ops[1]),
+                (_, 3) => (reg(0)?, reg(1)?, ops[2]),
+                _ => return Err(invalid()),
+            };
+            let value = parse_imm(value).ok_or_else(invalid)?;
+            let (imm, sh) = match value {
+                0..=0xfff => (value as u32, 0),
+                _ if value & 0xfff == 0 && value >> 12 <= 0xfff => ((value >> 12) as u32, 1),
+                _ => return Err(invalid()),
+            };
+            let (sub, flags) = match name {
+                "add" => (0, 0),
+                "adds" | "cmn" => (0, 1),
+                "sub" => (1, 0),
+                _ => (1, 1),
+            };
+            Ok((rd.1 as u32) << 31
+                | sub << 30
+                | flags << 29
+                | 0x11000000
+                | sh << 22
+                | imm << 10
+                | rn.0 << 5
+                | rd.0)
+        }
+        _ => Err(format!("Unknown instruction: {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_test() {
+        let pc = 0x100000;
+        let cases: [(u32, &str); 14] = [
+            (0xd503201f, "nop"),
+            (0xd65f03c0, "ret"),
+            (0x94000004, "bl 0x100010"),
+            (0x17ffffff, "b 0xFFFFC"),
+            (0x54000040, "b.eq 0x100008"),
+            (0xb4000041, "cbz x1, 0x100008"),
+            (0x90000000, "adrp x0, 0x100000"),
+            (0x910003fd, "mov x29, sp"),
+            (0xd10043ff, "sub sp, sp, #0x10"),
+            (0xf100041f, "cmp x0, #0x1"),
+            (0x52800540, "movz w0, #0x2A"),
+            (0xaa0103e0, "mov x0, x1"),
+            (0xf9400421, "ldr x1, [x1, #0x8]"),
+            (0xa9bf7bfd, "stp x29, x30, [sp, #-0x10]!"),
+        ];
+        for (w, text) in cases {
+            assert_eq!(decode(w, pc).0, text, "{:08X}", w);
+        }
+        assert_eq!(decode(0x94000004, pc).1, Some(0x100010));
+        assert_eq!(decode(0xffffffff, pc).0, ".word 0xFFFFFFFF");
+    }
+
+    #[test]
+    fn assemble_test() {
+        let pc = 0x100000;
+        for text in [
+            "nop",
+            "ret",
+            "bl 0x100010",
+            "b 0xFFFFC",
+            "b.eq 0x100008",
+            "cbz x1, 0x100008",
+            "mov x29, sp",
+            "sub sp, sp, #0x10",
+            "cmp x0, #0x1",
+            "movz w0, #0x2A",
+            "mov x0, x1",
+        ] {
+            let w = assemble(text, pc).unwrap();
+            assert_eq!(decode(w, pc).0, text, "{:08X}", w);
+        }
+        assert_eq!(assemble("mov w0, #0x2a", pc), Ok(0x52800540));
+        assert!(assemble("b 0x100002", pc).is_err());
+        assert!(assemble("ldr x0, [x1]", pc).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+// Assembler for single instructions written in Intel syntax. For x86, the
+// operands are parsed here and matched against iced's opcode tables; every
+// form that fits is encoded and the shortest one wins.
+
+use iced_x86::{Code, Encoder, Instruction, Mnemonic, OpCodeOperandKind as K, OpKind, Register};
+
+use super::{Arch, arm64};
+
+/// Memory operand, as written
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Mem {
+    base: Register,
+    index: Register,
+    scale: u32,
+    displ: i64,
+    /// size given with `byte ptr` and friends
+    size: Option<usize>,
+    segment: Register,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    Reg(Register),
+    Mem(Mem),
+    Imm(i64),
+}
+
+// what an operand of an opcode accepts
+#[derive(PartialEq)]
+enum Class {
+    Reg,
+    Mem,
+    RegOrMem,
+    Imm,
+    Branch,
+    Unsupported,
+}
+
+// instructions whose memory operand is as wide as the stack unless told
+// otherwise
+const STACK_WIDTH: [Mnemonic; 4] = [Mnemonic::Call, Mnemonic::Jmp, Mnemonic::Push, Mnemonic::Pop];
+
+// names iced doesn't use
+const ALIASES: [(&str, &str); 16] = [
+    ("jz", "je"),
+    ("jnz", "jne"),
+    ("jc", "jb"),
+    ("jnc", "jae"),
+    ("jnae", "jb"),
+    ("jnb", "jae"),
+    ("jna", "jbe"),
+    ("jnbe", "ja"),
+    ("jnge", "jl"),
+    ("jnl", "jge"),
+    ("jng", "jle"),
+    ("jnle", "jg"),
+    ("jpe", "jp"),
+    ("jpo", "jnp"),
+    ("sal", "shl"),
+    ("retn", "ret"),
+];
+
+/// Parse a number: decimal, hexadecimal with a `0x` prefix or an `h` suffix,
+/// optionally negative
+pub fn parse_number(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest.trim()),
+        None => (false, s),
+    };
+    let value = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if let Some(hex) = s.strip_suffix(['h', 'H'])
+        && hex.starts_with(|c: char| c.is_ascii_digit())
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else {
+        s.parse::<u64>().ok()?
+    } as i64;
+    Some(if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    })
+}
+
+fn register(name: &str) -> Option<Register> {
+    Register::values()
+        .skip(1)
+        .find(|r| format!("{:?}", r).eq_ignore_ascii_case(name))
+}
+
+fn mnemonic(name: &str) -> Option<Mnemonic> {
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, real)| real);
+    Mnemonic::values()
+        .skip(1)
+        .find(|m| format!("{:?}", m).eq_ignore_ascii_case(name))
+}
+
+fn size_keyword(s: &str) -> Option<(usize, &str)> {
+    const SIZES: [(&str, usize); 8] = [
+        ("byte", 1),
+        ("word", 2),
+        ("dword", 4),
+        ("fword", 6),
+        ("qword", 8),
+        ("tbyte", 10),
+        ("xmmword", 16),
+        ("ymmword", 32),
+    ];
+    let (word, rest) = s.split_once(char::is_whitespace)?;
+    let size = SIZES.iter().find(|(name, _)| *name == word)?.1;
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix("ptr").unwrap_or(rest).trim_start();
+    Some((size, rest))
+}
+
+fn parse_memory(s: &str, size: Option<usize>) -> Result<Mem, String> {
+    let invalid = || format!("Invalid memory operand: {}", s);
+
+    let (segment, rest) = match s.split_once(':') {
+        Some((seg, rest)) => (register(seg.trim()).ok_or_else(invalid)?, rest.trim()),
+        None => (Register::None, s),
+    };
+    let inner = rest
+        .strip_prefix('[')
+        .and_then(|r| r.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+
+    let mut mem = Mem {
+        base: Register::None,
+        index: Register::None,
+        scale: 1,
+        displ: 0,
+        size,
+        segment,
+    };
+    for term in inner.replace('-', "+-").split('+') {
+        let term = term.trim();
+        if term.is_empty() {
+            continue;
+        }
+        if let Some((a, b)) = term.split_once('*') {
+            let (reg, scale) = match (register(a.trim()), register(b.trim())) {
+                (Some(reg), None) => (reg, b),
+                (None, Some(reg)) => (reg, a),
+                _ => return Err(invalid()),
+            };
+            let scale = parse_number(scale).ok_or_else(invalid)?;
+            if mem.index != Register::None || ![1, 2, 4, 8].contains(&scale) {
+                return Err(invalid());
+            }
+            mem.index = reg;
+            mem.scale = scale as u32;
+        } else if let Some(reg) = register(term) {
+            if mem.base == Register::None {
+                mem.base = reg;
+            } else if mem.index == Register::None {
+                mem.index = reg;
+            } else {
+                return Err(invalid());
+            }
+        } else {
+            let value = parse_number(term).ok_or_else(invalid)?;
+            mem.displ = mem.displ.wrapping_add(value);
+        }
+    }
+    Ok(mem)
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let (size, rest) = match size_keyword(s) {
+        Some((size, rest)) => (Some(size), rest),
+        None => (None, s),
+    };
+    if rest.ends_with(']') {
+        return parse_memory(rest, size).map(Operand::Mem);
+    }
+    if size.is_none() {
+        if let Some(reg) = register(rest) {
+            return Ok(Operand::Reg(reg));
+        }
+        if let Some(value) = parse_number(rest) {
+            return Ok(Operand::Imm(value));
+        }
+    }
+    Err(format!("Invalid operand: {}", s))
+}
+
+fn class(kind: K) -> Class {
+    match kind {
+        K::br16_1 | K::br32_1 | K::br64_1 | K::br16_2 | K::br32_4 | K::br64_4 => Class::Branch,
+        K::xbegin_2 | K::xbegin_4 => Class::Branch,
+        K::imm8
+        | K::imm8_const_1
+        | K::imm8sex16
+        | K::imm8sex32
+        | K::imm8sex64
+        | K::imm16
+        | K::imm32
+        | K::imm32sex64
+        | K::imm64 => Class::Imm,
+        K::mem_offs | K::mem | K::mem_mpx | K::mem_mib | K::sibmem => Class::Mem,
+        K::mem_vsib32x | K::mem_vsib64x | K::mem_vsib32y | K::mem_vsib64y => Class::Mem,
+        K::mem_vsib32z | K::mem_vsib64z => Class::Mem,
+        K::r8_or_mem
+        | K::r16_or_mem
+        | K::r32_or_mem
+        | K::r32_or_mem_mpx
+        | K::r64_or_mem
+        | K::r64_or_mem_mpx
+        | K::mm_or_mem
+        | K::xmm_or_mem
+        | K::ymm_or_mem
+        | K::zmm_or_mem
+        | K::bnd_or_mem_mpx
+        | K::k_or_mem => Class::RegOrMem,
+        K::None | K::farbr2_2 | K::farbr4_2 | K::brdisp_2 | K::brdisp_4 | K::imm4_m2z => {
+            Class::Unsupported
+        }
+        _ => Class::Reg,
+    }
+}
+
+// size of the memory operand of a general purpose instruction
+fn memory_size(kind: K) -> Option<usize> {
+    match kind {
+        K::r8_or_mem => Some(1),
+        K::r16_or_mem => Some(2),
+        K::r32_or_mem | K::r32_or_mem_mpx => Some(4),
+        K::r64_or_mem | K::r64_or_mem_mpx => Some(8),
+        _ => None,
+    }
+}
+
+// immediate operand kind for a value, if it fits
+fn immediate_kind(kind: K, value: i64, second: bool) -> Option<OpKind> {
+    let fits = |lo: i64, hi: i64| (lo..=hi).contains(&value);
+    let (op_kind, ok) = match kind {
+        K::imm8 | K::imm8_const_1 if second => {
+            (OpKind::Immediate8_2nd, fits(i8::MIN as i64, u8::MAX as i64))
+        }
+        K::imm8 | K::imm8_const_1 => (OpKind::Immediate8, fits(i8::MIN as i64, u8::MAX as i64)),
+        K::imm8sex16 => (OpKind::Immediate8to16, fits(i8::MIN as i64, i8::MAX as i64)),
+        K::imm8sex32 => (OpKind::Immediate8to32, fits(i8::MIN as i64, i8::MAX as i64)),
+        K::imm8sex64 => (OpKind::Immediate8to64, fits(i8::MIN as i64, i8::MAX as i64)),
+        K::imm16 => (OpKind::Immediate16, fits(i16::MIN as i64, u16::MAX as i64)),
+        K::imm32 => (OpKind::Immediate32, fits(i32::MIN as i64, u32::MAX as i64)),
+        K::imm32sex64 => (
+            OpKind::Immediate32to64,
+            fits(i32::MIN as i64, i32::MAX as i64),
+        ),
+        K::imm64 => (OpKind::Immediate64, true),
+        _ => return None,
+    };
+    ok.then_some(op_kind)
+}
+
+// ways to encode a memory operand: in 64-bit mode, a lone address is
+// RIP-relative first, as the disassembler shows it, then absolute
+fn memory_forms(mem: &Mem, bitness: u32) -> Vec<Mem> {
+    if mem.base != Register::None || mem.index != Register::None {
+        return vec![*mem];
+    }
+    let mut forms = Vec::new();
+    if bitness == 64 {
+        forms.push(Mem {
+            base: Register::RIP,
+            ..*mem
+        });
+    }
+    forms.push(*mem);
+    forms
+}
+
+fn set_memory(insn: &mut Instruction, mem: &Mem, bitness: u32) {
+    let displ_size = match (mem.base, mem.index, bitness) {
+        (Register::None, Register::None, 16) => 2,
+        (Register::None, Register::None, _) => 4,
+        (Register::None, _, _) => 4,
+        _ if mem.displ == 0 => 0,
+        _ => 1,
+    };
+    insn.set_memory_base(mem.base);
+    insn.set_memory_index(mem.index);
+    insn.set_memory_index_scale(mem.scale);
+    insn.set_memory_displ_size(displ_size);
+    insn.set_memory_displacement64(mem.displ as u64);
+    insn.set_segment_prefix(mem.segment);
+}
+
+// the instruction for `code`, if the operands fit its kinds
+fn build(
+    code: Code,
+    kinds: &[K],
+    ops: &[Operand],
+    mem: Option<&Mem>,
+    bitness: u32,
+) -> Option<Instruction> {
+    if let ([kind], [Operand::Imm(target)]) = (kinds, ops)
+        && class(*kind) == Class::Branch
+    {
+        // branches with an operand size prefix truncate the target
+        let width = match kind {
+            K::br16_1 | K::br16_2 | K::xbegin_2 => 16,
+            K::br32_1 | K::br32_4 => 32,
+            _ => 64,
+        };
+        let native = if bitness == 64 && *kind == K::xbegin_4 {
+            64
+        } else {
+            width
+        };
+        if native != bitness {
+            return None;
+        }
+        return Instruction::with_branch(code, *target as u64).ok();
+    }
+
+    let mut insn = Instruction::default();
+    insn.set_code(code);
+    for (i, (kind, op)) in kinds.iter().zip(ops).enumerate() {
+        let class = class(*kind);
+        match op {
+            Operand::Reg(reg) if matches!(class, Class::Reg | Class::RegOrMem) => {
+                insn.set_op_kind(i as u32, OpKind::Register);
+                insn.set_op_register(i as u32, *reg);
+            }
+            Operand::Mem(written) if matches!(class, Class::Mem | Class::RegOrMem) => {
+                let size = memory_size(*kind);
+                if written.size.is_some() && size.is_some() && written.size != size {
+                    return None;
+                }
+                insn.set_op_kind(i as u32, OpKind::Memory);
+                set_memory(&mut insn, mem.unwrap_or(written), bitness);
+            }
+            Operand::Imm(value) if class == Class::Imm => {
+                let second = i > 0 && matches!(ops[i - 1], Operand::Imm(_));
+                let op_kind = immediate_kind(*kind, *value, second)?;
+                insn.set_op_kind(i as u32, op_kind);
+                insn.try_set_immediate_i64(i as u32, *value).ok()?;
+            }
+            _ => return None,
+        }
+    }
+    Some(insn)
+}
+
+/// Encode an x86 instruction found at address `ip`
+fn assemble_x86(text: &str, bitness: u32, ip: u64) -> Result<Vec<u8>, String> {
+    let text = text.trim().to_lowercase();
+    let (name, operands) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+    let mnemonic = mnemonic(name).ok_or_else(|| format!("Unknown instruction: {}", name))?;
+
+    let ops = if operands.trim().is_empty() {
+        Vec::new()
+    } else {
+        operands
+            .split(',')
+            .map(|s| parse_operand(s.trim()))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let written_mem = ops.iter().find_map(|op| match op {
+        Operand::Mem(mem) => Some(*mem),
+        _ => None,
+    });
+    let mem_forms = written_mem.map_or(vec![], |mem| memory_forms(&mem, bitness));
+    let has_reg = ops.iter().any(|op| matches!(op, Operand::Reg(_)));
+
+    // shortest encoding, with the size of its memory operand
+    let mut best: Option<Vec<u8>> = None;
+    let mut sizes = Vec::new();
+    for code in Code::values().filter(|c| c.mnemonic() == mnemonic) {
+        let op_code = code.op_code();
+        let mode = match bitness {
+            16 => op_code.mode16(),
+            32 => op_code.mode32(),
+            _ => op_code.mode64(),
+        };
+        let kinds = op_code.op_kinds();
+        if !op_code.is_instruction() || !mode || kinds.len() != ops.len() {
+            continue;
+        }
+        // `call [rax]` and `push [rax]` use the stack width by default
+        if STACK_WIDTH.contains(&mnemonic)
+            && written_mem.is_some_and(|m| m.size.is_none())
+            && kinds
+                .iter()
+                .any(|k| memory_size(*k).is_some_and(|s| s * 8 != bitness as usize))
+        {
+            continue;
+        }
+        // 0x90 doesn't clear the upper half of rax
+        if bitness == 64 && code == Code::Xchg_r32_EAX && ops == [Operand::Reg(Register::EAX); 2] {
+            continue;
+        }
+
+        let forms: Vec<Option<&Mem>> = if mem_forms.is_empty() {
+            vec![None]
+        } else {
+            mem_forms.iter().map(Some).collect()
+        };
+        for mem in forms {
+            let Some(insn) = build(code, kinds, &ops, mem, bitness) else {
+                continue;
+            };
+            let mut encoder = Encoder::new(bitness);
+            if encoder.encode(&insn, ip).is_err() {
+                continue;
+            }
+            let bytes = encoder.take_buffer();
+            if let Some(size) = kinds.iter().find_map(|k| memory_size(*k)) {
+                sizes.push(size);
+            }
+            if best.as_ref().is_none_or(|b| bytes.len() < b.len()) {
+                best = Some(bytes);
+            }
+            break;
+        }
+    }
+
+    // `mov [rax], 1` could be any size
+    sizes.sort_unstable();
+    sizes.dedup();
+    if written_mem.is_some_and(|m| m.size.is_none()) && !has_reg && sizes.len() > 1 {
+        return Err("Operand size needed: byte, word, dword or qword ptr".to_string());
+    }
+    best.ok_or_else(|| format!("Invalid operands for {}", name))
+}
+
+/// Encode the instruction in `text`, found at address `ip`
+pub fn assemble(arch: Arch, text: &str, ip: u64) -> Result<Vec<u8>, String> {
+    match arch {
+        Arch::X86_16 => assemble_x86(text, 16, ip),
+        Arch::X86_32 => assemble_x86(text, 32, ip),
+        Arch::X86_64 => assemble_x86(text, 64, ip),
+        Arch::Arm64 => arm64::assemble(text, ip).map(|w| w.to_le_bytes().to_vec()),
+        Arch::Arm | Arch::Thumb => Err(format!("No assembler for {}", arch.name())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assemble_x86_test() {
+        let asm = |text, ip| assemble(Arch::X86_64, text, ip);
+        assert_eq!(asm("nop", 0), Ok(vec![0x90]));
+        assert_eq!(asm("ret", 0), Ok(vec![0xc3]));
+        assert_eq!(asm("jmp 0x401010", 0x401000), Ok(vec![0xeb, 0x0e]));
+        assert_eq!(
+            asm("call 0x401009", 0x401004),
+            Ok(vec![0xe8, 0x00, 0x00, 0x00, 0x00])
+        );
+        assert_eq!(asm("jz 0x400ff0", 0x401000), Ok(vec![0x74, 0xee]));
+        assert_eq!(asm("mov rbp, rsp", 0), Ok(vec![0x48, 0x89, 0xe5]));
+        assert_eq!(asm("xor eax, eax", 0), Ok(vec![0x31, 0xc0]));
+        assert_eq!(asm("mov eax, 1", 0), Ok(vec![0xb8, 1, 0, 0, 0]));
+        assert_eq!(
+            asm("mov dword ptr [rbp-8], 0x10", 0),
+            Ok(vec![0xc7, 0x45, 0xf8, 0x10, 0, 0, 0])
+        );
+        assert_eq!(
+            asm("lea rsi, [0x1010]", 0x1000),
+            Ok(vec![0x48, 0x8d, 0x35, 0x09, 0, 0, 0])
+        );
+        assert!(asm("mov [rax], 1", 0).is_err());
+        assert!(asm("foo eax", 0).is_err());
+        assert!(asm("mov eax, rax", 0).is_err());
+
+        assert_eq!(
+            assemble(Arch::X86_32, "push 0x12345678", 0),
+            Ok(vec![0x68, 0x78, 0x56, 0x34, 0x12])
+        );
+    }
+}
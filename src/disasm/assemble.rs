@@ -0,0 +1,138 @@
+use ratatui::{
+    Frame,
+    layout::Alignment,
+    widgets::{Clear, Paragraph},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode};
+use std::io::Result;
+
+use tui_input::backend::crossterm::EventHandler;
+
+use crate::{
+    app::App,
+    disasm::{Arch, asm},
+    editor::UIState,
+    hex::edit::set_change,
+};
+
+const PROMPT: &str = "asm> ";
+
+impl App {
+    /// Encode `text` as an instruction placed at the cursor
+    fn assemble_at_cursor(&self, text: &str) -> std::result::Result<Vec<u8>, String> {
+        let offset = self.hex_view.offset;
+        let ip = self
+            .file_info
+            .binary
+            .as_ref()
+            .and_then(|b| b.offset_to_addr(offset))
+            .unwrap_or(offset as u64);
+        let arch = self
+            .disasm_arch()
+            .ok_or_else(|| "Unsupported architecture".to_string())?;
+        asm::assemble(arch, text, ip)
+    }
+}
+
+pub fn open_assemble(app: &mut App) {
+    if app.file_info.is_read_only || app.hex_view.offset >= app.file_info.size {
+        crate::beep!();
+        return;
+    }
+    app.state = UIState::DialogAssemble;
+    app.dialog_renderer = Some(dialog_assemble_draw);
+}
+
+pub fn dialog_assemble_draw(app: &mut App, frame: &mut Frame) {
+    let input = app.disasm_view.asm_input.value();
+    let para = Paragraph::new(format!("{}{}", PROMPT, input));
+
+    frame.render_widget(Clear, app.command_area);
+    frame.render_widget(para, app.command_area);
+
+    // what the instruction assembles to, as it's typed
+    if !input.trim().is_empty() {
+        let preview = match app.assemble_at_cursor(input) {
+            Ok(bytes) => Paragraph::new(
+                bytes
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+            .style(app.config.theme.main),
+            Err(e) => Paragraph::new(e).style(app.config.theme.error),
+        };
+        frame.render_widget(preview.alignment(Alignment::Right), app.command_area);
+    }
+
+    let x = app.disasm_view.asm_input.visual_cursor() + PROMPT.len();
+    frame.set_cursor_position((app.command_area.x + x as u16, app.command_area.y));
+}
+
+/// Write `bytes` at the cursor, padding with NOPs up to the end of the last
+/// instruction they overwrite, and move past them
+fn patch(app: &mut App, bytes: &[u8]) {
+    let offset = app.hex_view.offset;
+    if offset + bytes.len() > app.file_info.size {
+        crate::beep!();
+        App::log(
+            app,
+            format!("no room for {} bytes at {:x}", bytes.len(), offset),
+        );
+        return;
+    }
+
+    let mut replaced = 0;
+    if let Some(Arch::X86_16 | Arch::X86_32 | Arch::X86_64) = app.disasm_arch() {
+        for line in app.disassemble(offset, bytes.len()) {
+            if replaced >= bytes.len() {
+                break;
+            }
+            replaced += line.bytes.len();
+        }
+    }
+    let replaced = replaced.max(bytes.len()).min(app.file_info.size - offset);
+
+    // the whole instruction is undone at once
+    app.hex_view.history.begin();
+    for i in 0..replaced {
+        let b = bytes.get(i).copied().unwrap_or(0x90);
+        set_change(app, offset + i, format!("{:02X}", b));
+    }
+    app.hex_view.history.commit();
+
+    App::log(app, format!("{} bytes assembled at {:x}", replaced, offset));
+    app.goto((offset + replaced).min(app.file_info.size - 1));
+}
+
+pub fn dialog_assemble_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => {
+                app.disasm_view.asm_input.reset();
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            // the prompt stays open to assemble the next instruction
+            KeyCode::Enter => {
+                let text = app.disasm_view.asm_input.value().to_string();
+                if text.trim().is_empty() {
+                    return Ok(false);
+                }
+                match app.assemble_at_cursor(&text) {
+                    Ok(bytes) => {
+                        patch(app, &bytes);
+                        app.disasm_view.asm_input.reset();
+                    }
+                    Err(_) => crate::beep!(),
+                }
+            }
+            _ => {
+                app.disasm_view.asm_input.handle_event(event);
+            }
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,97 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    text::{Line, Text},
+    widgets::{Clear, Paragraph},
+};
+
+use crate::app::App;
+
+// bytes shown for each instruction; longer ones end with a `+`
+const BYTES_SHOWN: usize = 8;
+
+pub fn disasm_header_draw(app: &mut App, frame: &mut Frame, area: Rect) {
+    let header = format!(
+        "{:<width$}  {:<bytes$}  {}",
+        "Address",
+        "Bytes",
+        app.disasm_arch().map_or("unsupported", |a| a.name()),
+        width = app.addr_width(),
+        bytes = BYTES_SHOWN * 3
+    );
+    let para = Paragraph::new(header).style(app.config.theme.offsets);
+    frame.render_widget(para, area);
+}
+
+pub fn disasm_contents_draw(app: &mut App, frame: &mut Frame, area: Rect) {
+    let height = area.height as usize;
+    app.disasm_view.height = height;
+
+    // nothing is decoded as the wrong architecture
+    if app.disasm_arch().is_none() {
+        let machine = app.file_info.binary.as_ref().map_or("", |b| b.machine);
+        let text = format!(
+            "Unsupported architecture: {}. Press a to pick one to decode as.",
+            machine
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(text).style(app.config.theme.main), area);
+        return;
+    }
+
+    // keep the cursor, which is shared with the hex view, on the screen
+    let offset = app.hex_view.offset;
+    let mut lines = app.disassemble(app.disasm_view.top, height);
+    if offset < app.disasm_view.top || !lines.iter().any(|l| l.offset == offset) {
+        app.disasm_view.top = offset;
+        lines = app.disassemble(offset, height);
+    }
+
+    let mut text = Text::default();
+    for line in lines.iter() {
+        let mut bytes: String = line
+            .bytes
+            .iter()
+            .take(BYTES_SHOWN)
+            .map(|b| format!("{:02X} ", b))
+            .collect();
+        if line.bytes.len() > BYTES_SHOWN {
+            bytes.pop();
+            bytes.push('+');
+        }
+
+        // comments first, then the names of branch targets and of the
+        // instruction itself
+        let target = line
+            .target
+            .and_then(|t| app.disasm_target_offset(line.offset, t));
+        let bin = app.file_info.binary.as_ref();
+        let note = app
+            .hex_view
+            .comments
+            .get(&line.offset)
+            .map(|c| c.as_str())
+            .or_else(|| target.and_then(|t| bin?.symbol_at(t)))
+            .or_else(|| bin?.symbol_at(line.offset))
+            .map(|s| format!("; {}", s))
+            .unwrap_or_default();
+
+        let row = format!(
+            "{}  {:<bytes_width$}  {:<40} {}",
+            app.format_addr(line.offset),
+            bytes,
+            line.text,
+            note,
+            bytes_width = BYTES_SHOWN * 3
+        );
+        let style = if line.offset == offset {
+            app.config.theme.highlight
+        } else {
+            app.config.theme.main
+        };
+        text.push_line(Line::styled(row, style));
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(text).style(app.config.theme.main), area);
+}
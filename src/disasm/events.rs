@@ -0,0 +1,111 @@
+use std::io::Result;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{app::App, disasm::Arch};
+
+// move the cursor to the next instruction, scrolling if it was the last one
+// on the screen
+fn next_line(app: &mut App) {
+    let offset = app.hex_view.offset;
+    let Some(next) = app
+        .disassemble(offset, 1)
+        .first()
+        .map(|l| l.offset + l.bytes.len())
+    else {
+        return;
+    };
+    if next >= app.file_info.size {
+        return;
+    }
+
+    let lines = app.disassemble(app.disasm_view.top, app.disasm_view.height);
+    if lines.last().is_some_and(|l| l.offset == offset) && lines.len() > 1 {
+        app.disasm_view.top = lines[1].offset;
+    }
+    app.goto(next);
+}
+
+// move in the jump list, showing the instruction reached at the top
+fn jump(app: &mut App, step: fn(&mut App) -> bool) {
+    if step(app) {
+        app.disasm_view.top = app.hex_view.offset;
+    }
+}
+
+fn prev_line(app: &mut App) {
+    let offset = app.hex_view.offset;
+    if offset == 0 {
+        return;
+    }
+    let prev = app.disasm_prev(offset);
+    if offset == app.disasm_view.top {
+        app.disasm_view.top = prev;
+    }
+    app.goto(prev);
+}
+
+pub fn disasm_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        // jump list
+        KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::ALT) => {
+            crate::hex::jumps::open_jumps(app);
+        }
+        KeyCode::Down | KeyCode::Char('j') => next_line(app),
+        KeyCode::Up | KeyCode::Char('k') => prev_line(app),
+        KeyCode::PageDown => {
+            let lines = app.disassemble(app.disasm_view.top, app.disasm_view.height);
+            if let Some(last) = lines.last() {
+                let next = last.offset + last.bytes.len();
+                if next < app.file_info.size {
+                    app.disasm_view.top = next;
+                    app.goto(next);
+                }
+            }
+        }
+        KeyCode::PageUp => {
+            let mut top = app.disasm_view.top;
+            for _ in 0..app.disasm_view.height {
+                top = app.disasm_prev(top);
+            }
+            app.disasm_view.top = top;
+            app.goto(top);
+        }
+        KeyCode::Home if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.disasm_view.top = 0;
+            app.goto(0);
+        }
+        // follow the branch or the address used by the instruction
+        KeyCode::Enter => {
+            let offset = app.hex_view.offset;
+            let target = app
+                .disassemble(offset, 1)
+                .first()
+                .and_then(|l| l.target)
+                .and_then(|t| app.disasm_target_offset(offset, t));
+
+            match target {
+                Some(target) => {
+                    app.disasm_view.top = target;
+                    app.jump(target);
+                }
+                None => crate::beep!(),
+            }
+        }
+        // go back and forth in the jump list; Ctrl+i arrives as Tab
+        KeyCode::Backspace => jump(app, App::jump_back),
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            jump(app, App::jump_back)
+        }
+        KeyCode::Tab => jump(app, App::jump_forward),
+        // assemble an instruction at the cursor
+        KeyCode::Char('A') => super::assemble::open_assemble(app),
+        // cycle through the architectures
+        KeyCode::Char('a') => {
+            let arch = app.disasm_arch().map_or(Arch::ALL[0], |a| a.next());
+            app.disasm_view.arch = Some(arch);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
@@ -0,0 +1,313 @@
+pub mod arm;
+pub mod arm64;
+pub mod asm;
+pub mod assemble;
+pub mod draw;
+pub mod events;
+
+use iced_x86::{
+    Decoder, DecoderError, DecoderOptions, Formatter, Instruction, IntelFormatter, OpKind,
+};
+
+use tui_input::Input;
+
+use crate::app::App;
+
+// longest x86 instruction
+const MAX_INSN_LEN: usize = 15;
+// how far back to look for the previous x86 instruction
+const BACKTRACK: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arch {
+    X86_16,
+    X86_32,
+    X86_64,
+    Arm,
+    Thumb,
+    Arm64,
+}
+
+impl Arch {
+    pub const ALL: [Arch; 6] = [
+        Arch::X86_16,
+        Arch::X86_32,
+        Arch::X86_64,
+        Arch::Arm,
+        Arch::Thumb,
+        Arch::Arm64,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arch::X86_16 => "x86-16",
+            Arch::X86_32 => "x86",
+            Arch::X86_64 => "x86-64",
+            Arch::Arm => "ARM",
+            Arch::Thumb => "Thumb",
+            Arch::Arm64 => "AArch64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Arch> {
+        match name {
+            "x86-16" | "x16" | "16" => Some(Arch::X86_16),
+            "x86" | "x32" | "32" => Some(Arch::X86_32),
+            "x86-64" | "x64" | "64" => Some(Arch::X86_64),
+            "arm" | "arm32" | "a32" => Some(Arch::Arm),
+            "thumb" | "t32" => Some(Arch::Thumb),
+            "aarch64" | "arm64" => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+
+    /// Architecture of a machine named by the file parsers
+    pub fn from_machine(machine: &str) -> Option<Arch> {
+        match machine {
+            "x86" => Some(Arch::X86_32),
+            "x86-64" => Some(Arch::X86_64),
+            "ARM" => Some(Arch::Arm),
+            "ARM Thumb-2" => Some(Arch::Thumb),
+            "AArch64" => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> Arch {
+        let i = Arch::ALL.iter().position(|a| a == self).unwrap_or(0);
+        Arch::ALL[(i + 1) % Arch::ALL.len()]
+    }
+}
+
+/// A decoded instruction
+pub struct Line {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    pub text: String,
+    /// address a branch goes to, or a PC-relative load reads from
+    pub target: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct DisasmView {
+    /// architecture picked by the user, instead of the file's one
+    pub arch: Option<Arch>,
+    /// offset of the first instruction shown
+    pub top: usize,
+    /// instructions that fit in the screen
+    pub height: usize,
+    /// instruction typed in the assemble prompt
+    pub asm_input: Input,
+}
+
+/// Decode up to `count` instructions from `bytes`, found at `offset` in the
+/// file and at address `ip` in memory
+pub fn decode(arch: Arch, bytes: &[u8], offset: usize, ip: u64, count: usize) -> Vec<Line> {
+    let mut lines = Vec::with_capacity(count);
+
+    if let Arch::Arm | Arch::Arm64 = arch {
+        let decode_word = if arch == Arch::Arm {
+            arm::decode
+        } else {
+            arm64::decode
+        };
+        for (i, word) in bytes.chunks_exact(4).take(count).enumerate() {
+            let w = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            let (text, target) = decode_word(w, ip.wrapping_add(i as u64 * 4));
+            lines.push(Line {
+                offset: offset + i * 4,
+                bytes: word.to_vec(),
+                text,
+                target,
+            });
+        }
+        return lines;
+    }
+
+    // Thumb instructions take one halfword, or two when the first one says so
+    if arch == Arch::Thumb {
+        let mut pos = 0;
+        while pos + 2 <= bytes.len() && lines.len() < count {
+            let hw = u16::from_le_bytes([bytes[pos], bytes[pos + 1]]);
+            let pc = ip.wrapping_add(pos as u64);
+            let ((text, target), len) = match bytes.get(pos + 2..pos + 4) {
+                Some(next) if arm::is_thumb32(hw) => {
+                    let w = (hw as u32) << 16 | u16::from_le_bytes([next[0], next[1]]) as u32;
+                    (arm::decode_thumb32(w, pc), 4)
+                }
+                _ => (arm::decode_thumb(hw, pc), 2),
+            };
+            lines.push(Line {
+                offset: offset + pos,
+                bytes: bytes[pos..pos + len].to_vec(),
+                text,
+                target,
+            });
+            pos += len;
+        }
+        return lines;
+    }
+
+    let bitness = match arch {
+        Arch::X86_16 => 16,
+        Arch::X86_32 => 32,
+        _ => 64,
+    };
+    let mut decoder = Decoder::with_ip(bitness, bytes, ip, DecoderOptions::NONE);
+    let mut formatter = IntelFormatter::new();
+    formatter.options_mut().set_hex_prefix("0x");
+    formatter.options_mut().set_hex_suffix("");
+    formatter.options_mut().set_branch_leading_zeros(false);
+
+    let mut insn = Instruction::default();
+    let mut pos = 0;
+    while decoder.can_decode() && lines.len() < count {
+        decoder.decode_out(&mut insn);
+        let len = insn.len().max(1);
+
+        // stop at an instruction cut by the end of the buffer
+        if decoder.last_error() == DecoderError::NoMoreBytes && !lines.is_empty() {
+            break;
+        }
+
+        let mut text = String::new();
+        if insn.is_invalid() {
+            text.push_str("(bad)");
+        } else {
+            formatter.format(&insn, &mut text);
+        }
+        let target = match insn.op0_kind() {
+            OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64 => {
+                Some(insn.near_branch_target())
+            }
+            _ if insn.is_ip_rel_memory_operand() => Some(insn.ip_rel_memory_address()),
+            _ => None,
+        };
+
+        lines.push(Line {
+            offset: offset + pos,
+            bytes: bytes[pos..(pos + len).min(bytes.len())].to_vec(),
+            text,
+            target,
+        });
+        pos += len;
+    }
+    lines
+}
+
+impl App {
+    /// Architecture used to disassemble: the one picked by the user, the
+    /// file's one or x86-64 for files of no known format. None if the file
+    /// is for a machine that can't be disassembled.
+    pub fn disasm_arch(&self) -> Option<Arch> {
+        match (self.disasm_view.arch, &self.file_info.binary) {
+            (Some(arch), _) => Some(arch),
+            (None, Some(bin)) => Arch::from_machine(bin.machine),
+            (None, None) => Some(Arch::X86_64),
+        }
+    }
+
+    /// Bytes at `offset`, pending changes included
+    fn disasm_bytes(&mut self, offset: usize, len: usize) -> Vec<u8> {
+        (offset..offset.saturating_add(len))
+            .map_while(|ofs| self.read_changed_u8(ofs))
+            .collect()
+    }
+
+    /// Decode `count` instructions from `offset`
+    pub fn disassemble(&mut self, offset: usize, count: usize) -> Vec<Line> {
+        let Some(arch) = self.disasm_arch() else {
+            return Vec::new();
+        };
+        let bytes = self.disasm_bytes(offset, count * MAX_INSN_LEN);
+        let ip = self
+            .file_info
+            .binary
+            .as_ref()
+            .and_then(|b| b.offset_to_addr(offset))
+            .unwrap_or(offset as u64);
+        decode(arch, &bytes, offset, ip, count)
+    }
+
+    /// File offset of an address found in the code
+    pub fn disasm_target_offset(&self, offset: usize, target: u64) -> Option<usize> {
+        let ofs = match &self.file_info.binary {
+            Some(bin) if bin.offset_to_addr(offset).is_some() => bin.addr_to_offset(target)?,
+            _ => usize::try_from(target).ok()?,
+        };
+        (ofs < self.file_info.size).then_some(ofs)
+    }
+
+    /// Offset of the instruction before the one at `offset`. For x86, it's
+    /// the one found by decoding from the farthest point that lands on it.
+    /// For Thumb, it's 4 bytes back if the halfword there starts a 32-bit
+    /// instruction, and 2 otherwise.
+    pub fn disasm_prev(&mut self, offset: usize) -> usize {
+        match self.disasm_arch() {
+            Some(Arch::Arm | Arch::Arm64) => return offset.saturating_sub(4),
+            Some(Arch::Thumb) => {
+                let wide = offset >= 4
+                    && matches!(self.disasm_bytes(offset - 4, 2)[..], [lo, hi]
+                        if arm::is_thumb32(u16::from_le_bytes([lo, hi])));
+                return offset.saturating_sub(if wide { 4 } else { 2 });
+            }
+            _ => {}
+        }
+
+        let start = offset.saturating_sub(BACKTRACK);
+        for from in start..offset {
+            let lines = self.disassemble(from, offset - from);
+            if let Some(i) = lines.iter().position(|l| l.offset == offset)
+                && i > 0
+            {
+                return lines[i - 1].offset;
+            }
+            if let Some(last) = lines.last()
+                && last.offset + last.bytes.len() == offset
+            {
+                return last.offset;
+            }
+        }
+        offset.saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_x86_test() {
+        // push rbp; mov rbp, rsp; call $+5; ret
+        let code = [0x55, 0x48, 0x89, 0xe5, 0xe8, 0x00, 0x00, 0x00, 0x00, 0xc3];
+        let lines = decode(Arch::X86_64, &code, 0x400, 0x401000, 10);
+        let text: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(text, ["push rbp", "mov rbp,rsp", "call 0x401009", "ret"]);
+        assert_eq!(lines[2].offset, 0x404);
+        assert_eq!(lines[2].target, Some(0x401009));
+
+        let lines = decode(Arch::X86_32, &[0xff, 0xff, 0x90], 0, 0, 10);
+        assert_eq!(lines[0].text, "(bad)");
+        assert_eq!(lines.last().map(|l| l.text.as_str()), Some("nop"));
+    }
+
+    #[test]
+    fn decode_arm_test() {
+        // push {r4, lr}; bl; pop {r4, pc}
+        let code = [0x10, 0xb5, 0x00, 0xf0, 0x02, 0xf8, 0x10, 0xbd];
+        let lines = decode(Arch::Thumb, &code, 0, 0x8000, 10);
+        let text: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(text, ["push {r4, lr}", "bl 0x800A", "pop {r4, pc}"]);
+        assert_eq!(lines[2].offset, 6);
+        assert_eq!(lines[1].target, Some(0x800a));
+
+        let lines = decode(Arch::Arm, &[0x02, 0x00, 0x00, 0xeb], 0, 0x8000, 10);
+        assert_eq!(lines[0].text, "bl 0x8010");
+
+        // addresses wrap around instead of overflowing
+        let nops = [0x1f, 0x20, 0x03, 0xd5].repeat(2);
+        let lines = decode(Arch::Arm64, &nops, 0, u64::MAX - 3, 10);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(Arch::from_machine("ARM Thumb-2"), Some(Arch::Thumb));
+    }
+}
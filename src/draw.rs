@@ -2,6 +2,7 @@ use ratatui::{Frame, prelude::*, widgets::Paragraph};
 
 use crate::{
     app::App,
+    disasm,
     editor::AppView,
     global,
     hex::{self, comment},
@@ -20,6 +21,8 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         return;
     }
 
+    app.update_addr_width();
+
     // Draw things depending on the view
     match app.editor_view {
         AppView::Hex => {
@@ -43,20 +46,48 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
 
             app.command_area = vertical_layout[3];
 
+            let mut constraints = vec![
+                Constraint::Length(app.addr_width() as u16 + 2),
+                Constraint::Length((app.config.hex_mode_bytes_per_line * 3 + 2) as u16),
+                Constraint::Min(app.config.hex_mode_bytes_per_line as u16),
+            ];
+            // the inspector takes what's left after the ASCII dump
+            if app.hex_view.inspector.visible {
+                constraints[2] = Constraint::Length(app.config.hex_mode_bytes_per_line as u16 + 1);
+                constraints.push(Constraint::Min(0));
+            }
+
             let horizontal_layout = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints(vec![
-                    Constraint::Length(10),
-                    Constraint::Length((app.config.hex_mode_bytes_per_line * 3 + 2) as u16),
-                    Constraint::Min(app.config.hex_mode_bytes_per_line as u16),
-                ])
+                .constraints(constraints)
                 .split(vertical_layout[1]);
 
             hex::draw::draw_hex_offsets(app, frame, horizontal_layout[0]);
             hex::draw::draw_hex_contents(app, frame, horizontal_layout[1]);
             hex::draw::draw_hex_ascii(app, frame, horizontal_layout[2]);
+            if app.hex_view.inspector.visible {
+                hex::inspector::inspector_draw(app, frame, horizontal_layout[3]);
+            }
             comment::comment_show_draw(app, frame);
         }
+        AppView::Disasm => {
+            let constraints = vec![
+                Constraint::Length(1),       // header
+                Constraint::Percentage(100), // middle area (instructions)
+                Constraint::Length(1),       // status bar
+                Constraint::Length(1),       // command bar
+            ];
+
+            let vertical_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(constraints)
+                .split(frame.area());
+
+            disasm::draw::disasm_header_draw(app, frame, vertical_layout[0]);
+            global::status_bar::status_bar_draw(app, frame, vertical_layout[2]);
+            app.command_area = vertical_layout[3];
+            disasm::draw::disasm_contents_draw(app, frame, vertical_layout[1]);
+        }
         AppView::Text => {
             let constraints = vec![
                 Constraint::Percentage(100), // middle area (text content)
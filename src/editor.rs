@@ -2,6 +2,7 @@
 pub enum AppView {
     Text,
     Hex,
+    Disasm,
 }
 
 impl AppView {
@@ -9,6 +10,7 @@ impl AppView {
         match self {
             AppView::Text => *self = AppView::Hex,
             AppView::Hex => *self = AppView::Text,
+            AppView::Disasm => *self = AppView::Hex,
         }
     }
 }
@@ -16,16 +18,25 @@ impl AppView {
 #[derive(PartialEq)]
 pub enum UIState {
     Command,
+    DialogAssemble,
     DialogCalculator,
+    DialogChanges,
     DialogComment,
     DialogEncoding,
+    DialogFindAll,
     DialogHelp,
+    DialogInspector,
+    DialogJumps,
     DialogLog,
+    DialogMarks,
     DialogNames,
     DialogNamesRegex,
     DialogSearch,
+    DialogSlices,
     DialogStrings,
+    DialogStructure,
     DialogStringsRegex,
+    DialogYank,
     Error,
     HexEditing,
     HexSelection,
@@ -1,8 +1,10 @@
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use std::io::Result;
+use std::time::Duration;
 
 use crate::app::App;
 use crate::commands;
+use crate::disasm;
 use crate::editor::{AppView, UIState};
 use crate::global;
 use crate::hex;
@@ -20,15 +22,24 @@ pub fn handle_dialog_error_events(app: &mut App, key: KeyEvent) -> Result<bool>
 }
 
 pub fn handle_events(app: &mut App) -> Result<bool> {
+    // a find all in progress goes on while no key is pressed
+    if app.hex_view.find_all.is_searching() && !event::poll(Duration::ZERO)? {
+        hex::find_all::find_all_step(app);
+        return Ok(false);
+    }
     let event = event::read()?;
     match event {
         Event::Key(key) if key.kind == KeyEventKind::Press => {
             match app.state {
                 UIState::Normal | UIState::Error => {
-                    global::events::handle_global_events(app, key)?;
+                    // the key completes a command started in the view, like `ma`
+                    if app.hex_view.pending_key.is_none() {
+                        global::events::handle_global_events(app, key)?;
+                    }
                     match app.editor_view {
                         AppView::Hex => hex::events::hex_mode_events(app, key)?,
                         AppView::Text => text::events::text_mode_events(app, key)?,
+                        AppView::Disasm => disasm::events::disasm_mode_events(app, key)?,
                     }
                 }
                 UIState::DialogHelp => handle_dialog_error_events(app, key)?,
@@ -41,19 +52,35 @@ pub fn handle_events(app: &mut App) -> Result<bool> {
                 UIState::DialogStringsRegex => {
                     hex::strings::dialog_strings_regex_events(app, &event)?
                 }
+                UIState::DialogStructure => hex::structure::dialog_structure_events(app, &event)?,
+                UIState::DialogSlices => hex::structure::dialog_slices_events(app, key)?,
                 UIState::DialogLog => global::log::dialog_log_events(app, key)?,
+                UIState::DialogAssemble => disasm::assemble::dialog_assemble_events(app, &event)?,
+                UIState::DialogInspector => hex::inspector::dialog_inspector_events(app, &event)?,
                 UIState::DialogComment => hex::comment::dialog_comment_events(app, &event)?,
+                UIState::DialogChanges => hex::changes::dialog_changes_events(app, &event)?,
+                UIState::DialogFindAll => hex::find_all::dialog_find_all_events(app, &event)?,
+                UIState::DialogJumps => hex::jumps::dialog_jumps_events(app, &event)?,
+                UIState::DialogMarks => hex::marks::dialog_marks_events(app, &event)?,
                 UIState::DialogNames => hex::names::dialog_names_events(app, &event)?,
                 UIState::DialogNamesRegex => hex::names::dialog_names_regex_events(app, &event)?,
+                UIState::DialogYank => hex::clipboard::dialog_yank_events(app, key)?,
                 UIState::DialogCalculator => {
                     global::calculator::dialog_calculator_events(app, &event)?
                 }
             };
         }
         Event::Resize(width, _height) => {
+            // the panel is hidden when it no longer fits
+            if app.hex_view.inspector.visible && !hex::inspector::Inspector::fits(width) {
+                app.hex_view.inspector.visible = false;
+                if app.state == UIState::DialogInspector {
+                    app.state = UIState::Normal;
+                    app.dialog_renderer = None;
+                }
+            }
             if app.config.hex_mode_bytes_per_line_auto {
-                let max = ((width - 9) / 4) as usize;
-                app.config.hex_mode_bytes_per_line = max - 1;
+                app.config.hex_mode_bytes_per_line = app.hex_view.inspector.bytes_per_line(width);
             }
         }
         _ => {}
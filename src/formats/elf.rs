@@ -0,0 +1,528 @@
+use std::collections::HashSet;
+
+use crate::formats::{Binary, Data, Field, Region, Symbol, hex};
+
+pub const MAGIC: &[u8] = b"\x7fELF";
+
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+const SHN_LORESERVE: u16 = 0xff00;
+const STT_SECTION: u8 = 3;
+const STT_FILE: u8 = 4;
+const EM_ARM: u16 = 40;
+
+// symbol tables can be huge; past this, symbols are not listed
+const MAX_SYMBOLS: usize = 200_000;
+
+struct Section {
+    name: String,
+    kind: u32,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    entsize: u64,
+}
+
+fn file_type(t: u16) -> &'static str {
+    match t {
+        1 => "REL (relocatable)",
+        2 => "EXEC (executable)",
+        3 => "DYN (shared object)",
+        4 => "CORE (core dump)",
+        _ => "unknown",
+    }
+}
+
+pub fn machine(m: u16) -> &'static str {
+    match m {
+        3 => "x86",
+        8 => "MIPS",
+        20 => "PowerPC",
+        21 => "PowerPC64",
+        40 => "ARM",
+        62 => "x86-64",
+        183 => "AArch64",
+        243 => "RISC-V",
+        _ => "unknown",
+    }
+}
+
+fn segment_type(t: u32) -> String {
+    match t {
+        0 => "NULL".to_string(),
+        1 => "LOAD".to_string(),
+        2 => "DYNAMIC".to_string(),
+        3 => "INTERP".to_string(),
+        4 => "NOTE".to_string(),
+        5 => "SHLIB".to_string(),
+        6 => "PHDR".to_string(),
+        7 => "TLS".to_string(),
+        0x6474e550 => "GNU_EH_FRAME".to_string(),
+        0x6474e551 => "GNU_STACK".to_string(),
+        0x6474e552 => "GNU_RELRO".to_string(),
+        0x6474e553 => "GNU_PROPERTY".to_string(),
+        t => format!("0x{:08X}", t),
+    }
+}
+
+fn section_type(t: u32) -> String {
+    match t {
+        0 => "NULL".to_string(),
+        1 => "PROGBITS".to_string(),
+        2 => "SYMTAB".to_string(),
+        3 => "STRTAB".to_string(),
+        4 => "RELA".to_string(),
+        5 => "HASH".to_string(),
+        6 => "DYNAMIC".to_string(),
+        7 => "NOTE".to_string(),
+        8 => "NOBITS".to_string(),
+        9 => "REL".to_string(),
+        11 => "DYNSYM".to_string(),
+        14 => "INIT_ARRAY".to_string(),
+        15 => "FINI_ARRAY".to_string(),
+        0x6ffffff6 => "GNU_HASH".to_string(),
+        0x6ffffffe => "VERNEED".to_string(),
+        0x6fffffff => "VERSYM".to_string(),
+        t => format!("0x{:08X}", t),
+    }
+}
+
+fn segment_flags(f: u32) -> String {
+    [(4, 'R'), (2, 'W'), (1, 'X')]
+        .iter()
+        .map(|&(bit, c)| if f & bit != 0 { c } else { '-' })
+        .collect()
+}
+
+pub fn parse(bytes: &[u8]) -> Option<Binary> {
+    if !bytes.starts_with(MAGIC) {
+        return None;
+    }
+    let is64 = match bytes.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = match bytes.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let d = Data::new(bytes, big_endian);
+    let w = if is64 { 8 } else { 4 };
+
+    // header fields after e_entry are shifted by the word size
+    let e_type = d.u16(16)?;
+    let e_machine = d.u16(18)?;
+    let e_entry = d.word(24, is64)?;
+    let e_phoff = d.word(24 + w, is64)? as usize;
+    let e_shoff = d.word(24 + 2 * w, is64)? as usize;
+    let rest = 24 + 3 * w;
+    let e_flags = d.u32(rest)?;
+    let e_phentsize = d.u16(rest + 6)? as usize;
+    let e_phnum = d.u16(rest + 8)? as usize;
+    let e_shentsize = d.u16(rest + 10)? as usize;
+    let e_shnum = d.u16(rest + 12)? as usize;
+    let e_shstrndx = d.u16(rest + 14)? as usize;
+
+    let mut bin = Binary {
+        format: if is64 { "ELF64" } else { "ELF32" },
+        machine: machine(e_machine),
+        ..Default::default()
+    };
+
+    // program headers; a bad one ends the table, keeping those before it
+    let mut phdrs = Vec::new();
+    for i in 0..e_phnum {
+        let Some(p) = i
+            .checked_mul(e_phentsize)
+            .and_then(|o| o.checked_add(e_phoff))
+            .filter(|&p| p < bytes.len())
+        else {
+            break;
+        };
+        let header = if is64 {
+            (|| {
+                Some((
+                    d.u32(p)?,
+                    d.u32(p + 4)?,
+                    d.u64(p + 8)?,
+                    d.u64(p + 16)?,
+                    d.u64(p + 32)?,
+                    d.u64(p + 40)?,
+                ))
+            })()
+        } else {
+            (|| {
+                Some((
+                    d.u32(p)?,
+                    d.u32(p + 24)?,
+                    d.u32(p + 4)? as u64,
+                    d.u32(p + 8)? as u64,
+                    d.u32(p + 16)? as u64,
+                    d.u32(p + 20)? as u64,
+                ))
+            })()
+        };
+        let Some((kind, flags, offset, vaddr, filesz, memsz)) = header else {
+            break;
+        };
+        phdrs.push((p, kind, flags, offset, vaddr, filesz, memsz));
+
+        if kind == PT_LOAD && filesz > 0 {
+            bin.segments.push(Region {
+                name: format!("LOAD{}", bin.segments.len()),
+                offset: offset as usize,
+                size: filesz as usize,
+                addr: vaddr,
+            });
+        }
+    }
+
+    // base of the lowest loaded page
+    bin.image_base = bin.segments.iter().map(|s| s.addr).min().unwrap_or(0) & !0xfff;
+
+    // section headers; as above, a bad one ends the table
+    let mut sections = Vec::new();
+    if e_shoff != 0 {
+        for i in 0..e_shnum {
+            let Some(s) = i
+                .checked_mul(e_shentsize)
+                .and_then(|o| o.checked_add(e_shoff))
+                .filter(|&s| s < bytes.len())
+            else {
+                break;
+            };
+            let section = (|| {
+                let section = if is64 {
+                    Section {
+                        name: String::new(),
+                        kind: d.u32(s + 4)?,
+                        addr: d.u64(s + 16)?,
+                        offset: d.u64(s + 24)?,
+                        size: d.u64(s + 32)?,
+                        link: d.u32(s + 40)?,
+                        entsize: d.u64(s + 56)?,
+                    }
+                } else {
+                    Section {
+                        name: String::new(),
+                        kind: d.u32(s + 4)?,
+                        addr: d.u32(s + 12)? as u64,
+                        offset: d.u32(s + 16)? as u64,
+                        size: d.u32(s + 20)? as u64,
+                        link: d.u32(s + 24)?,
+                        entsize: d.u32(s + 36)? as u64,
+                    }
+                };
+                Some((d.u32(s)? as usize, section))
+            })();
+            let Some(section) = section else {
+                break;
+            };
+            sections.push(section);
+        }
+    }
+
+    // section names come from the section header string table
+    let shstrtab = sections.get(e_shstrndx).map(|(_, s)| s.offset as usize);
+    let sections: Vec<Section> = sections
+        .into_iter()
+        .map(|(name, mut s)| {
+            if let Some(table) = shstrtab {
+                s.name = table
+                    .checked_add(name)
+                    .and_then(|o| d.cstr(o, 256))
+                    .unwrap_or_default();
+            }
+            s
+        })
+        .collect();
+
+    for s in &sections {
+        if s.kind != SHT_NOBITS && s.size > 0 && !s.name.is_empty() {
+            bin.sections.push(Region {
+                name: s.name.clone(),
+                offset: s.offset as usize,
+                size: s.size as usize,
+                addr: s.addr,
+            });
+        }
+    }
+
+    // ELF header
+    bin.fields.push(Field::new(0, "ELF header", "").at(0));
+    bin.fields
+        .push(Field::new(1, "Class", if is64 { "ELF64" } else { "ELF32" }).at(4));
+    bin.fields.push(
+        Field::new(
+            1,
+            "Data",
+            if big_endian {
+                "big-endian"
+            } else {
+                "little-endian"
+            },
+        )
+        .at(5),
+    );
+    bin.fields
+        .push(Field::new(1, "Type", file_type(e_type)).at(16));
+    bin.fields
+        .push(Field::new(1, "Machine", machine(e_machine)).at(18));
+    let mut entry = Field::new(1, "Entry point", hex(e_entry, is64));
+    entry.offset = bin.addr_to_offset(e_entry);
+    bin.fields.push(entry);
+    bin.fields
+        .push(Field::new(1, "Flags", format!("0x{:X}", e_flags)).at(rest));
+    bin.fields
+        .push(Field::new(1, "Program headers offset", format!("0x{:X}", e_phoff)).at(24 + w));
+    bin.fields
+        .push(Field::new(1, "Section headers offset", format!("0x{:X}", e_shoff)).at(24 + 2 * w));
+
+    bin.fields
+        .push(Field::new(0, format!("Program headers ({})", phdrs.len()), "").at(e_phoff));
+    for (p, kind, flags, offset, vaddr, filesz, memsz) in phdrs {
+        bin.fields.push(
+            Field::new(
+                1,
+                segment_type(kind),
+                format!(
+                    "{} offset 0x{:X} vaddr {} filesz 0x{:X} memsz 0x{:X}",
+                    segment_flags(flags),
+                    offset,
+                    hex(vaddr, is64),
+                    filesz,
+                    memsz
+                ),
+            )
+            .at(p),
+        );
+        bin.fields
+            .push(Field::new(2, "Segment start", format!("0x{:X}", offset)).at(offset as usize));
+    }
+
+    bin.fields
+        .push(Field::new(0, format!("Section headers ({})", sections.len()), "").at(e_shoff));
+    for (i, s) in sections.iter().enumerate() {
+        let mut field = Field::new(
+            1,
+            if s.name.is_empty() {
+                format!("[{}]", i)
+            } else {
+                s.name.clone()
+            },
+            format!(
+                "{} addr {} offset 0x{:X} size 0x{:X}",
+                section_type(s.kind),
+                hex(s.addr, is64),
+                s.offset,
+                s.size
+            ),
+        );
+        // sections without bytes in the file point to their header
+        field.offset = Some(if s.kind != SHT_NOBITS && s.size > 0 {
+            s.offset as usize
+        } else {
+            e_shoff + i * e_shentsize
+        });
+        bin.fields.push(field);
+    }
+
+    // symbol tables
+    let mut seen = HashSet::new();
+    for s in &sections {
+        if (s.kind != SHT_SYMTAB && s.kind != SHT_DYNSYM) || s.entsize == 0 {
+            continue;
+        }
+        let Some(strtab) = sections.get(s.link as usize).map(|t| t.offset as usize) else {
+            continue;
+        };
+
+        let count = (s.size / s.entsize) as usize;
+        bin.fields.push(
+            Field::new(0, format!("Symbols {} ({})", s.name, count), "").at(s.offset as usize),
+        );
+
+        for i in 0..count.min(MAX_SYMBOLS) {
+            // a truncated table ends the list
+            let Some(e) = i
+                .checked_mul(s.entsize as usize)
+                .and_then(|o| o.checked_add(s.offset as usize))
+                .filter(|&e| e < bytes.len())
+            else {
+                break;
+            };
+            let entry = if is64 {
+                (|| Some((d.u32(e)?, d.u64(e + 8)?, d.u8(e + 4)?, d.u16(e + 6)?)))()
+            } else {
+                (|| {
+                    Some((
+                        d.u32(e)?,
+                        d.u32(e + 4)? as u64,
+                        d.u8(e + 12)?,
+                        d.u16(e + 14)?,
+                    ))
+                })()
+            };
+            let Some((name, value, info, shndx)) = entry else {
+                break;
+            };
+            let name = strtab
+                .checked_add(name as usize)
+                .and_then(|o| d.cstr(o, 1024))
+                .unwrap_or_default();
+            let kind = info & 0xf;
+            if name.is_empty() || kind == STT_SECTION || kind == STT_FILE {
+                continue;
+            }
+
+            // bit 0 of ARM addresses selects Thumb mode
+            let value = if e_machine == EM_ARM {
+                value & !1
+            } else {
+                value
+            };
+
+            // only symbols defined in sections with bytes in the file have an offset
+            let offset = match sections.get(shndx as usize) {
+                Some(sec)
+                    if shndx != 0
+                        && shndx < SHN_LORESERVE
+                        && sec.kind != SHT_NOBITS
+                        && value >= sec.addr
+                        && value - sec.addr < sec.size.max(1) =>
+                {
+                    sec.offset.checked_add(value - sec.addr).map(|o| o as usize)
+                }
+                _ => None,
+            };
+
+            let mut field = Field::new(1, &name, hex(value, is64));
+            field.offset = offset;
+            bin.fields.push(field);
+
+            if let Some(offset) = offset
+                && offset < bytes.len()
+                && seen.insert((offset, name.clone()))
+            {
+                bin.symbols.push(Symbol { name, offset });
+            }
+        }
+    }
+
+    Some(bin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a minimal little-endian ELF64 with a .text section and a symbol table
+    fn sample() -> Vec<u8> {
+        let mut b = vec![0u8; 0x280];
+        b[..4].copy_from_slice(MAGIC);
+        b[4] = 2; // ELF64
+        b[5] = 1; // little-endian
+        b[6] = 1;
+        let put16 =
+            |b: &mut Vec<u8>, o: usize, v: u16| b[o..o + 2].copy_from_slice(&v.to_le_bytes());
+        let put32 =
+            |b: &mut Vec<u8>, o: usize, v: u32| b[o..o + 4].copy_from_slice(&v.to_le_bytes());
+        let put64 =
+            |b: &mut Vec<u8>, o: usize, v: u64| b[o..o + 8].copy_from_slice(&v.to_le_bytes());
+
+        put16(&mut b, 16, 2); // EXEC
+        put16(&mut b, 18, 62); // x86-64
+        put64(&mut b, 24, 0x401010); // entry
+        put64(&mut b, 32, 0x40); // phoff
+        put64(&mut b, 40, 0x100); // shoff
+        put16(&mut b, 54, 56);
+        put16(&mut b, 56, 1);
+        put16(&mut b, 58, 64);
+        put16(&mut b, 60, 4);
+        put16(&mut b, 62, 3); // shstrndx
+
+        // LOAD segment mapping the whole file at 0x400000
+        put32(&mut b, 0x40, PT_LOAD);
+        put32(&mut b, 0x44, 5);
+        put64(&mut b, 0x48, 0);
+        put64(&mut b, 0x50, 0x400000);
+        put64(&mut b, 0x60, 0x200);
+        put64(&mut b, 0x68, 0x200);
+
+        // sections: NULL, .text, .symtab, .shstrtab (also used as .strtab)
+        let sh = |i: usize| 0x100 + i * 64;
+        put32(&mut b, sh(1), 1);
+        put32(&mut b, sh(1) + 4, 1);
+        put64(&mut b, sh(1) + 16, 0x401000);
+        put64(&mut b, sh(1) + 24, 0x80);
+        put64(&mut b, sh(1) + 32, 0x40);
+
+        put32(&mut b, sh(2), 7);
+        put32(&mut b, sh(2) + 4, SHT_SYMTAB);
+        put64(&mut b, sh(2) + 24, 0x200);
+        put64(&mut b, sh(2) + 32, 48);
+        put32(&mut b, sh(2) + 40, 3);
+        put64(&mut b, sh(2) + 56, 24);
+
+        put32(&mut b, sh(3), 15);
+        put32(&mut b, sh(3) + 4, 3);
+        put64(&mut b, sh(3) + 24, 0x240);
+        put64(&mut b, sh(3) + 32, 0x20);
+        b[0x240..0x240 + 30].copy_from_slice(b"\0.text\0.symtab\0.shstrtab\0main\0");
+
+        // symbol 1: main at 0x401010 in .text
+        put32(&mut b, 0x200 + 24, 25);
+        b[0x200 + 24 + 4] = 0x12; // GLOBAL FUNC
+        put16(&mut b, 0x200 + 24 + 6, 1);
+        put64(&mut b, 0x200 + 24 + 8, 0x401010);
+        b
+    }
+
+    #[test]
+    fn parse_elf_test() {
+        let b = sample();
+        let bin = parse(&b).unwrap();
+        assert_eq!(bin.format, "ELF64");
+        assert_eq!(bin.section_at(0x90), Some(".text"));
+        assert_eq!(bin.section_at(0x10), None);
+        assert_eq!(bin.addr_to_offset(0x400010), Some(0x10));
+        assert_eq!(bin.addr_to_offset(0x401010), Some(0x90));
+        assert_eq!(bin.offset_to_addr(0x10), Some(0x400010));
+        assert_eq!(bin.image_base, 0x400000);
+        assert_eq!(bin.symbols.len(), 1);
+        assert_eq!(bin.symbols[0].name, "main");
+        assert_eq!(bin.symbols[0].offset, 0x90);
+
+        // truncated files are rejected instead of panicking
+        assert!(parse(&b[..0x30]).is_none());
+        assert!(parse(b"\x7fELF").is_none());
+
+        // bad headers past the end of the file keep the ones before them
+        let mut b = sample();
+        b[60] = 200; // e_shnum
+        b[56] = 200; // e_phnum
+        let bin = parse(&b).unwrap();
+        assert_eq!(bin.section_at(0x90), Some(".text"));
+        assert_eq!(bin.segments.len(), 1);
+        assert!(parse(&sample()[..0x40]).is_some());
+
+        // offsets near the top of the address space are skipped
+        let corrupt = |o: usize| {
+            let mut b = sample();
+            b[o..o + 8].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+            parse(&b).unwrap()
+        };
+        assert!(corrupt(0x100 + 3 * 64 + 24).sections.is_empty()); // .shstrtab
+        assert!(corrupt(0x100 + 2 * 64 + 24).symbols.is_empty()); // .symtab
+        let bin = corrupt(0x100 + 64 + 24); // .text
+        assert!(bin.symbols.is_empty());
+        assert_eq!(bin.section_at(usize::MAX - 1), Some(".text"));
+        assert_eq!(bin.addr_to_offset(0x401010), None);
+        assert!(corrupt(40).sections.is_empty()); // e_shoff
+    }
+}
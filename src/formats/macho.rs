@@ -0,0 +1,615 @@
+use std::collections::HashSet;
+
+use crate::formats::{Binary, Data, Field, Region, Symbol, hex};
+
+const MH_MAGIC: u32 = 0xfeedface;
+const MH_MAGIC_64: u32 = 0xfeedfacf;
+const FAT_MAGIC: u32 = 0xcafebabe;
+const FAT_MAGIC_64: u32 = 0xcafebabf;
+// Java class files share the fat magic, but their next field is a version
+// number way bigger than any real number of slices
+const MAX_FAT_ARCHS: u32 = 30;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SYMTAB: u32 = 0x2;
+const LC_DYSYMTAB: u32 = 0xb;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_ID_DYLIB: u32 = 0xd;
+const LC_LOAD_DYLINKER: u32 = 0xe;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_UUID: u32 = 0x1b;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+const LC_FUNCTION_STARTS: u32 = 0x26;
+const LC_DYLD_INFO: u32 = 0x22;
+const LC_BUILD_VERSION: u32 = 0x32;
+const LC_REQ_DYLD: u32 = 0x80000000;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x18 | LC_REQ_DYLD;
+const LC_REEXPORT_DYLIB: u32 = 0x1f | LC_REQ_DYLD;
+const LC_DYLD_INFO_ONLY: u32 = LC_DYLD_INFO | LC_REQ_DYLD;
+const LC_MAIN: u32 = 0x28 | LC_REQ_DYLD;
+const LC_DYLD_EXPORTS_TRIE: u32 = 0x33 | LC_REQ_DYLD;
+const LC_DYLD_CHAINED_FIXUPS: u32 = 0x34 | LC_REQ_DYLD;
+
+const N_STAB: u8 = 0xe0;
+const N_TYPE: u8 = 0x0e;
+const N_SECT: u8 = 0x0e;
+
+// section types without bytes in the file
+const ZEROFILL_TYPES: [u32; 3] = [0x1, 0xc, 0x12];
+
+// limits against malformed files
+const MAX_COMMANDS: usize = 10_000;
+const MAX_SYMBOLS: usize = 200_000;
+
+/// Returns true if `bytes` start with any of the Mach-O or fat magics
+pub fn is_macho(bytes: &[u8]) -> bool {
+    let Some(magic) = Data::new(bytes, true).u32(0) else {
+        return false;
+    };
+    match magic {
+        MH_MAGIC | MH_MAGIC_64 => true,
+        _ if magic.swap_bytes() == MH_MAGIC || magic.swap_bytes() == MH_MAGIC_64 => true,
+        FAT_MAGIC | FAT_MAGIC_64 => fat_archs(bytes).is_some(),
+        _ => false,
+    }
+}
+
+pub fn cpu(cputype: u32) -> &'static str {
+    match cputype {
+        7 => "x86",
+        0x01000007 => "x86-64",
+        12 => "ARM",
+        0x0100000c => "AArch64",
+        0x0200000c => "ARM64_32",
+        18 => "PowerPC",
+        0x01000012 => "PowerPC64",
+        _ => "unknown",
+    }
+}
+
+fn file_type(t: u32) -> &'static str {
+    match t {
+        1 => "OBJECT",
+        2 => "EXECUTE",
+        3 => "FVMLIB",
+        4 => "CORE",
+        5 => "PRELOAD",
+        6 => "DYLIB",
+        7 => "DYLINKER",
+        8 => "BUNDLE",
+        9 => "DYLIB_STUB",
+        10 => "DSYM",
+        11 => "KEXT_BUNDLE",
+        _ => "unknown",
+    }
+}
+
+fn command_name(cmd: u32) -> String {
+    match cmd {
+        LC_SEGMENT => "LC_SEGMENT".to_string(),
+        LC_SYMTAB => "LC_SYMTAB".to_string(),
+        LC_DYSYMTAB => "LC_DYSYMTAB".to_string(),
+        LC_LOAD_DYLIB => "LC_LOAD_DYLIB".to_string(),
+        LC_ID_DYLIB => "LC_ID_DYLIB".to_string(),
+        LC_LOAD_DYLINKER => "LC_LOAD_DYLINKER".to_string(),
+        LC_SEGMENT_64 => "LC_SEGMENT_64".to_string(),
+        LC_UUID => "LC_UUID".to_string(),
+        LC_CODE_SIGNATURE => "LC_CODE_SIGNATURE".to_string(),
+        LC_FUNCTION_STARTS => "LC_FUNCTION_STARTS".to_string(),
+        LC_DYLD_INFO => "LC_DYLD_INFO".to_string(),
+        LC_DYLD_INFO_ONLY => "LC_DYLD_INFO_ONLY".to_string(),
+        LC_BUILD_VERSION => "LC_BUILD_VERSION".to_string(),
+        LC_LOAD_WEAK_DYLIB => "LC_LOAD_WEAK_DYLIB".to_string(),
+        LC_REEXPORT_DYLIB => "LC_REEXPORT_DYLIB".to_string(),
+        LC_MAIN => "LC_MAIN".to_string(),
+        LC_DYLD_EXPORTS_TRIE => "LC_DYLD_EXPORTS_TRIE".to_string(),
+        LC_DYLD_CHAINED_FIXUPS => "LC_DYLD_CHAINED_FIXUPS".to_string(),
+        cmd => format!("0x{:X}", cmd),
+    }
+}
+
+fn protection(p: u32) -> String {
+    [(1, 'r'), (2, 'w'), (4, 'x')]
+        .iter()
+        .map(|&(bit, c)| if p & bit != 0 { c } else { '-' })
+        .collect()
+}
+
+fn fixed_str(d: &Data, offset: usize) -> String {
+    d.cstr(offset, 16).unwrap_or_default()
+}
+
+// whether a segment or section read from the file lies within `bytes`,
+// without its addresses wrapping around
+fn fits_in(bytes: &[u8], offset: usize, size: usize, addr: u64) -> bool {
+    offset
+        .checked_add(size)
+        .is_some_and(|end| end <= bytes.len())
+        && addr.checked_add(size as u64).is_some()
+}
+
+/// (cputype, offset, size) of each slice of a fat binary
+fn fat_archs(bytes: &[u8]) -> Option<Vec<(u32, usize, usize)>> {
+    let d = Data::new(bytes, true);
+    let is64 = match d.u32(0)? {
+        FAT_MAGIC => false,
+        FAT_MAGIC_64 => true,
+        _ => return None,
+    };
+    let n = d.u32(4)?;
+    if n == 0 || n > MAX_FAT_ARCHS {
+        return None;
+    }
+
+    let mut archs = Vec::with_capacity(n as usize);
+    for i in 0..n as usize {
+        let (a, cputype) = if is64 {
+            let a = 8 + i * 32;
+            (a, d.u32(a)?)
+        } else {
+            let a = 8 + i * 20;
+            (a, d.u32(a)?)
+        };
+        let (offset, size) = if is64 {
+            (d.u64(a + 8)? as usize, d.u64(a + 16)? as usize)
+        } else {
+            (d.u32(a + 8)? as usize, d.u32(a + 12)? as usize)
+        };
+        if offset.checked_add(size)? > bytes.len() {
+            return None;
+        }
+        archs.push((cputype, offset, size));
+    }
+    Some(archs)
+}
+
+/// Parse a Mach-O file. For fat binaries, `slice` selects the architecture
+/// whose headers are parsed; its offsets are turned into offsets in the
+/// whole file.
+pub fn parse(bytes: &[u8], slice: usize) -> Option<Binary> {
+    let Some(archs) = fat_archs(bytes) else {
+        return parse_thin(bytes);
+    };
+
+    let slice = slice.min(archs.len() - 1);
+    let (_, offset, size) = archs[slice];
+    let mut bin = parse_thin(&bytes[offset..offset + size]).unwrap_or_default();
+    bin.shift(offset);
+
+    let mut fields = vec![Field::new(0, format!("Fat header ({} slices)", archs.len()), "").at(0)];
+    for (i, &(cputype, offset, size)) in archs.iter().enumerate() {
+        let marker = if i == slice { " (selected)" } else { "" };
+        fields.push(
+            Field::new(
+                1,
+                format!("{}{}", cpu(cputype), marker),
+                format!("offset 0x{:X} size 0x{:X}", offset, size),
+            )
+            .at(offset),
+        );
+        bin.slices.push(Region {
+            name: cpu(cputype).to_string(),
+            offset,
+            size,
+            addr: 0,
+        });
+    }
+    fields.append(&mut bin.fields);
+    bin.fields = fields;
+    bin.format = "Mach-O fat";
+    bin.slice = slice;
+    Some(bin)
+}
+
+fn parse_thin(bytes: &[u8]) -> Option<Binary> {
+    let (is64, big_endian) = match Data::new(bytes, true).u32(0)? {
+        MH_MAGIC => (false, true),
+        MH_MAGIC_64 => (true, true),
+        m if m.swap_bytes() == MH_MAGIC => (false, false),
+        m if m.swap_bytes() == MH_MAGIC_64 => (true, false),
+        _ => return None,
+    };
+    let d = Data::new(bytes, big_endian);
+
+    let cputype = d.u32(4)?;
+    let filetype = d.u32(12)?;
+    let ncmds = d.u32(16)? as usize;
+    let sizeofcmds = d.u32(20)?;
+    let flags = d.u32(24)?;
+
+    let mut bin = Binary {
+        format: if is64 { "Mach-O 64" } else { "Mach-O 32" },
+        machine: cpu(cputype),
+        ..Default::default()
+    };
+
+    bin.fields.push(Field::new(0, "Mach header", "").at(0));
+    bin.fields.push(Field::new(1, "CPU", cpu(cputype)).at(4));
+    bin.fields
+        .push(Field::new(1, "File type", file_type(filetype)).at(12));
+    bin.fields
+        .push(Field::new(1, "Load commands", ncmds.to_string()).at(16));
+    bin.fields
+        .push(Field::new(1, "Size of load commands", format!("0x{:X}", sizeofcmds)).at(20));
+    bin.fields
+        .push(Field::new(1, "Flags", format!("0x{:08X}", flags)).at(24));
+
+    bin.fields
+        .push(Field::new(0, format!("Load commands ({})", ncmds), ""));
+    let mut symtab = None;
+    let mut entry = None;
+    let mut lc = if is64 { 32 } else { 28 };
+
+    for _ in 0..ncmds.min(MAX_COMMANDS) {
+        let (Some(cmd), Some(cmdsize)) = (d.u32(lc), d.u32(lc + 4)) else {
+            break;
+        };
+        let cmdsize = cmdsize as usize;
+        if cmdsize < 8 {
+            break;
+        }
+        let linkedit = |name: &str, off: usize, size: usize| {
+            let (off, size) = (d.u32(lc + off).unwrap_or(0), d.u32(lc + size).unwrap_or(0));
+            let field = Field::new(3, name, format!("offset 0x{:X} size 0x{:X}", off, size));
+            if off != 0 && size != 0 {
+                field.at(off as usize)
+            } else {
+                field
+            }
+        };
+
+        match cmd {
+            LC_SEGMENT | LC_SEGMENT_64 => {
+                let seg64 = cmd == LC_SEGMENT_64;
+                let name = fixed_str(&d, lc + 8);
+                let (vmaddr, vmsize, fileoff, filesize, initprot, nsects, sects, sect_size) =
+                    if seg64 {
+                        (
+                            d.u64(lc + 24)?,
+                            d.u64(lc + 32)?,
+                            d.u64(lc + 40)? as usize,
+                            d.u64(lc + 48)? as usize,
+                            d.u32(lc + 60)?,
+                            d.u32(lc + 64)? as usize,
+                            lc + 72,
+                            80,
+                        )
+                    } else {
+                        (
+                            d.u32(lc + 24)? as u64,
+                            d.u32(lc + 28)? as u64,
+                            d.u32(lc + 32)? as usize,
+                            d.u32(lc + 36)? as usize,
+                            d.u32(lc + 44)?,
+                            d.u32(lc + 48)? as usize,
+                            lc + 56,
+                            68,
+                        )
+                    };
+
+                bin.fields.push(
+                    Field::new(
+                        1,
+                        format!("{} {}", command_name(cmd), name),
+                        format!(
+                            "{} vmaddr {} vmsize 0x{:X} fileoff 0x{:X} filesize 0x{:X}",
+                            protection(initprot),
+                            hex(vmaddr, seg64),
+                            vmsize,
+                            fileoff,
+                            filesize
+                        ),
+                    )
+                    .at(lc),
+                );
+                if filesize > 0 && fits_in(bytes, fileoff, filesize, vmaddr) {
+                    bin.fields.push(
+                        Field::new(2, "Segment start", format!("0x{:X}", fileoff)).at(fileoff),
+                    );
+                    bin.segments.push(Region {
+                        name: name.clone(),
+                        offset: fileoff,
+                        size: filesize,
+                        addr: vmaddr,
+                    });
+                }
+
+                for i in 0..nsects.min(cmdsize / sect_size) {
+                    let s = sects + i * sect_size;
+                    let sectname = fixed_str(&d, s);
+                    let (addr, size, offset, sflags) = if seg64 {
+                        (
+                            d.u64(s + 32)?,
+                            d.u64(s + 40)?,
+                            d.u32(s + 48)?,
+                            d.u32(s + 64)?,
+                        )
+                    } else {
+                        (
+                            d.u32(s + 32)? as u64,
+                            d.u32(s + 36)? as u64,
+                            d.u32(s + 40)?,
+                            d.u32(s + 56)?,
+                        )
+                    };
+                    let full_name = format!("{},{}", name, sectname);
+                    let in_file = offset != 0 && !ZEROFILL_TYPES.contains(&(sflags & 0xff));
+
+                    let field = Field::new(
+                        2,
+                        &full_name,
+                        format!(
+                            "addr {} offset 0x{:X} size 0x{:X}",
+                            hex(addr, seg64),
+                            offset,
+                            size
+                        ),
+                    );
+                    bin.fields.push(if in_file {
+                        field.at(offset as usize)
+                    } else {
+                        field.at(s)
+                    });
+                    if in_file && size > 0 && fits_in(bytes, offset as usize, size as usize, addr) {
+                        bin.sections.push(Region {
+                            name: full_name,
+                            offset: offset as usize,
+                            size: size as usize,
+                            addr,
+                        });
+                    }
+                }
+            }
+            LC_SYMTAB => {
+                let (symoff, nsyms, stroff, strsize) = (
+                    d.u32(lc + 8)?,
+                    d.u32(lc + 12)?,
+                    d.u32(lc + 16)?,
+                    d.u32(lc + 20)?,
+                );
+                bin.fields
+                    .push(Field::new(1, command_name(cmd), format!("{} symbols", nsyms)).at(lc));
+                bin.fields.push(
+                    Field::new(2, "Symbol table", format!("offset 0x{:X}", symoff))
+                        .at(symoff as usize),
+                );
+                bin.fields.push(
+                    Field::new(
+                        2,
+                        "String table",
+                        format!("offset 0x{:X} size 0x{:X}", stroff, strsize),
+                    )
+                    .at(stroff as usize),
+                );
+                symtab = Some((
+                    symoff as usize,
+                    nsyms as usize,
+                    stroff as usize,
+                    strsize as usize,
+                ));
+            }
+            LC_DYLD_INFO | LC_DYLD_INFO_ONLY => {
+                bin.fields.push(Field::new(1, command_name(cmd), "").at(lc));
+                bin.fields.push(linkedit("Rebase", 8, 12));
+                bin.fields.push(linkedit("Bind", 16, 20));
+                bin.fields.push(linkedit("Weak bind", 24, 28));
+                bin.fields.push(linkedit("Lazy bind", 32, 36));
+                bin.fields.push(linkedit("Export", 40, 44));
+                for f in bin.fields.iter_mut().rev().take(5) {
+                    f.depth = 2;
+                }
+            }
+            LC_DYLD_EXPORTS_TRIE
+            | LC_DYLD_CHAINED_FIXUPS
+            | LC_FUNCTION_STARTS
+            | LC_CODE_SIGNATURE => {
+                let mut field = linkedit(&command_name(cmd), 8, 12);
+                field.depth = 1;
+                bin.fields.push(field);
+            }
+            LC_LOAD_DYLIB | LC_ID_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB
+            | LC_LOAD_DYLINKER => {
+                let name = d
+                    .u32(lc + 8)
+                    .and_then(|o| d.cstr(lc + o as usize, cmdsize.saturating_sub(o as usize)))
+                    .unwrap_or_default();
+                bin.fields
+                    .push(Field::new(1, command_name(cmd), name).at(lc));
+            }
+            LC_UUID => {
+                let uuid = d
+                    .bytes
+                    .get(lc + 8..lc + 24)
+                    .map(::hex::encode_upper)
+                    .unwrap_or_default();
+                bin.fields
+                    .push(Field::new(1, command_name(cmd), uuid).at(lc));
+            }
+            LC_MAIN => {
+                entry = d.u64(lc + 8);
+                let value = format!("entry offset 0x{:X}", entry.unwrap_or(0));
+                bin.fields
+                    .push(Field::new(1, command_name(cmd), value).at(lc));
+            }
+            _ => bin.fields.push(Field::new(1, command_name(cmd), "").at(lc)),
+        }
+        lc += cmdsize;
+    }
+
+    // the segment holding the header, usually __TEXT
+    bin.image_base = bin
+        .segments
+        .iter()
+        .find(|s| s.offset == 0)
+        .map_or(0, |s| s.addr);
+
+    if let Some(entry) = entry
+        && (entry as usize) < bytes.len()
+    {
+        bin.fields.insert(
+            6,
+            Field::new(1, "Entry point", format!("0x{:X}", entry)).at(entry as usize),
+        );
+    }
+
+    if let Some((symoff, nsyms, stroff, strsize)) = symtab {
+        let size = if is64 { 16 } else { 12 };
+        let mut seen = HashSet::new();
+        bin.fields
+            .push(Field::new(0, format!("Symbols ({})", nsyms), "").at(symoff));
+
+        for i in 0..nsyms.min(MAX_SYMBOLS) {
+            let n = symoff + i * size;
+            let (Some(strx), Some(kind)) = (d.u32(n), d.u8(n + 4)) else {
+                break;
+            };
+            let Some(value) = d.word(n + 8, is64) else {
+                break;
+            };
+            if kind & N_STAB != 0 || strx as usize >= strsize {
+                continue;
+            }
+            let name = d.cstr(stroff + strx as usize, 1024).unwrap_or_default();
+            if name.is_empty() {
+                continue;
+            }
+
+            // only symbols defined in a section have an address
+            let offset = if kind & N_TYPE == N_SECT {
+                bin.addr_to_offset(value)
+            } else {
+                None
+            };
+            let mut field = Field::new(1, &name, hex(value, is64));
+            field.offset = offset;
+            bin.fields.push(field);
+
+            if let Some(offset) = offset
+                && seen.insert((offset, name.clone()))
+            {
+                bin.symbols.push(Symbol { name, offset });
+            }
+        }
+    }
+
+    Some(bin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put32(b: &mut [u8], o: usize, v: u32) {
+        b[o..o + 4].copy_from_slice(&v.to_le_bytes());
+    }
+
+    fn put64(b: &mut [u8], o: usize, v: u64) {
+        b[o..o + 8].copy_from_slice(&v.to_le_bytes());
+    }
+
+    // a minimal 64-bit executable with a __TEXT,__text section and a `_main`
+    // symbol that is also the entry point
+    fn sample(cputype: u32) -> Vec<u8> {
+        let mut b = vec![0u8; 0x200];
+        b[..4].copy_from_slice(&MH_MAGIC_64.to_le_bytes());
+        put32(&mut b, 4, cputype);
+        put32(&mut b, 12, 2);
+        put32(&mut b, 16, 3);
+
+        put32(&mut b, 32, LC_SEGMENT_64);
+        put32(&mut b, 36, 152);
+        b[40..46].copy_from_slice(b"__TEXT");
+        put64(&mut b, 56, 0x100000000);
+        put64(&mut b, 64, 0x1000);
+        put64(&mut b, 80, 0x200);
+        put32(&mut b, 92, 5);
+        put32(&mut b, 96, 1);
+        b[104..110].copy_from_slice(b"__text");
+        b[120..126].copy_from_slice(b"__TEXT");
+        put64(&mut b, 136, 0x100000100);
+        put64(&mut b, 144, 0x40);
+        put32(&mut b, 152, 0x100);
+
+        put32(&mut b, 184, LC_SYMTAB);
+        put32(&mut b, 188, 24);
+        put32(&mut b, 192, 0x180);
+        put32(&mut b, 196, 1);
+        put32(&mut b, 200, 0x1a0);
+        put32(&mut b, 204, 0x10);
+
+        put32(&mut b, 208, LC_MAIN);
+        put32(&mut b, 212, 24);
+        put64(&mut b, 216, 0x110);
+
+        put32(&mut b, 0x180, 1);
+        b[0x184] = N_SECT | 1;
+        b[0x185] = 1;
+        put64(&mut b, 0x188, 0x100000110);
+        b[0x1a1..0x1a7].copy_from_slice(b"_main\0");
+        b
+    }
+
+    #[test]
+    fn parse_macho_test() {
+        let b = sample(0x0100000c);
+        assert!(is_macho(&b));
+
+        let bin = parse(&b, 0).unwrap();
+        assert_eq!(bin.format, "Mach-O 64");
+        assert_eq!(bin.section_at(0x120), Some("__TEXT,__text"));
+        assert_eq!(bin.addr_to_offset(0x100000110), Some(0x110));
+        assert_eq!(bin.symbols.len(), 1);
+        assert_eq!(bin.symbols[0].name, "_main");
+        assert_eq!(bin.symbols[0].offset, 0x110);
+        assert!(
+            bin.fields
+                .iter()
+                .any(|f| f.name == "Entry point" && f.offset == Some(0x110))
+        );
+
+        // segments and sections running past the file are left out
+        let mut b = sample(0x0100000c);
+        put64(&mut b, 80, u64::MAX); // segment filesize
+        put32(&mut b, 152, 0x1f0); // section offset
+        let bin = parse(&b, 0).unwrap();
+        assert!(bin.segments.is_empty());
+        assert!(bin.sections.is_empty());
+
+        let mut b = sample(0x0100000c);
+        put64(&mut b, 56, u64::MAX - 0x10); // vmaddr
+        let bin = parse(&b, 0).unwrap();
+        assert!(bin.segments.is_empty());
+        assert_eq!(bin.section_at(0x120), Some("__TEXT,__text"));
+    }
+
+    #[test]
+    fn parse_fat_test() {
+        let mut b = vec![0u8; 0x1000];
+        b[..4].copy_from_slice(&FAT_MAGIC.to_be_bytes());
+        b[4..8].copy_from_slice(&2u32.to_be_bytes());
+        for (i, (cputype, offset)) in [(0x01000007u32, 0x1000u32), (0x0100000c, 0x1200)]
+            .into_iter()
+            .enumerate()
+        {
+            let a = 8 + i * 20;
+            b[a..a + 4].copy_from_slice(&cputype.to_be_bytes());
+            b[a + 8..a + 12].copy_from_slice(&offset.to_be_bytes());
+            b[a + 12..a + 16].copy_from_slice(&0x200u32.to_be_bytes());
+        }
+        b.extend(sample(0x01000007));
+        b.extend(sample(0x0100000c));
+
+        let bin = parse(&b, 1).unwrap();
+        assert_eq!(bin.format, "Mach-O fat");
+        assert_eq!(bin.slice, 1);
+        assert_eq!(bin.slices.len(), 2);
+        assert_eq!(bin.slices[0].name, "x86-64");
+        assert_eq!(bin.machine, "AArch64");
+        assert_eq!(bin.symbols[0].offset, 0x1310);
+        assert_eq!(bin.section_at(0x1320), Some("__TEXT,__text"));
+
+        // a Java class file has the same magic
+        let class = [0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 0x34];
+        assert!(!is_macho(&class));
+    }
+}
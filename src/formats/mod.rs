@@ -0,0 +1,220 @@
+pub mod elf;
+pub mod macho;
+pub mod pe;
+
+/// Reads integers and strings from a binary file without panicking on
+/// truncated or malformed input
+#[derive(Clone, Copy)]
+pub struct Data<'a> {
+    pub bytes: &'a [u8],
+    pub big_endian: bool,
+}
+
+impl<'a> Data<'a> {
+    pub fn new(bytes: &'a [u8], big_endian: bool) -> Self {
+        Data { bytes, big_endian }
+    }
+
+    fn array<const N: usize>(&self, offset: usize) -> Option<[u8; N]> {
+        self.bytes
+            .get(offset..offset.checked_add(N)?)?
+            .try_into()
+            .ok()
+    }
+
+    pub fn u8(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(offset).copied()
+    }
+
+    pub fn u16(&self, offset: usize) -> Option<u16> {
+        let a = self.array(offset)?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(a)
+        } else {
+            u16::from_le_bytes(a)
+        })
+    }
+
+    pub fn u32(&self, offset: usize) -> Option<u32> {
+        let a = self.array(offset)?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(a)
+        } else {
+            u32::from_le_bytes(a)
+        })
+    }
+
+    pub fn u64(&self, offset: usize) -> Option<u64> {
+        let a = self.array(offset)?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(a)
+        } else {
+            u64::from_le_bytes(a)
+        })
+    }
+
+    /// Reads a 64-bit value, or a 32-bit one if `is64` is false
+    pub fn word(&self, offset: usize, is64: bool) -> Option<u64> {
+        if is64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    /// Reads a NUL-terminated string, up to `max` bytes long
+    pub fn cstr(&self, offset: usize, max: usize) -> Option<String> {
+        let bytes = self.bytes.get(offset..)?;
+        let bytes = &bytes[..bytes.len().min(max)];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// An entry of the structure browser. Entries with a `depth` greater than
+/// zero belong to the last entry with a smaller one.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub depth: usize,
+    pub name: String,
+    pub value: String,
+    pub offset: Option<usize>,
+}
+
+impl Field {
+    pub fn new(depth: usize, name: impl Into<String>, value: impl Into<String>) -> Self {
+        Field {
+            depth,
+            name: name.into(),
+            value: value.into(),
+            offset: None,
+        }
+    }
+
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+/// A range of the file that is mapped in memory, like a section or a segment
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+    pub addr: u64,
+}
+
+impl Region {
+    /// Whether `offset` is in the range of the file the region covers.
+    /// Sizes come from the file, so the end may be past the last offset.
+    pub fn contains(&self, offset: usize) -> bool {
+        offset >= self.offset && offset - self.offset < self.size
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub offset: usize,
+}
+
+/// What a parser found in a binary file
+#[derive(Debug, Default)]
+pub struct Binary {
+    pub format: &'static str,
+    /// Processor the code is meant for, as named by `elf::machine`
+    pub machine: &'static str,
+    pub fields: Vec<Field>,
+    pub sections: Vec<Region>,
+    pub segments: Vec<Region>,
+    pub symbols: Vec<Symbol>,
+    /// Address RVAs are relative to
+    pub image_base: u64,
+    /// Architectures of a fat binary and the one being shown
+    pub slices: Vec<Region>,
+    pub slice: usize,
+}
+
+impl Binary {
+    /// Name of the section holding `offset`
+    pub fn section_at(&self, offset: usize) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|s| s.contains(offset))
+            .map(|s| s.name.as_str())
+    }
+
+    /// Move everything found by `offset` bytes, for headers parsed from a
+    /// slice of the file
+    pub fn shift(&mut self, offset: usize) {
+        for f in self.fields.iter_mut() {
+            f.offset = f.offset.map(|o| o.saturating_add(offset));
+        }
+        for r in self.sections.iter_mut().chain(self.segments.iter_mut()) {
+            r.offset = r.offset.saturating_add(offset);
+        }
+        for s in self.symbols.iter_mut() {
+            s.offset = s.offset.saturating_add(offset);
+        }
+    }
+
+    /// Name of the symbol at `offset`
+    pub fn symbol_at(&self, offset: usize) -> Option<&str> {
+        let i = self.symbols.partition_point(|s| s.offset < offset);
+        self.symbols
+            .get(i)
+            .filter(|s| s.offset == offset)
+            .map(|s| s.name.as_str())
+    }
+
+    /// Virtual address of a file offset, if it's mapped in memory
+    pub fn offset_to_addr(&self, offset: usize) -> Option<u64> {
+        self.segments
+            .iter()
+            .chain(self.sections.iter())
+            .find(|r| r.contains(offset))
+            .and_then(|r| r.addr.checked_add((offset - r.offset) as u64))
+    }
+
+    /// File offset of a virtual address, if it's backed by the file
+    pub fn addr_to_offset(&self, addr: u64) -> Option<usize> {
+        self.segments
+            .iter()
+            .chain(self.sections.iter())
+            .find(|r| addr >= r.addr && addr - r.addr < r.size as u64)
+            .and_then(|r| r.offset.checked_add((addr - r.addr) as usize))
+    }
+}
+
+/// Parse a binary file in any of the supported formats
+pub fn parse(bytes: &[u8]) -> Option<Binary> {
+    parse_slice(bytes, 0)
+}
+
+/// Like `parse`, picking the architecture `slice` of a fat binary
+pub fn parse_slice(bytes: &[u8], slice: usize) -> Option<Binary> {
+    let mut bin = if bytes.starts_with(elf::MAGIC) {
+        elf::parse(bytes)
+    } else if bytes.starts_with(pe::MAGIC) {
+        pe::parse(bytes)
+    } else if macho::is_macho(bytes) {
+        macho::parse(bytes, slice)
+    } else {
+        None
+    }?;
+
+    // sorted for symbol_at()
+    bin.symbols.sort_by_key(|s| s.offset);
+    Some(bin)
+}
+
+/// Format an address with as many digits as the binary uses
+pub fn hex(value: u64, is64: bool) -> String {
+    if is64 {
+        format!("0x{:016X}", value)
+    } else {
+        format!("0x{:08X}", value)
+    }
+}
@@ -0,0 +1,645 @@
+use std::collections::HashSet;
+
+use crate::formats::{Binary, Data, Field, Region, Symbol, hex};
+
+pub const MAGIC: &[u8] = b"MZ";
+
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+const DIRECTORY_NAMES: [&str; 16] = [
+    "Export",
+    "Import",
+    "Resource",
+    "Exception",
+    "Security",
+    "Base relocation",
+    "Debug",
+    "Architecture",
+    "Global pointer",
+    "TLS",
+    "Load config",
+    "Bound import",
+    "IAT",
+    "Delay import",
+    "COM descriptor",
+    "Reserved",
+];
+
+// limits against malformed tables
+const MAX_IMPORTS: usize = 100_000;
+const MAX_EXPORTS: usize = 100_000;
+// the resource tree is no longer walked once the browser has this many
+// entries, or once this many directory entries were read
+const MAX_FIELDS: usize = 200_000;
+const MAX_RESOURCE_ENTRIES: usize = 200_000;
+
+struct Section {
+    name: String,
+    vsize: u32,
+    rva: u32,
+    raw_size: u32,
+    raw_offset: u32,
+}
+
+/// Translates relative virtual addresses into file offsets
+struct Sections {
+    list: Vec<Section>,
+    headers_size: u32,
+}
+
+impl Sections {
+    fn offset(&self, rva: u32) -> Option<usize> {
+        if rva < self.headers_size {
+            return Some(rva as usize);
+        }
+        self.list
+            .iter()
+            .find(|s| rva >= s.rva && rva - s.rva < s.vsize.max(s.raw_size))
+            .filter(|s| rva - s.rva < s.raw_size)
+            .and_then(|s| s.raw_offset.checked_add(rva - s.rva))
+            .map(|o| o as usize)
+    }
+}
+
+fn machine(m: u16) -> &'static str {
+    match m {
+        0x14c => "x86",
+        0x8664 => "x86-64",
+        0x1c0 => "ARM",
+        0x1c4 => "ARM Thumb-2",
+        0xaa64 => "AArch64",
+        _ => "unknown",
+    }
+}
+
+fn subsystem(s: u16) -> &'static str {
+    match s {
+        1 => "Native",
+        2 => "Windows GUI",
+        3 => "Windows console",
+        9 => "Windows CE GUI",
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        _ => "unknown",
+    }
+}
+
+fn resource_type(id: u32) -> String {
+    match id {
+        1 => "CURSOR".to_string(),
+        2 => "BITMAP".to_string(),
+        3 => "ICON".to_string(),
+        4 => "MENU".to_string(),
+        5 => "DIALOG".to_string(),
+        6 => "STRING".to_string(),
+        7 => "FONTDIR".to_string(),
+        8 => "FONT".to_string(),
+        9 => "ACCELERATOR".to_string(),
+        10 => "RCDATA".to_string(),
+        11 => "MESSAGETABLE".to_string(),
+        12 => "GROUP_CURSOR".to_string(),
+        14 => "GROUP_ICON".to_string(),
+        16 => "VERSION".to_string(),
+        24 => "MANIFEST".to_string(),
+        id => id.to_string(),
+    }
+}
+
+fn section_flags(c: u32) -> String {
+    [(0x40000000, 'R'), (0x80000000, 'W'), (0x20000000, 'X')]
+        .iter()
+        .map(|&(bit, ch)| if c & bit != 0 { ch } else { '-' })
+        .collect()
+}
+
+/// Offset of the PE signature, if `bytes` is a PE file
+fn pe_offset(d: &Data) -> Option<usize> {
+    if !d.bytes.starts_with(MAGIC) {
+        return None;
+    }
+    let pe = d.u32(0x3c)? as usize;
+    (d.bytes.get(pe..pe + 4)? == b"PE\0\0").then_some(pe)
+}
+
+fn imports(d: &Data, sections: &Sections, rva: u32, is64: bool, bin: &mut Binary) {
+    let Some(mut desc) = sections.offset(rva) else {
+        return;
+    };
+    let w = if is64 { 8 } else { 4 };
+    let ordinal_flag = if is64 { 1 << 63 } else { 1 << 31 };
+    let mut count = 0;
+
+    while let (Some(oft), Some(name), Some(ft)) = (d.u32(desc), d.u32(desc + 12), d.u32(desc + 16))
+    {
+        if name == 0 && ft == 0 {
+            break;
+        }
+        let dll = sections
+            .offset(name)
+            .and_then(|o| d.cstr(o, 256))
+            .unwrap_or_default();
+        bin.fields
+            .push(Field::new(1, &dll, format!("IAT RVA 0x{:X}", ft)).at(desc));
+
+        // the lookup table holds names even after the IAT is bound
+        let lookup = if oft != 0 { oft } else { ft };
+        let mut slot = ft;
+        if let (Some(mut thunk), Some(mut iat)) = (sections.offset(lookup), sections.offset(ft)) {
+            while let Some(entry) = d.word(thunk, is64)
+                && entry != 0
+                && count < MAX_IMPORTS
+            {
+                let func = if entry & ordinal_flag != 0 {
+                    format!("#{}", entry & 0xffff)
+                } else {
+                    sections
+                        .offset(entry as u32 & 0x7fffffff)
+                        .and_then(|o| d.cstr(o + 2, 256))
+                        .unwrap_or_default()
+                };
+                let name = format!("{}!{}", dll, func);
+                bin.fields
+                    .push(Field::new(2, &func, format!("IAT slot RVA 0x{:X}", slot)).at(iat));
+                bin.symbols.push(Symbol { name, offset: iat });
+
+                thunk += w;
+                iat += w;
+                slot = slot.wrapping_add(w as u32);
+                count += 1;
+            }
+        }
+        desc += 20;
+    }
+}
+
+fn exports(d: &Data, sections: &Sections, rva: u32, size: u32, bin: &mut Binary) {
+    let Some(dir) = sections.offset(rva) else {
+        return;
+    };
+    let (Some(base), Some(names), Some(functions), Some(name_table), Some(ordinals)) = (
+        d.u32(dir + 16),
+        d.u32(dir + 24),
+        d.u32(dir + 28).and_then(|r| sections.offset(r)),
+        d.u32(dir + 32).and_then(|r| sections.offset(r)),
+        d.u32(dir + 36).and_then(|r| sections.offset(r)),
+    ) else {
+        return;
+    };
+
+    if let Some(name) = d
+        .u32(dir + 12)
+        .and_then(|r| sections.offset(r))
+        .and_then(|o| d.cstr(o, 256))
+    {
+        bin.fields.push(Field::new(1, "Name", name).at(dir + 12));
+    }
+
+    for i in 0..(names as usize).min(MAX_EXPORTS) {
+        let Some(name) = d
+            .u32(name_table + i * 4)
+            .and_then(|r| sections.offset(r))
+            .and_then(|o| d.cstr(o, 256))
+        else {
+            break;
+        };
+        let Some(ordinal) = d.u16(ordinals + i * 2) else {
+            break;
+        };
+        let Some(func) = d.u32(functions + ordinal as usize * 4) else {
+            continue;
+        };
+
+        // forwarders point to a string inside the export directory
+        let forwarded = func >= rva && func - rva < size;
+        let mut field = Field::new(
+            1,
+            &name,
+            format!("ordinal {} RVA 0x{:X}", base + ordinal as u32, func),
+        );
+        if !forwarded && let Some(offset) = sections.offset(func) {
+            field.offset = Some(offset);
+            bin.symbols.push(Symbol { name, offset });
+        }
+        bin.fields.push(field);
+    }
+}
+
+fn resource_name(d: &Data, root: usize, id: u32, level: usize) -> String {
+    if id & 0x80000000 != 0 {
+        // named entries point to a length-prefixed UTF-16 string
+        let o = root + (id & 0x7fffffff) as usize;
+        let len = d.u16(o).unwrap_or(0) as usize;
+        let units: Vec<u16> = (0..len).filter_map(|i| d.u16(o + 2 + i * 2)).collect();
+        String::from_utf16_lossy(&units)
+    } else if level == 0 {
+        resource_type(id)
+    } else {
+        id.to_string()
+    }
+}
+
+// Offset of the resource tree, with the directories already walked and the
+// entries read so far, so that a crafted tree can't make the walk go on forever
+struct ResourceWalk {
+    root: usize,
+    visited: HashSet<usize>,
+    entries: usize,
+}
+
+// Walk the type/name/language levels of the resource tree
+fn resources(
+    d: &Data,
+    sections: &Sections,
+    walk: &mut ResourceWalk,
+    dir: usize,
+    path: &str,
+    level: usize,
+    bin: &mut Binary,
+) {
+    let root = walk.root;
+    if level > 2 || !walk.visited.insert(dir) {
+        return;
+    }
+    let (Some(named), Some(ids)) = (d.u16(dir + 12), d.u16(dir + 14)) else {
+        return;
+    };
+
+    for i in 0..(named as usize + ids as usize) {
+        walk.entries += 1;
+        if walk.entries > MAX_RESOURCE_ENTRIES || bin.fields.len() > MAX_FIELDS {
+            return;
+        }
+        let e = dir + 16 + i * 8;
+        let (Some(id), Some(data)) = (d.u32(e), d.u32(e + 4)) else {
+            return;
+        };
+        let name = resource_name(d, root, id, level);
+        let path = if path.is_empty() {
+            name
+        } else {
+            format!("{}/{}", path, name)
+        };
+
+        if data & 0x80000000 != 0 {
+            let sub = root + (data & 0x7fffffff) as usize;
+            // a subdirectory can't point back before its parent
+            if sub > dir {
+                resources(d, sections, walk, sub, &path, level + 1, bin);
+            }
+        } else {
+            let entry = root + data as usize;
+            if let (Some(rva), Some(size)) = (d.u32(entry), d.u32(entry + 4)) {
+                let mut field = Field::new(1, &path, format!("RVA 0x{:X} size 0x{:X}", rva, size));
+                field.offset = sections.offset(rva);
+                bin.fields.push(field);
+            }
+        }
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Option<Binary> {
+    let d = Data::new(bytes, false);
+    let pe = pe_offset(&d)?;
+
+    // COFF header
+    let coff = pe + 4;
+    let machine_id = d.u16(coff)?;
+    let nsections = d.u16(coff + 2)? as usize;
+    let timestamp = d.u32(coff + 4)?;
+    let opt_size = d.u16(coff + 16)? as usize;
+    let characteristics = d.u16(coff + 18)?;
+
+    // optional header
+    let opt = coff + 20;
+    let is64 = match d.u16(opt)? {
+        PE32_MAGIC => false,
+        PE32_PLUS_MAGIC => true,
+        _ => return None,
+    };
+    let entry = d.u32(opt + 16)?;
+    let image_base = if is64 {
+        d.u64(opt + 24)?
+    } else {
+        d.u32(opt + 28)? as u64
+    };
+    let section_alignment = d.u32(opt + 32)?;
+    let file_alignment = d.u32(opt + 36)?;
+    let image_size = d.u32(opt + 56)?;
+    let headers_size = d.u32(opt + 60)?;
+    let subsystem_id = d.u16(opt + 68)?;
+    let (ndirs, dirs) = if is64 {
+        (opt + 108, opt + 112)
+    } else {
+        (opt + 92, opt + 96)
+    };
+    let ndirs = (d.u32(ndirs)? as usize).min(DIRECTORY_NAMES.len());
+
+    // section table
+    let table = opt + opt_size;
+    let mut list = Vec::with_capacity(nsections);
+    for i in 0..nsections {
+        let s = table + i * 40;
+        let name = d.bytes.get(s..s + 8)?;
+        let end = name.iter().position(|&b| b == 0).unwrap_or(8);
+        list.push(Section {
+            name: String::from_utf8_lossy(&name[..end]).into_owned(),
+            vsize: d.u32(s + 8)?,
+            rva: d.u32(s + 12)?,
+            raw_size: d.u32(s + 16)?,
+            raw_offset: d.u32(s + 20)?,
+        });
+    }
+    let sections = Sections { list, headers_size };
+
+    let mut bin = Binary {
+        format: if is64 { "PE32+" } else { "PE32" },
+        machine: machine(machine_id),
+        image_base,
+        ..Default::default()
+    };
+    // headers are mapped at the image base
+    bin.segments.push(Region {
+        name: "headers".to_string(),
+        offset: 0,
+        size: (headers_size as usize).min(bytes.len()),
+        addr: image_base,
+    });
+
+    for s in &sections.list {
+        if s.raw_size > 0
+            && let Some(addr) = image_base.checked_add(s.rva as u64)
+        {
+            bin.sections.push(Region {
+                name: s.name.clone(),
+                offset: s.raw_offset as usize,
+                size: s.raw_size as usize,
+                addr,
+            });
+        }
+    }
+
+    bin.fields.push(Field::new(0, "DOS header", "MZ").at(0));
+    bin.fields
+        .push(Field::new(1, "PE header offset", format!("0x{:X}", pe)).at(0x3c));
+
+    bin.fields.push(Field::new(0, "NT headers", "PE").at(pe));
+    bin.fields
+        .push(Field::new(1, "Machine", machine(machine_id)).at(coff));
+    bin.fields
+        .push(Field::new(1, "Number of sections", nsections.to_string()).at(coff + 2));
+    bin.fields
+        .push(Field::new(1, "Timestamp", format!("0x{:08X}", timestamp)).at(coff + 4));
+    bin.fields
+        .push(Field::new(1, "Characteristics", format!("0x{:04X}", characteristics)).at(coff + 18));
+
+    bin.fields
+        .push(Field::new(0, "Optional header", if is64 { "PE32+" } else { "PE32" }).at(opt));
+    let mut entry_field = Field::new(
+        1,
+        "Entry point",
+        format!(
+            "RVA 0x{:X} VA {}",
+            entry,
+            image_base
+                .checked_add(entry as u64)
+                .map_or("out of range".to_string(), |va| hex(va, is64))
+        ),
+    );
+    entry_field.offset = sections.offset(entry);
+    bin.fields.push(entry_field);
+    bin.fields.push(
+        Field::new(1, "Image base", hex(image_base, is64)).at(if is64 {
+            opt + 24
+        } else {
+            opt + 28
+        }),
+    );
+    bin.fields.push(
+        Field::new(1, "Section alignment", format!("0x{:X}", section_alignment)).at(opt + 32),
+    );
+    bin.fields
+        .push(Field::new(1, "File alignment", format!("0x{:X}", file_alignment)).at(opt + 36));
+    bin.fields
+        .push(Field::new(1, "Size of image", format!("0x{:X}", image_size)).at(opt + 56));
+    bin.fields
+        .push(Field::new(1, "Size of headers", format!("0x{:X}", headers_size)).at(opt + 60));
+    bin.fields
+        .push(Field::new(1, "Subsystem", subsystem(subsystem_id)).at(opt + 68));
+
+    bin.fields
+        .push(Field::new(0, format!("Data directories ({})", ndirs), "").at(dirs));
+    let mut directories = Vec::with_capacity(ndirs);
+    for (i, name) in DIRECTORY_NAMES.iter().enumerate().take(ndirs) {
+        let (rva, size) = (d.u32(dirs + i * 8)?, d.u32(dirs + i * 8 + 4)?);
+        directories.push((rva, size));
+        if rva == 0 {
+            continue;
+        }
+        let mut field = Field::new(1, *name, format!("RVA 0x{:X} size 0x{:X}", rva, size));
+        // the security directory holds a file offset instead of an RVA
+        field.offset = if i == 4 {
+            Some(rva as usize)
+        } else {
+            sections.offset(rva)
+        };
+        bin.fields.push(field);
+    }
+
+    bin.fields
+        .push(Field::new(0, format!("Section table ({})", nsections), "").at(table));
+    for (i, s) in sections.list.iter().enumerate() {
+        let flags = d.u32(table + i * 40 + 36).unwrap_or(0);
+        let mut field = Field::new(
+            1,
+            &s.name,
+            format!(
+                "{} RVA 0x{:X} vsize 0x{:X} offset 0x{:X} size 0x{:X}",
+                section_flags(flags),
+                s.rva,
+                s.vsize,
+                s.raw_offset,
+                s.raw_size
+            ),
+        );
+        field.offset = Some(if s.raw_size > 0 {
+            s.raw_offset as usize
+        } else {
+            table + i * 40
+        });
+        bin.fields.push(field);
+    }
+
+    let directory = |i: usize| directories.get(i).copied().filter(|&(rva, _)| rva != 0);
+
+    if let Some((rva, size)) = directory(0) {
+        bin.fields
+            .push(Field::new(0, "Exports", "").at(sections.offset(rva).unwrap_or(0)));
+        exports(&d, &sections, rva, size, &mut bin);
+    }
+    if let Some((rva, _)) = directory(1) {
+        bin.fields
+            .push(Field::new(0, "Imports", "").at(sections.offset(rva).unwrap_or(0)));
+        imports(&d, &sections, rva, is64, &mut bin);
+    }
+    if let Some((rva, _)) = directory(2)
+        && let Some(root) = sections.offset(rva)
+    {
+        bin.fields.push(Field::new(0, "Resources", "").at(root));
+        let mut walk = ResourceWalk {
+            root,
+            visited: HashSet::new(),
+            entries: 0,
+        };
+        resources(&d, &sections, &mut walk, root, "", 0, &mut bin);
+    }
+
+    // the same function can be exported under several names
+    let mut seen = HashSet::new();
+    bin.symbols
+        .retain(|s| seen.insert((s.offset, s.name.clone())));
+
+    Some(bin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a minimal PE32+ with a single .text section exporting `run` and
+    // importing `ExitProcess` from kernel32.dll
+    fn sample() -> Vec<u8> {
+        let mut b = vec![0u8; 0x400];
+        let put16 =
+            |b: &mut Vec<u8>, o: usize, v: u16| b[o..o + 2].copy_from_slice(&v.to_le_bytes());
+        let put32 =
+            |b: &mut Vec<u8>, o: usize, v: u32| b[o..o + 4].copy_from_slice(&v.to_le_bytes());
+
+        b[..2].copy_from_slice(MAGIC);
+        put32(&mut b, 0x3c, 0x80);
+        b[0x80..0x84].copy_from_slice(b"PE\0\0");
+        put16(&mut b, 0x84, 0x8664);
+        put16(&mut b, 0x86, 1);
+        put16(&mut b, 0x94, 240);
+
+        let opt = 0x98;
+        put16(&mut b, opt, PE32_PLUS_MAGIC);
+        put32(&mut b, opt + 16, 0x1000); // entry point
+        b[opt + 24..opt + 32].copy_from_slice(&0x140000000u64.to_le_bytes());
+        put32(&mut b, opt + 60, 0x200); // size of headers
+        put32(&mut b, opt + 108, 16);
+        put32(&mut b, opt + 112, 0x1100); // export directory
+        put32(&mut b, opt + 116, 0x40);
+        put32(&mut b, opt + 120, 0x1180); // import directory
+        put32(&mut b, opt + 124, 0x28);
+
+        // .text at RVA 0x1000, file offset 0x200
+        let s = opt + 240;
+        b[s..s + 5].copy_from_slice(b".text");
+        put32(&mut b, s + 8, 0x200);
+        put32(&mut b, s + 12, 0x1000);
+        put32(&mut b, s + 16, 0x200);
+        put32(&mut b, s + 20, 0x200);
+        put32(&mut b, s + 36, 0x60000020);
+
+        // export directory at 0x300: one function, one name
+        let rva = |o: usize| (o - 0x200 + 0x1000) as u32;
+        put32(&mut b, 0x300 + 16, 1);
+        put32(&mut b, 0x300 + 20, 1);
+        put32(&mut b, 0x300 + 24, 1);
+        put32(&mut b, 0x300 + 28, rva(0x340));
+        put32(&mut b, 0x300 + 32, rva(0x344));
+        put32(&mut b, 0x300 + 36, rva(0x348));
+        put32(&mut b, 0x340, 0x1010);
+        put32(&mut b, 0x344, rva(0x34c));
+        b[0x34c..0x350].copy_from_slice(b"run\0");
+
+        // import descriptor at 0x380, lookup table at 0x3c0, IAT at 0x3d0
+        put32(&mut b, 0x380, rva(0x3c0));
+        put32(&mut b, 0x380 + 12, rva(0x3e0));
+        put32(&mut b, 0x380 + 16, rva(0x3d0));
+        put32(&mut b, 0x3c0, rva(0x3f0));
+        put32(&mut b, 0x3d0, rva(0x3f0));
+        b[0x3e0..0x3ed].copy_from_slice(b"kernel32.dll\0");
+        b[0x3f2..0x3fe].copy_from_slice(b"ExitProcess\0");
+        b
+    }
+
+    #[test]
+    fn parse_pe_test() {
+        let b = sample();
+        let bin = parse(&b).unwrap();
+        assert_eq!(bin.format, "PE32+");
+        assert_eq!(bin.section_at(0x210), Some(".text"));
+        assert_eq!(bin.addr_to_offset(0x140001010), Some(0x210));
+        assert_eq!(bin.offset_to_addr(0x210), Some(0x140001010));
+        assert_eq!(bin.offset_to_addr(0x80), Some(0x140000080));
+        assert_eq!(bin.image_base, 0x140000000);
+
+        let symbols: Vec<_> = bin
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.offset))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![("run", 0x210), ("kernel32.dll!ExitProcess", 0x3d0)]
+        );
+
+        // text starting with "MZ" is not a PE file
+        assert!(parse(b"MZ is not a PE file").is_none());
+    }
+
+    #[test]
+    fn crafted_resources_test() {
+        let mut b = sample();
+        b.resize(0x8200, 0);
+        let put16 =
+            |b: &mut Vec<u8>, o: usize, v: u16| b[o..o + 2].copy_from_slice(&v.to_le_bytes());
+        let put32 =
+            |b: &mut Vec<u8>, o: usize, v: u32| b[o..o + 4].copy_from_slice(&v.to_le_bytes());
+
+        // .text grows to hold the tree
+        let s = 0x98 + 240;
+        put32(&mut b, s + 8, 0x8000);
+        put32(&mut b, s + 16, 0x8000);
+        put32(&mut b, 0x98 + 128, 0x1200);
+        put32(&mut b, 0x98 + 132, 0x6010);
+
+        // every entry of each level points to the same directory of the next
+        // one, which would be walked 1000^3 times
+        let root = 0x400;
+        for (dir, sub) in [(0, 0x2000), (0x2000, 0x4000), (0x4000, 0x6000)] {
+            put16(&mut b, root + dir + 14, 1000);
+            for i in 0..1000 {
+                put32(&mut b, root + dir + 16 + i * 8, i as u32);
+                put32(&mut b, root + dir + 20 + i * 8, 0x80000000 | sub);
+            }
+        }
+
+        let bin = parse(&b).unwrap();
+        assert!(bin.fields.iter().any(|f| f.name == "Resources"));
+    }
+
+    #[test]
+    fn corrupted_header_test() {
+        // an image base at the top of the address space
+        let mut b = sample();
+        b[0x98 + 24..0x98 + 32].copy_from_slice(&(u64::MAX - 0x10).to_le_bytes());
+        let bin = parse(&b).unwrap();
+        assert!(bin.sections.is_empty());
+        let entry = bin.fields.iter().find(|f| f.name == "Entry point").unwrap();
+        assert!(entry.value.ends_with("out of range"));
+
+        // a section whose raw data would end past 4 GiB
+        let mut b = sample();
+        b[0x98 + 240 + 20..0x98 + 240 + 24].copy_from_slice(&0xfffffff0u32.to_le_bytes());
+        let bin = parse(&b).unwrap();
+        assert!(bin.symbols.is_empty());
+
+        for len in [0x40, 0x84, 0x98, 0x100, 0x200, 0x300] {
+            parse(&sample()[..len]);
+        }
+    }
+}
@@ -206,7 +206,7 @@ fn load_variables(app: &mut App) {
         .context
         .set_value(
             "@O".to_string(),
-            Value::from_int(app.hex_view.last_visited_offset as i64),
+            Value::from_int(app.hex_view.jumps.previous().unwrap_or(app.hex_view.offset) as i64),
         )
         .unwrap();
 
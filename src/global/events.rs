@@ -1,13 +1,19 @@
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::{app::App, commands, editor::UIState, global};
+use crate::{
+    app::App,
+    commands,
+    editor::{AppView, UIState},
+    global,
+};
 
 use std::io::Result;
 
 pub fn handle_global_events(app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
-        // switch views
-        KeyCode::Enter => app.switch_editor_view(),
+        // switch views; Enter follows branches in the disassembly view
+        KeyCode::Enter if app.editor_view != AppView::Disasm => app.switch_editor_view(),
+        KeyCode::F(4) => app.toggle_disasm_view(),
         // log window
         KeyCode::Char('l') => {
             if key.modifiers.contains(KeyModifiers::ALT) {
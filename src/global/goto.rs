@@ -63,8 +63,6 @@ impl App {
         self.hex_view.cursor.x =
             (offset - self.reader.page_start) % self.config.hex_mode_bytes_per_line;
 
-        // Save current offset (user can press backspace to restore it)
-        self.hex_view.last_visited_offset = self.hex_view.offset;
         // Update offset
         self.hex_view.offset = offset;
 
@@ -4,7 +4,12 @@ use ratatui::{
     widgets::Paragraph,
 };
 
-use crate::{app::App, editor::UIState, hex::search::SearchMode};
+use crate::{
+    app::App,
+    config::{AddrMode, Endian},
+    editor::UIState,
+    hex::changes,
+};
 
 pub fn status_bar_draw(app: &mut App, frame: &mut Frame, area: Rect) {
     // Bookmarks
@@ -23,17 +28,23 @@ pub fn status_bar_draw(app: &mut App, frame: &mut Frame, area: Rect) {
         }
     }
 
+    let search = &app.hex_view.search;
+    let search_mode = format!(
+        "SEARCH/{}{}",
+        search.mode.name(),
+        if search.ignore_case() { "/I" } else { "" }
+    );
     let mode = match app.state {
         UIState::Normal => "NORMAL",
-        UIState::HexEditing => "REPLACE",
-        UIState::HexSelection => "SELECT",
-        UIState::DialogSearch => {
-            if app.hex_view.search.mode == SearchMode::Hex {
-                "SEARCH/HEX"
+        UIState::HexEditing => {
+            if app.hex_view.inserting {
+                "INSERT"
             } else {
-                "SEARCH/UTF-8"
+                "REPLACE"
             }
         }
+        UIState::HexSelection => "SELECT",
+        UIState::DialogSearch => search_mode.as_str(),
         UIState::Command => "COMMAND",
         _ => "",
     };
@@ -56,9 +67,58 @@ pub fn status_bar_draw(app: &mut App, frame: &mut Frame, area: Rect) {
         .alignment(Alignment::Left);
     frame.render_widget(status_bar_info_left, area);
 
+    // number of changed ranges waiting to be written
+    let changes = match changes::count(app) {
+        0 => String::new(),
+        1 => "1 change ".to_string(),
+        n => format!("{} changes ", n),
+    };
+
+    // section holding the cursor, if the file format is known
+    let section = app
+        .file_info
+        .binary
+        .as_ref()
+        .and_then(|bin| bin.section_at(app.hex_view.offset))
+        .map(|s| format!("{} ", s))
+        .unwrap_or_default();
+
+    // address of the cursor, besides its offset, in VA and RVA modes
+    let addr = match app.addr_mode() {
+        AddrMode::Offset => String::new(),
+        AddrMode::Va => format!("VA {} ", app.format_addr(app.hex_view.offset)),
+        AddrMode::Rva => format!("RVA {} ", app.format_addr(app.hex_view.offset)),
+    };
+
+    let endian = match app.config.endian {
+        Endian::Little => "LE",
+        Endian::Big => "BE",
+    };
+
+    // keys typed so far of a command, like the count of `20j` or the `m` of `ma`
+    let mut pending = String::new();
+    if let Some(count) = app.hex_view.count {
+        pending.push_str(&count.to_string());
+    }
+    if let Some(key) = app.hex_view.pending_key {
+        pending.push(key);
+    }
+    if !pending.is_empty() {
+        pending.push(' ');
+    }
+
     let status_bar_info_right = Paragraph::new(format!(
-        "{} {} {} {:08X} {}%",
-        mode, bookmarks_string, app.file_info.r#type, app.hex_view.offset, percent
+        "{}{}{} {} {} {} {}{}{:08X} {}%",
+        pending,
+        changes,
+        mode,
+        bookmarks_string,
+        app.file_info.r#type,
+        endian,
+        section,
+        addr,
+        app.hex_view.offset,
+        percent
     ))
     .style(app.config.theme.topbar)
     .alignment(Alignment::Right);
@@ -1,7 +1,19 @@
-use crate::app::App;
+use crate::{app::App, editor::AppView};
 
 impl App {
     pub fn switch_editor_view(&mut self) {
         self.editor_view.next();
     }
+
+    /// Go to the disassembly view, or back to the hex view
+    pub fn toggle_disasm_view(&mut self) {
+        self.editor_view = match self.editor_view {
+            AppView::Disasm => AppView::Hex,
+            _ => {
+                // start from the cursor
+                self.disasm_view.top = self.hex_view.offset;
+                AppView::Disasm
+            }
+        };
+    }
 }
@@ -0,0 +1,101 @@
+use crate::{app::App, config::AddrMode};
+
+impl App {
+    /// The address mode in use; files of unknown format only have offsets
+    pub fn addr_mode(&self) -> AddrMode {
+        match self.file_info.binary {
+            Some(_) => self.config.addr_mode,
+            None => AddrMode::Offset,
+        }
+    }
+
+    /// VA or RVA of `offset` in the current address mode, or the offset
+    /// itself. Returns None for bytes not mapped in memory.
+    pub fn offset_to_addr(&self, offset: usize) -> Option<u64> {
+        let bin = self.file_info.binary.as_ref();
+        match self.addr_mode() {
+            AddrMode::Offset => Some(offset as u64),
+            AddrMode::Va => bin?.offset_to_addr(offset),
+            AddrMode::Rva => {
+                let bin = bin?;
+                Some(bin.offset_to_addr(offset)?.wrapping_sub(bin.image_base))
+            }
+        }
+    }
+
+    /// File offset of an address given in `mode`
+    pub fn addr_to_offset(&self, addr: u64, mode: AddrMode) -> Result<usize, String> {
+        if mode == AddrMode::Offset {
+            return Ok(addr as usize);
+        }
+        let Some(bin) = &self.file_info.binary else {
+            return Err("Addresses need a known file format".to_string());
+        };
+        let (name, va) = match mode {
+            AddrMode::Rva => ("RVA", bin.image_base.wrapping_add(addr)),
+            _ => ("VA", addr),
+        };
+        bin.addr_to_offset(va)
+            .filter(|&ofs| ofs < self.file_info.size)
+            .ok_or_else(|| format!("{} {:X} is not mapped to the file", name, addr))
+    }
+
+    /// Offset held by the `size`-byte value at the cursor: an address in the
+    /// current address mode or, if `relative`, a signed distance from the
+    /// cursor
+    pub fn pointer_target(&mut self, size: usize, relative: bool) -> Result<usize, String> {
+        let offset = self.hex_view.offset;
+        let value = match size {
+            2 => self.read_u16(offset).map(|v| (v as u64, v as i16 as i64)),
+            4 => self.read_u32(offset).map(|v| (v as u64, v as i32 as i64)),
+            _ => self.read_u64(offset).map(|v| (v, v as i64)),
+        };
+        let Some((value, signed)) = value else {
+            return Err(format!("No {}-byte value at {:X}", size, offset));
+        };
+
+        let target = if relative {
+            offset
+                .checked_add_signed(signed as isize)
+                .ok_or_else(|| format!("Offset {:X} {:+} is out of the file", offset, signed))?
+        } else {
+            self.addr_to_offset(value, self.addr_mode())?
+        };
+        if target >= self.file_info.size {
+            return Err(format!("Offset {:X} is out of the file", target));
+        }
+        Ok(target)
+    }
+
+    /// Work out the digits used to show offsets or addresses, as they don't
+    /// change while a frame is drawn
+    pub fn update_addr_width(&mut self) {
+        let bin = self.file_info.binary.as_ref();
+        let max = match (self.addr_mode(), bin) {
+            (AddrMode::Offset, _) | (_, None) => self.file_info.size as u64,
+            (_, Some(bin)) => bin
+                .segments
+                .iter()
+                .chain(bin.sections.iter())
+                .map(|r| r.addr.saturating_add(r.size as u64))
+                .max()
+                .unwrap_or(0),
+        };
+        let digits = (64 - max.leading_zeros() as usize).div_ceil(4);
+        self.hex_view.addr_width = digits.max(8);
+    }
+
+    /// Digits used to show offsets or addresses
+    pub fn addr_width(&self) -> usize {
+        self.hex_view.addr_width
+    }
+
+    /// `offset` formatted as the offsets column shows it
+    pub fn format_addr(&self, offset: usize) -> String {
+        let width = self.addr_width();
+        match self.offset_to_addr(offset) {
+            Some(addr) => format!("{:0width$X}", addr),
+            None => "-".repeat(width),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use ratatui::{
+    Frame,
+    crossterm::event::KeyModifiers,
+    layout::Alignment,
+    widgets::{Block, Clear, List, ListItem, Padding},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode};
+use std::io::Result;
+
+use crate::{
+    app::App,
+    editor::UIState,
+    hex::{
+        edit::{delete_bytes, restore_bytes},
+        history::Edit,
+    },
+    piece_table::{Piece, Source},
+    util::center_widget,
+};
+
+// bytes shown for each side of a change in the list
+const SHOWN_BYTES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Replaced,
+    Inserted,
+    /// holds the range of the original file that was deleted
+    Deleted(Piece),
+}
+
+/// A contiguous range of the buffer that differs from the file on disk
+#[derive(Debug, Clone)]
+pub struct ChangedRange {
+    pub offset: usize,
+    pub kind: ChangeKind,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+impl ChangedRange {
+    pub fn len(&self) -> usize {
+        self.old.len().max(self.new.len())
+    }
+}
+
+// value of the byte at `offset` once the changes are written
+fn new_byte(app: &App, offset: usize) -> u8 {
+    app.hex_view
+        .changed_bytes
+        .get(&offset)
+        .and_then(|v| u8::from_str_radix(v, 16).ok())
+        .or_else(|| app.file_info.byte_at(offset))
+        .unwrap_or(0)
+}
+
+// Returns true if the pending change at `offset` starts a replaced range.
+// Pending changes of inserted bytes belong to the inserted range.
+fn starts_range(app: &App, offset: usize, structural: bool) -> bool {
+    let inserted = |o| structural && app.file_info.pieces.is_inserted(o);
+    !inserted(offset)
+        && (offset == 0
+            || !app.hex_view.changed_bytes.contains_key(&(offset - 1))
+            || inserted(offset - 1))
+}
+
+/// List every changed range, sorted by offset
+pub fn changed_ranges(app: &App) -> Vec<ChangedRange> {
+    let original = app.file_info.get_buffer();
+    let structural = app.file_info.pieces.is_modified();
+    let mut ranges = Vec::new();
+
+    // inserted bytes and gaps left by deleted ones
+    let mut offset = 0;
+    let mut next_original = 0;
+    for p in &app.file_info.pieces.pieces {
+        match p.source {
+            Source::Original => {
+                if p.start > next_original {
+                    ranges.push(ChangedRange {
+                        offset,
+                        kind: ChangeKind::Deleted(Piece {
+                            source: Source::Original,
+                            start: next_original,
+                            len: p.start - next_original,
+                        }),
+                        old: original[next_original..p.start].to_vec(),
+                        new: Vec::new(),
+                    });
+                }
+                next_original = p.start + p.len;
+            }
+            Source::Added => ranges.push(ChangedRange {
+                offset,
+                kind: ChangeKind::Inserted,
+                old: Vec::new(),
+                new: (offset..offset + p.len).map(|o| new_byte(app, o)).collect(),
+            }),
+        }
+        offset += p.len;
+    }
+    if next_original < original.len() {
+        ranges.push(ChangedRange {
+            offset,
+            kind: ChangeKind::Deleted(Piece {
+                source: Source::Original,
+                start: next_original,
+                len: original.len() - next_original,
+            }),
+            old: original[next_original..].to_vec(),
+            new: Vec::new(),
+        });
+    }
+
+    // bytes replaced in place
+    for &start in app.hex_view.changed_bytes.keys() {
+        if !starts_range(app, start, structural) {
+            continue;
+        }
+        let mut end = start + 1;
+        while app.hex_view.changed_bytes.contains_key(&end)
+            && !(structural && app.file_info.pieces.is_inserted(end))
+        {
+            end += 1;
+        }
+        ranges.push(ChangedRange {
+            offset: start,
+            kind: ChangeKind::Replaced,
+            old: (start..end)
+                .map(|o| app.file_info.byte_at(o).unwrap_or(0))
+                .collect(),
+            new: (start..end).map(|o| new_byte(app, o)).collect(),
+        });
+    }
+
+    ranges.sort_by_key(|r| r.offset);
+    ranges
+}
+
+/// Number of changed ranges, cheap enough to be shown in the status bar
+pub fn count(app: &App) -> usize {
+    let structural = app.file_info.pieces.is_modified();
+    let replaced = app
+        .hex_view
+        .changed_bytes
+        .keys()
+        .filter(|&&o| starts_range(app, o, structural))
+        .count();
+
+    if !structural {
+        return replaced;
+    }
+
+    let mut structural_ranges = 0;
+    let mut next_original = 0;
+    for p in &app.file_info.pieces.pieces {
+        match p.source {
+            Source::Original => {
+                if p.start > next_original {
+                    structural_ranges += 1;
+                }
+                next_original = p.start + p.len;
+            }
+            Source::Added => structural_ranges += 1,
+        }
+    }
+    if next_original < app.file_info.get_buffer().len() {
+        structural_ranges += 1;
+    }
+    replaced + structural_ranges
+}
+
+/// Undo a single range, as a transaction of its own
+fn revert(app: &mut App, range: &ChangedRange) {
+    app.hex_view.history.commit();
+    app.hex_view.history.begin();
+    match range.kind {
+        ChangeKind::Replaced => {
+            for offset in range.offset..range.offset + range.len() {
+                if let Some(old) = app.hex_view.changed_bytes.remove(&offset) {
+                    app.hex_view.history.record(Edit::Replace {
+                        offset,
+                        old: Some(old),
+                        new: None,
+                    });
+                }
+            }
+        }
+        ChangeKind::Inserted => delete_bytes(app, range.offset, range.len()),
+        ChangeKind::Deleted(piece) => restore_bytes(app, range.offset, &[piece]),
+    }
+    app.hex_view.history.commit();
+}
+
+fn bytes_to_string(bytes: &[u8]) -> String {
+    let mut s = bytes
+        .iter()
+        .take(SHOWN_BYTES)
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bytes.len() > SHOWN_BYTES {
+        s.push_str(" ..");
+    }
+    s
+}
+
+pub fn dialog_changes_draw(app: &mut App, frame: &mut Frame) {
+    let mut items = Vec::with_capacity(app.hex_view.changes.len());
+
+    for range in &app.hex_view.changes {
+        let (old, new) = match range.kind {
+            ChangeKind::Replaced => (bytes_to_string(&range.old), bytes_to_string(&range.new)),
+            ChangeKind::Inserted => ("(inserted)".to_string(), bytes_to_string(&range.new)),
+            ChangeKind::Deleted(_) => (bytes_to_string(&range.old), "(deleted)".to_string()),
+        };
+        items.push(ListItem::from(format!(
+            "{:08X}  {:>8}  {:<26} -> {}",
+            range.offset,
+            range.len(),
+            old,
+            new
+        )));
+    }
+
+    let list = List::new(items)
+        .style(app.config.theme.dialog)
+        .block(
+            Block::bordered()
+                .title(format!(" Changes ({}) ", app.hex_view.changes.len()))
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        )
+        .highlight_style(app.config.theme.highlight)
+        .repeat_highlight_symbol(true);
+
+    let width = (frame.area().width * 3 / 4).max(80.min(frame.area().width));
+    let height = frame.area().height / 2 + 4;
+    let dialog_area = center_widget(width, height, frame.area());
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_stateful_widget(list, dialog_area, &mut app.hex_view.changes_list_state);
+}
+
+/// Open the pending changes list
+pub fn open_changes(app: &mut App) {
+    app.hex_view.changes = changed_ranges(app);
+    app.state = UIState::DialogChanges;
+    app.dialog_renderer = Some(dialog_changes_draw);
+    if app.hex_view.changes_list_state.selected().is_none() {
+        app.hex_view.changes_list_state.select_first();
+    }
+}
+
+pub fn dialog_changes_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => {
+                app.hex_view.changes.clear();
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.hex_view.changes_list_state.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.hex_view.changes_list_state.select_previous();
+            }
+            KeyCode::PageDown => {
+                app.hex_view.changes_list_state.scroll_down_by(30);
+            }
+            KeyCode::PageUp => {
+                app.hex_view.changes_list_state.scroll_up_by(30);
+            }
+            KeyCode::Home => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.changes_list_state.select_first();
+                } else if let Some(n) = app.hex_view.changes_list_state.selected() {
+                    let new_index = n.saturating_sub(29);
+                    app.hex_view.changes_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::End => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.changes_list_state.select_last();
+                } else if let Some(n) = app.hex_view.changes_list_state.selected() {
+                    let new_index = n + 29;
+                    app.hex_view.changes_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(range) = app
+                    .hex_view
+                    .changes_list_state
+                    .selected()
+                    .and_then(|i| app.hex_view.changes.get(i))
+                {
+                    let offset = range.offset.min(app.file_info.size.saturating_sub(1));
+                    app.jump(offset);
+                }
+                app.hex_view.changes.clear();
+                app.state = UIState::Normal;
+                app.dialog_renderer = None;
+            }
+            // revert the selected range
+            KeyCode::Char('d') => {
+                if let Some(range) = app
+                    .hex_view
+                    .changes_list_state
+                    .selected()
+                    .and_then(|i| app.hex_view.changes.get(i))
+                    .cloned()
+                {
+                    revert(app, &range);
+                    app.hex_view.changes = changed_ranges(app);
+                    // the list state clamps the selection when rendering
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,287 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+};
+
+use std::io::Result;
+
+use crate::{
+    app::App,
+    editor::UIState,
+    hex::edit::{insert_bytes, set_change},
+    widgets::ListChoice,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YankFormat {
+    Hex,
+    HexSpaced,
+    C,
+    Python,
+    Rust,
+    Go,
+    Base64,
+    Escaped,
+}
+
+// formats in the order they're shown in the yank dialog
+const YANK_FORMATS: [(&str, YankFormat); 8] = [
+    ("Hex", YankFormat::Hex),
+    ("Hex (space-separated)", YankFormat::HexSpaced),
+    ("C unsigned char[]", YankFormat::C),
+    ("Python bytes", YankFormat::Python),
+    ("Rust &[u8]", YankFormat::Rust),
+    ("Go []byte", YankFormat::Go),
+    ("Base64", YankFormat::Base64),
+    ("Escaped string", YankFormat::Escaped),
+];
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[(n >> (18 - i * 6)) as usize & 0x3f] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Format bytes to be pasted somewhere else, like source code
+pub fn format_bytes(bytes: &[u8], format: YankFormat) -> String {
+    let list = |prefix: &str| {
+        bytes
+            .iter()
+            .map(|b| format!("{prefix}{b:02x}"))
+            .collect::<Vec<_>>()
+    };
+
+    match format {
+        YankFormat::Hex => list("").concat().to_uppercase(),
+        YankFormat::HexSpaced => list("").join(" ").to_uppercase(),
+        YankFormat::C => format!(
+            "unsigned char data[{}] = {{ {} }};",
+            bytes.len(),
+            list("0x").join(", ")
+        ),
+        YankFormat::Python => format!("b\"{}\"", list("\\x").concat()),
+        YankFormat::Rust => format!("&[{}]", list("0x").join(", ")),
+        YankFormat::Go => format!("[]byte{{{}}}", list("0x").join(", ")),
+        YankFormat::Base64 => base64(bytes),
+        YankFormat::Escaped => bytes
+            .iter()
+            .map(|&b| match b {
+                b'"' => "\\\"".to_string(),
+                b'\\' => "\\\\".to_string(),
+                b'\n' => "\\n".to_string(),
+                b'\r' => "\\r".to_string(),
+                b'\t' => "\\t".to_string(),
+                b' ' => " ".to_string(),
+                b if b.is_ascii_graphic() => (b as char).to_string(),
+                b => format!("\\x{b:02x}"),
+            })
+            .collect(),
+    }
+}
+
+/// Copy the selected bytes to the system's clipboard, pending changes included
+pub fn yank(app: &mut App, format: YankFormat) {
+    let mut bytes = Vec::new();
+    for offset in app.hex_view.selection {
+        if let Some(b) = app.read_changed_u8(offset) {
+            bytes.push(b);
+        }
+    }
+
+    if let Ok(clip) = app.clipboard.as_mut() {
+        let _ = clip.set_text(format_bytes(&bytes, format));
+    }
+    app.state = UIState::Normal;
+    app.hex_view.selection.clear();
+}
+
+pub fn dialog_yank_draw(app: &mut App, frame: &mut Frame) {
+    let mut dialog = ListChoice::new();
+    dialog.set_title(" Yank as ".to_string());
+    dialog.choices = YANK_FORMATS.iter().map(|(s, _)| s.to_string()).collect();
+    dialog.render(app, frame);
+}
+
+pub fn dialog_yank_events(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        // back to selection
+        KeyCode::Esc => {
+            app.state = UIState::HexSelection;
+            app.dialog_renderer = None;
+        }
+        KeyCode::Enter => {
+            let sel = app.list_state.selected().unwrap_or(0);
+            let (_, format) = YANK_FORMATS[sel.min(YANK_FORMATS.len() - 1)];
+            app.dialog_renderer = None;
+            yank(app, format);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            if app.list_state.selected() == Some(YANK_FORMATS.len() - 1) {
+                app.list_state.select_first();
+            } else {
+                app.list_state.select_next();
+            }
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            if app.list_state.selected() == Some(0) {
+                app.list_state.select_last();
+            } else {
+                app.list_state.select_previous();
+            }
+        }
+        KeyCode::PageUp | KeyCode::Home => {
+            app.list_state.select_first();
+        }
+        KeyCode::PageDown | KeyCode::End => {
+            app.list_state.select_last();
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Convert clipboard text to bytes. Hex strings are accepted with or without
+/// spaces or commas, with `0x` prefixes or with `\x` escapes. Anything else,
+/// like an odd number of digits, is taken as raw text.
+pub fn parse_clipboard(text: &str) -> Vec<u8> {
+    let tokens: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let digits = if tokens
+        .iter()
+        .all(|t| t.starts_with("0x") || t.starts_with("0X"))
+    {
+        tokens.iter().map(|t| &t[2..]).collect()
+    } else if tokens
+        .iter()
+        .all(|t| t.starts_with("\\x") && t.split("\\x").skip(1).all(|b| b.len() == 2))
+    {
+        tokens.concat().replace("\\x", "")
+    } else {
+        tokens.concat()
+    };
+
+    if !digits.is_empty()
+        && digits.len().is_multiple_of(2)
+        && digits.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        hex::decode(digits).unwrap_or_default()
+    } else {
+        text.as_bytes().to_vec()
+    }
+}
+
+/// Paste the clipboard contents at `offset`, either overwriting the bytes
+/// there or inserting new ones. Overwriting stops at the end of the file,
+/// or after `max` bytes if given.
+pub fn paste(app: &mut App, offset: usize, insert: bool, max: Option<usize>) {
+    if app.file_info.is_read_only {
+        crate::beep!();
+        return;
+    }
+
+    let text = match app.clipboard.as_mut() {
+        Ok(clip) => clip.get_text().map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    let bytes = match text {
+        Ok(text) => parse_clipboard(&text),
+        Err(e) => {
+            App::log(app, format!("clipboard: {}", e));
+            crate::beep!();
+            return;
+        }
+    };
+
+    if bytes.is_empty() {
+        return;
+    }
+
+    let pasted = if insert {
+        bytes.len()
+    } else {
+        bytes
+            .len()
+            .min(app.file_info.size.saturating_sub(offset))
+            .min(max.unwrap_or(usize::MAX))
+    };
+
+    // the whole paste is undone at once
+    app.hex_view.history.begin();
+    if insert {
+        insert_bytes(app, offset, &bytes);
+    } else {
+        for (i, b) in bytes.iter().take(pasted).enumerate() {
+            set_change(app, offset + i, format!("{:02X}", b));
+        }
+    }
+    app.hex_view.history.commit();
+
+    App::log(app, format!("{} bytes pasted at {:x}", pasted, offset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_clipboard_test() {
+        assert_eq!(parse_clipboard("41424344"), b"ABCD");
+        assert_eq!(parse_clipboard("41"), b"A");
+        assert_eq!(parse_clipboard("cafe"), b"\xca\xfe");
+        assert_eq!(parse_clipboard("41 42\n43"), b"ABC");
+        assert_eq!(parse_clipboard("41,42"), b"AB");
+        assert_eq!(parse_clipboard("0x41, 0X42"), b"AB");
+        assert_eq!(parse_clipboard("0x4142"), b"AB");
+        assert_eq!(parse_clipboard("\\x41\\x42"), b"AB");
+        // not hex
+        assert_eq!(parse_clipboard("hello"), b"hello");
+        assert_eq!(parse_clipboard("add"), b"add");
+        assert_eq!(parse_clipboard("414"), b"414");
+        assert_eq!(parse_clipboard("41 4"), b"41 4");
+        assert_eq!(parse_clipboard("0x414"), b"0x414");
+        assert_eq!(parse_clipboard("\\x4\\x142"), b"\\x4\\x142");
+        assert_eq!(parse_clipboard(""), b"");
+    }
+
+    #[test]
+    fn format_bytes_test() {
+        let bytes = b"A\x00\"";
+        assert_eq!(format_bytes(bytes, YankFormat::Hex), "410022");
+        assert_eq!(format_bytes(bytes, YankFormat::HexSpaced), "41 00 22");
+        assert_eq!(
+            format_bytes(bytes, YankFormat::C),
+            "unsigned char data[3] = { 0x41, 0x00, 0x22 };"
+        );
+        assert_eq!(
+            format_bytes(bytes, YankFormat::Python),
+            "b\"\\x41\\x00\\x22\""
+        );
+        assert_eq!(format_bytes(bytes, YankFormat::Rust), "&[0x41, 0x00, 0x22]");
+        assert_eq!(
+            format_bytes(bytes, YankFormat::Go),
+            "[]byte{0x41, 0x00, 0x22}"
+        );
+        assert_eq!(format_bytes(bytes, YankFormat::Escaped), "A\\x00\\\"");
+        assert_eq!(format_bytes(b"Man", YankFormat::Base64), "TWFu");
+        assert_eq!(format_bytes(b"Ma", YankFormat::Base64), "TWE=");
+        assert_eq!(format_bytes(b"M", YankFormat::Base64), "TQ==");
+    }
+}
@@ -15,6 +15,9 @@ use crate::{app::App, commands::Commands, editor::UIState};
 pub struct Comment {
     pub offset: usize,
     pub comment: String,
+    // symbols come from the file itself, so they are not saved in the database
+    #[serde(skip)]
+    pub symbol: bool,
 }
 
 pub fn dialog_comment_draw(app: &mut App, frame: &mut Frame) {
@@ -32,7 +35,7 @@ fn sync_comments(app: &mut App) {
         .hex_view
         .comment_name_list
         .iter()
-        .position(|x| x.offset == ofs)
+        .position(|x| x.offset == ofs && !x.symbol)
     {
         app.hex_view.comment_name_list.remove(idx);
     }
@@ -47,9 +50,11 @@ impl Commands {
         } else {
             app.hex_view.comments.insert(offset, comment.clone());
             sync_comments(app);
-            app.hex_view
-                .comment_name_list
-                .push(Comment { offset, comment });
+            app.hex_view.comment_name_list.push(Comment {
+                offset,
+                comment,
+                symbol: false,
+            });
         }
         app.dialog_renderer = None;
         app.state = UIState::Normal;
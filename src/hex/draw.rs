@@ -4,7 +4,7 @@ use ratatui::{
     widgets::{Cell, Clear, Row, Table},
 };
 
-use crate::{app::App, editor::UIState};
+use crate::{app::App, config::AddrMode, editor::UIState};
 
 // Left column with offsets
 pub fn draw_hex_offsets(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -15,7 +15,7 @@ pub fn draw_hex_offsets(app: &mut App, frame: &mut Frame, area: Rect) {
     let height = frame.area().height as usize;
 
     for _ in 0..height {
-        rows.push(Row::new([format!("{ofs:08X}")]));
+        rows.push(Row::new([app.format_addr(ofs)]));
         ofs += app.config.hex_mode_bytes_per_line;
 
         // Prevent further offsets to appear
@@ -26,7 +26,7 @@ pub fn draw_hex_offsets(app: &mut App, frame: &mut Frame, area: Rect) {
     }
 
     // Show filesize as last offset
-    if app.file_info.size > 0 {
+    if app.file_info.size > 0 && app.addr_mode() == AddrMode::Offset {
         rows.push(Row::new([format!("{:08X}", app.file_info.size)]));
     }
 
@@ -34,7 +34,8 @@ pub fn draw_hex_offsets(app: &mut App, frame: &mut Frame, area: Rect) {
         .offset_state
         .select(Some(app.hex_view.cursor.y));
 
-    let table = Table::new(rows, [Constraint::Length(12); 1]).style(app.config.theme.offsets);
+    let width = app.addr_width() as u16 + 4;
+    let table = Table::new(rows, [Constraint::Length(width); 1]).style(app.config.theme.offsets);
 
     frame.render_stateful_widget(table, area, &mut app.hex_view.offset_state);
 }
@@ -49,13 +50,10 @@ pub fn draw_hex_contents(app: &mut App, frame: &mut Frame, area: Rect) {
     let mut cell_hl_style = app.config.theme.highlight;
     let mut byte_style = app.config.theme.main;
 
-    let buffer = app.file_info.get_buffer();
-    for (i, byte) in buffer
-        .iter()
-        .skip(app.reader.page_start)
-        .take(app.reader.page_current_size)
-        .enumerate()
-    {
+    let buffer = app
+        .file_info
+        .read(app.reader.page_start, app.reader.page_current_size);
+    for (i, byte) in buffer.iter().enumerate() {
         // we need the absolute offset of this byte to check
         // whether there's a new value for it in the hashmap
         // if yes, we draw the new one and style it
@@ -65,8 +63,12 @@ pub fn draw_hex_contents(app: &mut App, frame: &mut Frame, area: Rect) {
         byte_style =
             if app.state == UIState::HexSelection && app.hex_view.selection.contains(offset) {
                 app.config.theme.highlight
+            } else if app.hex_view.find_all.contains(offset) {
+                app.config.theme.search_match
             } else if app.hex_view.highlights.contains(byte) {
                 app.config.theme.byte_highlight
+            } else if app.file_info.pieces.is_inserted(offset) {
+                app.config.theme.changed_bytes
             } else if *byte == b'\0' && app.config.dim_zeroes {
                 app.config.theme.dimmed
             } else if !byte.is_ascii_graphic() && app.config.dim_control_chars {
@@ -167,13 +169,10 @@ pub fn draw_hex_ascii(app: &mut App, frame: &mut Frame, area: Rect) {
         app.config.theme.highlight
     };
 
-    let buffer = app.file_info.get_buffer();
-    for (i, byte) in buffer
-        .iter()
-        .skip(app.reader.page_start)
-        .take(app.reader.page_current_size)
-        .enumerate()
-    {
+    let buffer = app
+        .file_info
+        .read(app.reader.page_start, app.reader.page_current_size);
+    for (i, byte) in buffer.iter().enumerate() {
         // Antes de criar a Cell a partir do byte, preciso tratar
         // os bytes inválidos em ASCII
         let c = if (*byte).is_ascii_graphic() {
@@ -213,6 +212,16 @@ pub fn draw_hex_ascii(app: &mut App, frame: &mut Frame, area: Rect) {
             char_style = app.config.theme.highlight;
             let s = String::from(c);
             Cell::new(s).style(char_style)
+        } else if app.hex_view.find_all.contains(offset) {
+            // Bytes de um resultado do find all
+            char_style = app.config.theme.search_match;
+            let s = String::from(c);
+            Cell::new(s).style(char_style)
+        } else if app.file_info.pieces.is_inserted(offset) {
+            // Bytes inseridos também são bytes alterados
+            char_style = app.config.theme.changed_bytes;
+            let s = String::from(c);
+            Cell::new(s).style(char_style)
         } else {
             // Se não for um byte alterado, usa o estilo padrão do tema
             char_style = app.config.theme.main;
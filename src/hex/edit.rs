@@ -1,28 +1,171 @@
 use ratatui::crossterm::event::KeyModifiers;
 
 use crate::app::App;
+use crate::hex::history::Edit;
+use crate::piece_table::Piece;
 
 use crate::editor::UIState;
 
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
 use std::io::Result;
 
+/// Set a new value for the byte at `offset`, to be written when the file is saved
+pub fn set_change(app: &mut App, offset: usize, value: String) {
+    let old = app.hex_view.changed_bytes.insert(offset, value.clone());
+    app.hex_view.history.record(Edit::Replace {
+        offset,
+        old,
+        new: Some(value),
+    });
+}
+
 pub fn fill_with(app: &mut App, with: u8, advance: bool) {
     let s = format!("{:02X}", with);
-    app.hex_view.changed_bytes.insert(app.hex_view.offset, s);
-    app.hex_view.changed_history.push(app.hex_view.offset);
+    set_change(app, app.hex_view.offset, s);
     if advance {
         app.goto(app.hex_view.offset + 1);
     }
 }
 
+/// Keep pending changes attached to their bytes after `delta` bytes
+/// were inserted (positive) or deleted (negative) at `offset`. Pending
+/// changes of deleted bytes are dropped and returned.
+fn shift_changes(app: &mut App, offset: usize, delta: isize) -> Vec<(usize, String)> {
+    let deleted_end = offset + if delta < 0 { delta.unsigned_abs() } else { 0 };
+    let mut dropped = Vec::new();
+
+    app.hex_view.changed_bytes = std::mem::take(&mut app.hex_view.changed_bytes)
+        .into_iter()
+        .filter_map(|(k, v)| {
+            if k < offset {
+                Some((k, v))
+            } else if k < deleted_end {
+                dropped.push((k, v));
+                None
+            } else {
+                Some((k.saturating_add_signed(delta), v))
+            }
+        })
+        .collect();
+
+    dropped
+}
+
+/// Insert bytes without recording it in the undo history
+pub fn insert_untracked(app: &mut App, offset: usize, bytes: &[u8]) {
+    app.file_info.insert(offset, bytes);
+    shift_changes(app, offset, bytes.len() as isize);
+    // the matches found before no longer line up with the bytes
+    app.hex_view.find_all.clear();
+}
+
+/// Delete bytes without recording it in the undo history. Returns the
+/// pieces that held the deleted bytes and their pending changes.
+pub fn delete_untracked(
+    app: &mut App,
+    offset: usize,
+    len: usize,
+) -> (Vec<Piece>, Vec<(usize, String)>) {
+    let removed = app.file_info.delete(offset, len);
+    let removed_len = removed.iter().map(|p| p.len).sum::<usize>();
+    let changes = shift_changes(app, offset, -(removed_len as isize));
+    app.hex_view.find_all.clear();
+
+    if app.file_info.size > 0 && app.hex_view.offset >= app.file_info.size {
+        app.goto(app.file_info.size - 1);
+    }
+    (removed, changes)
+}
+
+/// Put deleted pieces back without recording it in the undo history
+pub fn restore_untracked(app: &mut App, offset: usize, pieces: &[Piece]) {
+    app.file_info.restore(offset, pieces);
+    let len = pieces.iter().map(|p| p.len).sum::<usize>();
+    shift_changes(app, offset, len as isize);
+    app.hex_view.find_all.clear();
+}
+
+pub fn insert_bytes(app: &mut App, offset: usize, bytes: &[u8]) {
+    insert_untracked(app, offset, bytes);
+    app.hex_view.history.record(Edit::Insert {
+        offset,
+        bytes: bytes.to_vec(),
+    });
+}
+
+pub fn delete_bytes(app: &mut App, offset: usize, len: usize) {
+    let (pieces, changes) = delete_untracked(app, offset, len);
+    if !pieces.is_empty() {
+        app.hex_view.history.record(Edit::Delete {
+            offset,
+            pieces,
+            changes,
+        });
+    }
+}
+
+pub fn restore_bytes(app: &mut App, offset: usize, pieces: &[Piece]) {
+    restore_untracked(app, offset, pieces);
+    app.hex_view.history.record(Edit::Restore {
+        offset,
+        pieces: pieces.to_vec(),
+    });
+}
+
+// In insert mode, a new byte is inserted before the cursor, or after it
+// when appending, and the cursor is placed over it
+fn insert_at_cursor(app: &mut App, with: u8) {
+    let ofs = if app.hex_view.appending {
+        app.hex_view.offset + 1
+    } else {
+        app.hex_view.offset
+    }
+    .min(app.file_info.size);
+
+    app.hex_view.appending = false;
+    insert_bytes(app, ofs, &[with]);
+    app.goto(ofs);
+}
+
+// Moves to the next byte. In insert mode there's no next byte at the
+// end of the file, so the next one typed is appended instead.
+fn advance(app: &mut App) {
+    let ofs = app.hex_view.offset + 1;
+    if app.hex_view.inserting && ofs >= app.file_info.size {
+        app.hex_view.appending = true;
+    } else {
+        app.goto(ofs);
+    }
+}
+
+// Overwrites the byte under the cursor or, in insert mode, inserts a new one
+fn put_byte(app: &mut App, with: u8) {
+    if app.hex_view.inserting {
+        insert_at_cursor(app, with);
+        advance(app);
+    } else {
+        fill_with(app, with, true);
+    }
+}
+
 pub fn edit_events(app: &mut App, key: KeyEvent) -> Result<bool> {
+    // A byte typed in hex takes two keys, but it's undone as a single change.
+    // Anything other than a nibble ends it.
+    let typing_nibble = app.hex_view.editing_hex
+        && matches!(key.code, KeyCode::Char(c) if c.is_ascii_hexdigit())
+        && !key.modifiers.contains(KeyModifiers::CONTROL);
+    if !typing_nibble {
+        app.hex_view.history.commit();
+    }
+
     match key.code {
         KeyCode::Esc | KeyCode::Enter => {
             app.state = UIState::Normal;
             // app.hex_view.changed_bytes.clear();
             app.dialog_renderer = None;
             app.hex_view.editing_hex = true;
+            app.hex_view.inserting = false;
+            app.hex_view.appending = false;
         }
 
         KeyCode::Left | KeyCode::Backspace => {
@@ -49,49 +192,53 @@ pub fn edit_events(app: &mut App, key: KeyEvent) -> Result<bool> {
         KeyCode::Char(c) => {
             if app.hex_view.editing_hex {
                 if c.is_ascii_hexdigit() && !key.modifiers.contains(KeyModifiers::CONTROL) {
-                    // If the hashmap contains the key, it means the user has typed
-                    // one character
-                    if app
+                    let half_typed = app
                         .hex_view
                         .changed_bytes
-                        .contains_key(&app.hex_view.offset)
-                    {
-                        // Get the current value and check if it has two characters, meaning
-                        // the user navigated back to an already changed offset and will change
-                        // it again
-                        let value = app
-                            .hex_view
-                            .changed_bytes
-                            .get_mut(&app.hex_view.offset)
-                            .unwrap(); // It should be safe as we checked for .contains_key()
+                        .get(&app.hex_view.offset)
+                        .is_some_and(|v| v.len() == 1);
+
+                    if !half_typed {
+                        app.hex_view.history.commit();
+                        app.hex_view.history.begin();
+
+                        // In insert mode, the first nibble typed creates a new byte
+                        if app.hex_view.inserting {
+                            insert_at_cursor(app, 0x00);
+                        }
+                    }
+
+                    // If the hashmap contains the key, it means the user has typed
+                    // one character
+                    if let Some(value) = app.hex_view.changed_bytes.get(&app.hex_view.offset) {
+                        // Check if it has two characters, meaning the user navigated
+                        // back to an already changed offset and will change it again
                         if value.len() == 2 {
                             // There are two characters there already, restart the process
                             // by replacing the value using the same key
-                            app.hex_view
-                                .changed_bytes
-                                .insert(app.hex_view.offset, c.to_ascii_uppercase().to_string());
-                            // Update history for undo command
-                            app.hex_view.changed_history.push(app.hex_view.offset);
+                            set_change(
+                                app,
+                                app.hex_view.offset,
+                                c.to_ascii_uppercase().to_string(),
+                            );
                         } else {
                             // If the number of characters there is not two, concatenate
                             // what's in there with whatever the user typed
-                            (*value).push(c.to_ascii_uppercase());
-                            app.hex_view.changed_history.push(app.hex_view.offset);
-                            app.goto(app.hex_view.offset + 1);
+                            let value = format!("{}{}", value, c.to_ascii_uppercase());
+                            set_change(app, app.hex_view.offset, value);
+                            app.hex_view.history.commit();
+                            advance(app);
                         }
                     } else {
                         // First char was typed, just add it to the hashmap
-                        app.hex_view
-                            .changed_bytes
-                            .insert(app.hex_view.offset, c.to_ascii_uppercase().to_string());
-                        app.hex_view.changed_history.push(app.hex_view.offset);
+                        set_change(app, app.hex_view.offset, c.to_ascii_uppercase().to_string());
                     }
                 } else if c == 'z' {
                     // zero out bytes
-                    fill_with(app, 0x00, true);
+                    put_byte(app, 0x00);
                 } else if c == 'n' {
                     // NOP bytes
-                    fill_with(app, 0x90, true);
+                    put_byte(app, 0x90);
                 } else if c == 'a' && key.modifiers.contains(KeyModifiers::CONTROL) {
                     let ofs = app.hex_view.offset;
                     if let Some(s) = app.hex_view.changed_bytes.get(&ofs) {
@@ -111,16 +258,16 @@ pub fn edit_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                         fill_with(app, b.wrapping_sub(1), false);
                     }
                 } else if c == 'T' {
-                    // truncate the file
-                    if let Some(f) = &app.file_info.file {
-                        f.set_len((app.hex_view.offset + 1) as u64)?;
-                        app.reload_file();
-                        app.state = UIState::Normal;
-                        app.hex_view.editing_hex = true;
-                    }
+                    // truncate the file after the cursor
+                    let ofs = app.hex_view.offset + 1;
+                    delete_bytes(app, ofs, app.file_info.size.saturating_sub(ofs));
+                    app.state = UIState::Normal;
+                    app.hex_view.editing_hex = true;
+                    app.hex_view.inserting = false;
+                    app.hex_view.appending = false;
                 }
             } else {
-                fill_with(app, c as u8, true);
+                put_byte(app, c as u8);
             }
         }
         _ => {}
@@ -1,4 +1,4 @@
-use crate::{app::App, commands::Commands, editor::UIState, hex};
+use crate::{app::App, commands::Commands, disasm, editor::UIState, hex};
 
 use crate::hex::search::SearchDirection;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -9,7 +9,9 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
     // it is called when the user sends either 'o' or 'O'
     fn goto_other_byte(app: &mut App, delta: isize) {
         let mut ofs = app.hex_view.offset;
-        let current_byte = app.read_u8(ofs).unwrap();
+        let Some(current_byte) = app.read_u8(ofs) else {
+            return;
+        };
 
         while ofs < app.file_info.size {
             if let Some(b) = app.read_u8(ofs)
@@ -29,40 +31,103 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
     }
 
-    fn search_next(app: &mut App) {
-        let mut ofs = None;
+    // go to the `count`th next match of the last search
+    fn search_next(app: &mut App, count: usize) {
+        if app.state != UIState::Normal {
+            return;
+        }
 
-        if app.state == UIState::Normal {
-            if app.hex_view.search.mode == hex::search::SearchMode::Utf8
-                && !app.hex_view.search.input_text.value().is_empty()
-            {
-                ofs = hex::search::search(app, app.hex_view.search.input_text.value().to_string())
-            } else if app.hex_view.search.mode == hex::search::SearchMode::Hex
-                && !app.hex_view.search.input_hex.value().is_empty()
-            {
-                let hex_string = app.hex_view.search.input_hex.value().to_string();
+        let Some(Ok(needle)) = app.hex_view.search.needle(app.config.endian) else {
+            return;
+        };
 
-                if let Some(by) = hex::search::hex_string_to_u8(&hex_string) {
-                    ofs = hex::search::search(app, &by)
+        let mut hits: Vec<usize> = Vec::new();
+        let mut from = app.hex_view.offset;
+        let target = loop {
+            match hex::search::search_from(app, &needle, from) {
+                None => break hits.last().copied(),
+                // every match was seen once: the rest of the count goes around them
+                Some(ofs) if hits.first() == Some(&ofs) => {
+                    break Some(hits[(count - 1) % hits.len()]);
+                }
+                Some(ofs) => {
+                    hits.push(ofs);
+                    if hits.len() >= count {
+                        break Some(ofs);
+                    }
+                    from = ofs;
                 }
             }
+        };
 
-            if let Some(ofs) = ofs {
-                app.goto(ofs);
+        if let Some(ofs) = target {
+            app.jump(ofs);
+        }
+    }
+
+    // move `count` times `step` bytes forward, as far as the file allows
+    fn advance(app: &mut App, count: usize, step: usize) {
+        let left = app
+            .file_info
+            .size
+            .saturating_sub(1)
+            .saturating_sub(app.hex_view.offset);
+        let steps = count.min(left / step);
+        if steps > 0 {
+            app.goto(app.hex_view.offset + steps * step);
+        }
+    }
+
+    // move `count` times `step` bytes backward, as far as the file allows
+    fn retreat(app: &mut App, count: usize, step: usize) {
+        let steps = count.min(app.hex_view.offset / step);
+        if steps > 0 {
+            app.goto(app.hex_view.offset - steps * step);
+        }
+    }
+
+    // add `delta` to the byte under the cursor, wrapping around
+    fn add_to_byte(app: &mut App, delta: u8) {
+        let ofs = app.hex_view.offset;
+        if let Some(b) = app.read_changed_u8(ofs) {
+            app.state = UIState::HexEditing;
+            hex::edit::fill_with(app, b.wrapping_add(delta), false);
+        }
+    }
+
+    // the second key of `m{a-zA-Z}` and `'{a-zA-Z}`; anything else cancels them
+    if let Some(first) = app.hex_view.pending_key.take() {
+        match (first, key.code) {
+            ('m', KeyCode::Char(c)) => hex::marks::set_mark(app, c),
+            // `''` goes back like Backspace does
+            ('\'', KeyCode::Char('\'')) => {
+                app.jump_back();
             }
+            ('\'', KeyCode::Char(c)) => hex::marks::goto_mark(app, c),
+            _ => {}
         }
+        return Ok(false);
     }
 
+    // a count typed before the key, like `20j`; `0` alone still goes to the
+    // beginning of the line
+    if let KeyCode::Char(c @ '0'..='9') = key.code
+        && !key.modifiers.contains(KeyModifiers::ALT)
+        && (c != '0' || app.hex_view.count.is_some())
+    {
+        let digit = c as usize - '0' as usize;
+        let count = app.hex_view.count.unwrap_or(0);
+        app.hex_view.count = Some(count.saturating_mul(10).saturating_add(digit));
+        return Ok(false);
+    }
+    let count = app.hex_view.count.take().unwrap_or(1).max(1);
+
     // it is important to call goto as it looks for the offset in the
     // cache and, in case it is not there, it reads the needed block, and
     // also checks and updates offset position, cursor position, etc.
     match key.code {
         // move left
-        KeyCode::Left => {
-            if app.hex_view.offset > 0 {
-                app.goto(app.hex_view.offset - 1);
-            }
-        }
+        KeyCode::Left => retreat(app, count, 1),
         KeyCode::Char('h') => {
             if key.modifiers.contains(KeyModifiers::ALT) {
                 if let Some(b) = app.read_u8(app.hex_view.offset) {
@@ -72,23 +137,30 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                         app.hex_view.highlights.insert(b);
                     }
                 }
-            } else if app.hex_view.offset > 0 {
-                app.goto(app.hex_view.offset - 1);
+            } else {
+                retreat(app, count, 1);
             }
         }
         // move right
         KeyCode::Right | KeyCode::Char('l') if !key.modifiers.contains(KeyModifiers::ALT) => {
-            app.goto(app.hex_view.offset + 1);
+            advance(app, count, 1);
         }
         // move up
         KeyCode::Up | KeyCode::Char('k') => {
-            if app.hex_view.offset >= app.config.hex_mode_bytes_per_line {
-                app.goto(app.hex_view.offset - app.config.hex_mode_bytes_per_line);
-            }
+            retreat(app, count, app.config.hex_mode_bytes_per_line);
+        }
+        // marks
+        KeyCode::Char('m') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::marks::open_marks(app);
+        }
+        KeyCode::Char(c @ ('m' | '\'')) => app.hex_view.pending_key = Some(c),
+        // jump list
+        KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::jumps::open_jumps(app);
         }
         // move down
         KeyCode::Down | KeyCode::Char('j') => {
-            app.goto(app.hex_view.offset + app.config.hex_mode_bytes_per_line);
+            advance(app, count, app.config.hex_mode_bytes_per_line);
         }
         // BOL
         KeyCode::Char('g') => app.goto(0),
@@ -100,17 +172,17 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
         }
         // EOF
-        KeyCode::Char('G') => app.goto(app.file_info.size - 1),
+        KeyCode::Char('G') => app.goto(app.file_info.size.saturating_sub(1)),
 
         // EOL
         KeyCode::End | KeyCode::Char('$') => {
             // `Ctrl+End` goes to EOF too
             if key.modifiers.contains(KeyModifiers::CONTROL) {
-                app.goto(app.file_info.size - 1);
+                app.goto(app.file_info.size.saturating_sub(1));
             } else {
                 // `End` or `$` alone go to EOL
                 if app.hex_view.offset + app.config.hex_mode_bytes_per_line > app.file_info.size {
-                    app.goto(app.file_info.size - 1);
+                    app.goto(app.file_info.size.saturating_sub(1));
                 } else {
                     app.goto(
                         app.hex_view.offset + app.config.hex_mode_bytes_per_line
@@ -122,19 +194,22 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
         // go down one page
         KeyCode::PageDown => {
-            app.goto(app.hex_view.offset + app.reader.page_current_size);
+            advance(app, count, app.reader.page_current_size);
         }
         // go up one page
         KeyCode::PageUp => {
-            if app.hex_view.offset > app.reader.page_current_size {
-                app.goto(app.hex_view.offset - app.reader.page_current_size);
-            } else {
-                app.goto(0);
-            }
+            let size = count.saturating_mul(app.reader.page_current_size);
+            app.goto(app.hex_view.offset.saturating_sub(size));
         }
-        // go to last visited offset
+        // go back and forth in the jump list; Ctrl+i arrives as Tab
         KeyCode::Backspace => {
-            app.goto(app.hex_view.last_visited_offset);
+            app.jump_back();
+        }
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.jump_back();
+        }
+        KeyCode::Tab => {
+            app.jump_forward();
         }
         // add a bookmark
         KeyCode::Char('+') => {
@@ -157,7 +232,7 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 } else {
                     // Go to last bookmark
                     if let Some(&ofs) = app.hex_view.bookmarks.last() {
-                        app.goto(ofs);
+                        app.jump(ofs);
                     }
                 }
             }
@@ -169,7 +244,7 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 let n = (c as u8 - b'0') as usize;
                 if let Some(&ofs) = app.hex_view.bookmarks.get(n - 1) {
                     // if there's a value there, go to it
-                    app.goto(ofs);
+                    app.jump(ofs);
                 }
             }
         }
@@ -181,13 +256,31 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.goto(app.hex_view.offset - app.hex_view.cursor.x)
             }
         }
+        // follow the (w|d|q)word under the cursor; the uppercase keys take
+        // it as relative to the cursor
+        KeyCode::Char(c @ ('w' | 'd' | 'q' | 'W' | 'D' | 'Q'))
+            if key.modifiers.contains(KeyModifiers::ALT) =>
+        {
+            let size = match c.to_ascii_lowercase() {
+                'w' => 2,
+                'd' => 4,
+                _ => 8,
+            };
+            match app.pointer_target(size, c.is_ascii_uppercase()) {
+                Ok(target) => app.jump(target),
+                Err(e) => {
+                    crate::beep!();
+                    App::log(app, e);
+                }
+            }
+        }
         // advance (w|d|q)word
-        KeyCode::Char('w') => app.goto(app.hex_view.offset + 2),
-        KeyCode::Char('W') => app.goto(app.hex_view.offset.saturating_sub(2)),
-        KeyCode::Char('d') => app.goto(app.hex_view.offset + 4),
-        KeyCode::Char('D') => app.goto(app.hex_view.offset.saturating_sub(4)),
-        KeyCode::Char('q') => app.goto(app.hex_view.offset + 8),
-        KeyCode::Char('Q') => app.goto(app.hex_view.offset.saturating_sub(8)),
+        KeyCode::Char('w') => advance(app, count, 2),
+        KeyCode::Char('W') => app.goto(app.hex_view.offset.saturating_sub(count.saturating_mul(2))),
+        KeyCode::Char('d') => advance(app, count, 4),
+        KeyCode::Char('D') => app.goto(app.hex_view.offset.saturating_sub(count.saturating_mul(4))),
+        KeyCode::Char('q') => advance(app, count, 8),
+        KeyCode::Char('Q') => app.goto(app.hex_view.offset.saturating_sub(count.saturating_mul(8))),
 
         // next other byte
         KeyCode::Char('o') => goto_other_byte(app, 1),
@@ -207,14 +300,16 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 && app.hex_view.offset < app.file_info.size
                 && key.modifiers.contains(KeyModifiers::CONTROL)
             {
-                app.state = UIState::HexEditing;
-                let ofs = app.hex_view.offset;
-                if let Some(s) = app.hex_view.changed_bytes.get(&ofs) {
-                    if let Ok(b) = u8::from_str_radix(s, 16) {
-                        hex::edit::fill_with(app, b.wrapping_add(1), false);
-                    }
-                } else if let Some(b) = app.read_u8(ofs) {
-                    hex::edit::fill_with(app, b.wrapping_add(1), false);
+                // the count wraps around like the byte does
+                add_to_byte(app, count as u8);
+            } else if !key.modifiers.contains(KeyModifiers::CONTROL) {
+                // append: insert bytes after the cursor
+                if app.file_info.is_read_only {
+                    crate::beep!();
+                } else {
+                    app.state = UIState::HexEditing;
+                    app.hex_view.inserting = true;
+                    app.hex_view.appending = app.file_info.size > 0;
                 }
             }
         }
@@ -225,14 +320,13 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 && app.hex_view.offset < app.file_info.size
                 && key.modifiers.contains(KeyModifiers::CONTROL)
             {
-                app.state = UIState::HexEditing;
-                let ofs = app.hex_view.offset;
-                if let Some(s) = app.hex_view.changed_bytes.get(&ofs) {
-                    if let Ok(b) = u8::from_str_radix(s, 16) {
-                        hex::edit::fill_with(app, b.wrapping_sub(1), false);
-                    }
-                } else if let Some(b) = app.read_u8(ofs) {
-                    hex::edit::fill_with(app, b.wrapping_sub(1), false);
+                add_to_byte(app, (count as u8).wrapping_neg());
+            } else if !key.modifiers.contains(KeyModifiers::CONTROL) {
+                // delete byte under the cursor
+                if app.file_info.is_read_only || app.file_info.size == 0 {
+                    crate::beep!();
+                } else {
+                    hex::edit::delete_bytes(app, app.hex_view.offset, 1);
                 }
             }
         }
@@ -242,6 +336,29 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
             app.state = UIState::DialogHelp;
             app.dialog_renderer = Some(hex::help::dialog_help_draw);
         }
+        // paste over the bytes under the cursor
+        KeyCode::Char('p') => hex::clipboard::paste(app, app.hex_view.offset, false, None),
+        // paste before the cursor
+        KeyCode::Char('P') => hex::clipboard::paste(app, app.hex_view.offset, true, None),
+        // data inspector
+        KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::inspector::toggle_inspector(app);
+        }
+        // insert bytes before the cursor
+        KeyCode::Char('i') => {
+            if app.file_info.is_read_only {
+                crate::beep!();
+            } else {
+                app.state = UIState::HexEditing;
+                app.hex_view.inserting = true;
+            }
+        }
+        // redo
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if !hex::history::redo(app) {
+                crate::beep!(); // beep if there's nothing to redo
+            }
+        }
         // replace
         KeyCode::Char('r') => {
             if app.file_info.is_read_only {
@@ -250,6 +367,10 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.state = UIState::HexEditing;
             }
         }
+        // file structure
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::structure::open_structure(app);
+        }
         // strings list
         KeyCode::Char('s') => {
             Commands::strings(app);
@@ -266,6 +387,14 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
             app.hex_view.search.direction = SearchDirection::Backward;
             app.dialog_renderer = Some(hex::search::dialog_search_draw);
         }
+        // every match of the last search
+        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::find_all::find_all(app);
+        }
+        // pending changes
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+            hex::changes::open_changes(app);
+        }
         // names and search next (forward)
         KeyCode::Char('n') => {
             // names
@@ -278,13 +407,13 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
             } else {
                 // search next (forward)
                 app.hex_view.search.direction = SearchDirection::Forward;
-                search_next(app);
+                search_next(app, count);
             }
         }
         // search next (backward)
         KeyCode::Char('N') => {
             app.hex_view.search.direction = SearchDirection::Backward;
-            search_next(app);
+            search_next(app, count);
         }
         // comment
         KeyCode::Char(';') => {
@@ -293,6 +422,10 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.dialog_renderer = Some(hex::comment::dialog_comment_draw);
             }
         }
+        // assemble an instruction at the cursor
+        KeyCode::Char('A') => disasm::assemble::open_assemble(app),
+        // data inspector
+        KeyCode::Char('I') => hex::inspector::focus_inspector(app),
         // selection
         KeyCode::Char('v') => {
             if app.file_info.size > 0 {
@@ -302,12 +435,8 @@ pub fn hex_mode_events(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
         }
         // undo
-        KeyCode::Char('u') => {
-            if let Some(ofs) = app.hex_view.changed_history.pop() {
-                let _ = app.hex_view.changed_bytes.remove(&ofs);
-            } else {
-                crate::beep!(); // beep if there's nothing to undo
-            }
+        KeyCode::Char('u') if !hex::history::undo(app) => {
+            crate::beep!(); // beep if there's nothing to undo
         }
         _ => {}
     }
@@ -0,0 +1,295 @@
+use ratatui::{
+    Frame,
+    crossterm::event::KeyModifiers,
+    layout::Alignment,
+    widgets::{Block, Clear, List, ListItem, ListState, Padding},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode};
+use std::io::Result;
+
+use crate::{
+    app::App,
+    config::{Endian, FIND_ALL_BLOCK, FIND_ALL_LIMIT},
+    editor::UIState,
+    hex::search::{Needle, SearchMode},
+    util::center_widget,
+};
+
+// matches starting near the end of a block are looked for this far into the
+// next one; longer regex matches are cut short
+const MAX_MATCH_LEN: usize = 64 * 1024;
+
+/// Every match of a search. The file is searched a block at a time between
+/// key presses, so big files don't freeze the screen.
+#[derive(Debug, Default)]
+pub struct FindAll {
+    /// offset and length of each match, in file order
+    pub matches: Vec<(usize, usize)>,
+    pub list_state: ListState,
+    // mode and text searched, to reopen the list of the same search
+    query: String,
+    // the search in progress and where it goes on from
+    needle: Option<Needle>,
+    next: usize,
+}
+
+impl FindAll {
+    pub fn start(&mut self, query: String, needle: Needle) {
+        self.clear();
+        self.query = query;
+        self.needle = Some(needle);
+        self.list_state.select_first();
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.needle.is_some()
+    }
+
+    /// Whether the byte at `offset` is part of a match
+    pub fn contains(&self, offset: usize) -> bool {
+        let i = self.matches.partition_point(|&(start, _)| start <= offset);
+        i > 0 && {
+            let (start, len) = self.matches[i - 1];
+            offset < start + len.max(1)
+        }
+    }
+
+    /// Forget every match and stop searching
+    pub fn clear(&mut self) {
+        self.matches.clear();
+        self.query.clear();
+        self.needle = None;
+        self.next = 0;
+    }
+
+    // look for matches starting in the next `block` bytes of a file of `size`
+    // bytes; `window` holds the file from where the search goes on, at least
+    // `MAX_MATCH_LEN` bytes past the block. Returns false once the search is
+    // over
+    fn step(&mut self, window: &[u8], size: usize, block: usize) -> bool {
+        let Some(needle) = &self.needle else {
+            return false;
+        };
+        let block_len = block.min(size.saturating_sub(self.next)).min(window.len());
+
+        // matches don't overlap, so a long one isn't listed once per byte
+        let mut pos = 0;
+        while pos < block_len {
+            let Some((start, len)) = needle.find_at(window, pos) else {
+                break;
+            };
+            if start >= block_len {
+                break;
+            }
+            self.matches.push((self.next + start, len));
+            if self.matches.len() >= FIND_ALL_LIMIT {
+                self.needle = None;
+                return false;
+            }
+            pos = start + len.max(1);
+        }
+
+        self.next += pos.max(block_len).max(1);
+        if self.next >= size {
+            self.needle = None;
+        }
+        self.needle.is_some()
+    }
+}
+
+/// Search the next block of the file for the find all in progress
+pub fn find_all_step(app: &mut App) {
+    let find_all = &mut app.hex_view.find_all;
+    let window = app
+        .file_info
+        .slice(find_all.next, FIND_ALL_BLOCK + MAX_MATCH_LEN);
+    if !find_all.step(&window, app.file_info.size, FIND_ALL_BLOCK) {
+        let count = app.hex_view.find_all.matches.len();
+        App::log(app, format!("find all: {} matches", count));
+    }
+}
+
+/// List every match of the search typed last, searching again only if it
+/// has changed
+pub fn find_all(app: &mut App) {
+    let search = &app.hex_view.search;
+    let needle = match search.needle(app.config.endian) {
+        Some(Ok(needle)) => needle,
+        Some(Err(e)) => {
+            crate::beep!();
+            App::log(app, e);
+            return;
+        }
+        None => {
+            crate::beep!();
+            return;
+        }
+    };
+    let mut query = format!("{}: {}", search.mode.name(), search.input().value());
+    // numbers without `le` or `be` are read in the byte order set
+    if search.mode == SearchMode::Number {
+        query.push_str(match app.config.endian {
+            Endian::Little => " (LE)",
+            Endian::Big => " (BE)",
+        });
+    }
+    if query != app.hex_view.find_all.query {
+        app.hex_view.find_all.start(query, needle);
+    }
+    app.state = UIState::DialogFindAll;
+    app.dialog_renderer = Some(dialog_find_all_draw);
+}
+
+pub fn dialog_find_all_draw(app: &mut App, frame: &mut Frame) {
+    let find_all = &app.hex_view.find_all;
+    let mut items = Vec::with_capacity(find_all.matches.len());
+
+    for &(offset, len) in &find_all.matches {
+        let bytes = app.file_info.read(offset, len.min(8));
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let ascii: String = bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() {
+                    b as char
+                } else {
+                    app.config.hex_mode_non_graphic_char
+                }
+            })
+            .collect();
+        items.push(ListItem::from(format!(
+            "{}  {:>5}  {:<23}  {}",
+            app.format_addr(offset),
+            len,
+            hex.join(" "),
+            ascii
+        )));
+    }
+
+    let count = if find_all.is_searching() {
+        let percent = find_all.next * 100 / app.file_info.size.max(1);
+        format!("{}, {}%", find_all.matches.len(), percent)
+    } else if find_all.matches.len() >= FIND_ALL_LIMIT {
+        format!("{}+", FIND_ALL_LIMIT)
+    } else {
+        format!("{}", find_all.matches.len())
+    };
+
+    let list = List::new(items)
+        .style(app.config.theme.dialog)
+        .block(
+            Block::bordered()
+                .title(format!(" Matches ({}) ", count))
+                .title_bottom(format!(" {} ", find_all.query))
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        )
+        .highlight_style(app.config.theme.highlight)
+        .repeat_highlight_symbol(true);
+
+    let width = (frame.area().width * 3 / 4).max(80.min(frame.area().width));
+    let height = frame.area().height / 2 + 4;
+    let dialog_area = center_widget(width, height, frame.area());
+
+    // an empty list loses its selection, so it's made once matches come in
+    let list_state = &mut app.hex_view.find_all.list_state;
+    if list_state.selected().is_none() && !app.hex_view.find_all.matches.is_empty() {
+        list_state.select_first();
+    }
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_stateful_widget(list, dialog_area, list_state);
+}
+
+pub fn dialog_find_all_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        let list_state = &mut app.hex_view.find_all.list_state;
+        match key.code {
+            // the matches stay highlighted, and are still looked for
+            KeyCode::Esc => {
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                list_state.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                list_state.select_previous();
+            }
+            KeyCode::PageDown => {
+                list_state.scroll_down_by(30);
+            }
+            KeyCode::PageUp => {
+                list_state.scroll_up_by(30);
+            }
+            KeyCode::Home => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    list_state.select_first();
+                } else if let Some(n) = list_state.selected() {
+                    let new_index = n.saturating_sub(29);
+                    list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::End => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    list_state.select_last();
+                } else if let Some(n) = list_state.selected() {
+                    let new_index = n + 29;
+                    list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(&(offset, _)) = list_state
+                    .selected()
+                    .and_then(|i| app.hex_view.find_all.matches.get(i))
+                {
+                    app.jump(offset);
+                }
+                app.state = UIState::Normal;
+                app.dialog_renderer = None;
+            }
+            // forget every match, which are no longer highlighted
+            KeyCode::Char('D') => {
+                app.hex_view.find_all.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_all_test() {
+        // the window goes on past the block, like the one read from the file
+        let step = |find_all: &mut FindAll, hay: &[u8], block| {
+            find_all.step(&hay[find_all.next..], hay.len(), block)
+        };
+        let hay = b"xxabcabcxabcx";
+        let mut find_all = FindAll::default();
+        find_all.start("test".to_string(), Needle::Bytes(b"abc".to_vec()));
+
+        // the first match starts in the first block and ends in the next one
+        assert!(step(&mut find_all, hay, 3));
+        assert_eq!(find_all.matches, vec![(2, 3)]);
+        while step(&mut find_all, hay, 3) {}
+        assert_eq!(find_all.matches, vec![(2, 3), (5, 3), (9, 3)]);
+        assert!(!find_all.is_searching());
+
+        assert!(!find_all.contains(1));
+        assert!(find_all.contains(2));
+        assert!(find_all.contains(7));
+        assert!(!find_all.contains(8));
+        assert!(find_all.contains(11));
+        assert!(!find_all.contains(12));
+
+        // matches don't overlap
+        find_all.start("test".to_string(), Needle::Bytes(b"aa".to_vec()));
+        while step(&mut find_all, b"aaaaa", 2) {}
+        assert_eq!(find_all.matches, vec![(0, 2), (2, 2)]);
+    }
+}
@@ -1,10 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use ratatui::widgets::{ListState, TableState};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use tui_input::Input;
 
-use crate::hex::comment::Comment;
+use crate::hex::{
+    changes::ChangedRange, comment::Comment, find_all::FindAll, history::History,
+    inspector::Inspector, jumps::JumpList, marks::MarkEntry,
+};
+
+// symbols found in the file are listed along with the comments, but only
+// the comments are saved
+fn serialize_comments<S: Serializer>(list: &[Comment], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(list.iter().filter(|c| !c.symbol))
+}
 
 // used in hex view struct to track the cursor position
 #[derive(Default, Debug)]
@@ -15,33 +24,59 @@ pub struct Point {
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct HexView {
+    #[serde(skip)]
+    pub addr_width: usize, // digits of the offsets, worked out once per frame
+    #[serde(skip)]
+    pub appending: bool,
     #[serde(skip)]
     pub ascii_state: TableState,
     pub bookmarks: Vec<usize>,
     #[serde(skip)]
     pub changed_bytes: HashMap<usize, String>,
     #[serde(skip)]
-    pub changed_history: Vec<usize>,
+    pub changes: Vec<ChangedRange>, // shown in the Changes list
+    #[serde(skip)]
+    pub changes_list_state: ListState,
     #[serde(skip)]
     pub comment_input: Input, // the input comment widget (tui-input)
 
     // `comment_name_list` is used to show comments in Names list
     // and also on the conversion from selected item on the list
     // to file offset passed to goto()
+    #[serde(serialize_with = "serialize_comments")]
     pub comment_name_list: Vec<Comment>,
 
     // `comments` store the comments internally as it is much easier
     // to handle that with a hash map
     pub comments: HashMap<usize, String>,
 
+    #[serde(skip)]
+    pub count: Option<usize>, // typed before a key, like the 20 of `20j`
     #[serde(skip)]
     pub cursor: Point,
     #[serde(skip)]
     pub editing_hex: bool,
     #[serde(skip)]
+    pub find_all: FindAll, // matches of find all, highlighted until cleared
+    #[serde(skip)]
     pub highlights: HashSet<u8>, // byte highlight
     #[serde(skip)]
-    pub last_visited_offset: usize,
+    pub history: History,
+    #[serde(skip)]
+    pub inserting: bool,
+    #[serde(skip)]
+    pub inspector: Inspector,
+    #[serde(default)]
+    pub jumps: JumpList,
+    #[serde(skip)]
+    pub jumps_list_state: ListState,
+    #[serde(skip)]
+    pub mark_list: Vec<MarkEntry>, // shown in the Marks list
+    // lowercase marks; uppercase ones are kept out of the database
+    #[serde(default)]
+    pub marks: BTreeMap<char, usize>,
+    #[serde(skip)]
+    pub marks_list_state: ListState,
     #[serde(skip)]
     pub names_list_state: ListState,
     #[serde(skip)]
@@ -53,11 +88,15 @@ pub struct HexView {
     #[serde(skip)]
     pub offset: usize,
     #[serde(skip)]
+    pub pending_key: Option<char>, // first key of `m{a-zA-Z}` and `'{a-zA-Z}`
+    #[serde(skip)]
     pub search: crate::hex::search::Search,
     #[serde(skip)]
     pub selection: crate::hex::selection::Selection,
     #[serde(skip)]
     pub strings_regex_input: Input,
     #[serde(skip)]
+    pub structure_list_state: ListState,
+    #[serde(skip)]
     pub table_state: TableState,
 }
@@ -0,0 +1,166 @@
+use crate::{app::App, hex::edit, piece_table::Piece};
+
+/// A single change made to the buffer
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// The pending value of a byte in `changed_bytes` went from `old` to `new`.
+    /// `None` means the byte had no pending value.
+    Replace {
+        offset: usize,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Insert {
+        offset: usize,
+        bytes: Vec<u8>,
+    },
+    /// `pieces` held the deleted bytes and `changes` holds the pending values
+    /// dropped along with them
+    Delete {
+        offset: usize,
+        pieces: Vec<Piece>,
+        changes: Vec<(usize, String)>,
+    },
+    /// Deleted bytes were put back as they were
+    Restore {
+        offset: usize,
+        pieces: Vec<Piece>,
+    },
+}
+
+impl Edit {
+    fn offset(&self) -> usize {
+        match self {
+            Edit::Replace { offset, .. }
+            | Edit::Insert { offset, .. }
+            | Edit::Delete { offset, .. }
+            | Edit::Restore { offset, .. } => *offset,
+        }
+    }
+}
+
+/// Edits that are undone and redone at once, e.g. all the bytes of a selection fill
+pub type Transaction = Vec<Edit>;
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Transaction>,
+    redo: Vec<Transaction>,
+    // transaction being recorded between begin() and commit()
+    pending: Option<Transaction>,
+}
+
+impl History {
+    /// Group every edit recorded until commit() is called in a single transaction
+    pub fn begin(&mut self) {
+        if self.pending.is_none() {
+            self.pending = Some(Transaction::new());
+        }
+    }
+
+    pub fn commit(&mut self) {
+        if let Some(transaction) = self.pending.take()
+            && !transaction.is_empty()
+        {
+            self.undo.push(transaction);
+        }
+    }
+
+    /// Record an edit. Outside of begin()/commit(), it is a transaction by itself.
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        match &mut self.pending {
+            Some(transaction) => transaction.push(edit),
+            None => self.undo.push(vec![edit]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.pending = None;
+    }
+}
+
+fn set_value(app: &mut App, offset: usize, value: &Option<String>) {
+    match value {
+        Some(v) => app.hex_view.changed_bytes.insert(offset, v.clone()),
+        None => app.hex_view.changed_bytes.remove(&offset),
+    };
+}
+
+fn apply(app: &mut App, edit: &Edit) {
+    match edit {
+        Edit::Replace { offset, new, .. } => set_value(app, *offset, new),
+        Edit::Insert { offset, bytes } => edit::insert_untracked(app, *offset, bytes),
+        Edit::Delete { offset, pieces, .. } => {
+            let len = pieces.iter().map(|p| p.len).sum();
+            edit::delete_untracked(app, *offset, len);
+        }
+        Edit::Restore { offset, pieces } => edit::restore_untracked(app, *offset, pieces),
+    }
+}
+
+fn revert(app: &mut App, edit: &Edit) {
+    match edit {
+        Edit::Replace { offset, old, .. } => set_value(app, *offset, old),
+        Edit::Insert { offset, bytes } => {
+            edit::delete_untracked(app, *offset, bytes.len());
+        }
+        Edit::Delete {
+            offset,
+            pieces,
+            changes,
+        } => {
+            edit::restore_untracked(app, *offset, pieces);
+            app.hex_view.changed_bytes.extend(changes.iter().cloned());
+        }
+        Edit::Restore { offset, pieces } => {
+            let len = pieces.iter().map(|p| p.len).sum();
+            edit::delete_untracked(app, *offset, len);
+        }
+    }
+}
+
+// place the cursor where the buffer was changed
+fn goto_edit(app: &mut App, offset: usize) {
+    app.goto(offset.min(app.file_info.size.saturating_sub(1)));
+}
+
+/// Undo the last transaction. Returns false if there's nothing to undo.
+pub fn undo(app: &mut App) -> bool {
+    app.hex_view.history.commit();
+
+    let Some(transaction) = app.hex_view.history.undo.pop() else {
+        return false;
+    };
+
+    for edit in transaction.iter().rev() {
+        revert(app, edit);
+    }
+
+    if let Some(edit) = transaction.first() {
+        goto_edit(app, edit.offset());
+    }
+    app.hex_view.history.redo.push(transaction);
+    true
+}
+
+/// Redo the last undone transaction. Returns false if there's nothing to redo.
+pub fn redo(app: &mut App) -> bool {
+    app.hex_view.history.commit();
+
+    let Some(transaction) = app.hex_view.history.redo.pop() else {
+        return false;
+    };
+
+    for edit in transaction.iter() {
+        apply(app, edit);
+    }
+
+    if let Some(edit) = transaction.first() {
+        goto_edit(app, edit.offset());
+    }
+    app.hex_view.history.undo.push(transaction);
+    true
+}
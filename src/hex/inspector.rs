@@ -0,0 +1,764 @@
+use std::{
+    io::Result,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode},
+    layout::{Constraint, Rect},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+use crate::{app::App, editor::UIState, hex::edit::set_change};
+
+/// Width reserved for the panel when the bytes per line are set to `auto`
+pub const INSPECTOR_WIDTH: u16 = 46;
+
+// columns the hex view needs beside the panel: offsets and one byte
+const MIN_HEX_WIDTH: u16 = 17;
+
+// seconds between 1601-01-01 (FILETIME) and 1970-01-01 (Unix)
+const FILETIME_EPOCH: i64 = 11_644_473_600;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Int {
+        size: usize,
+        signed: bool,
+        big: bool,
+    },
+    Float {
+        size: usize,
+        big: bool,
+    },
+    Uleb128,
+    Sleb128,
+    Unix,
+    Filetime,
+    Dos,
+    Guid,
+    Ipv4,
+    Ipv6,
+    Utf8,
+    Utf16 {
+        big: bool,
+    },
+}
+
+const fn int(size: usize, signed: bool, big: bool) -> Kind {
+    Kind::Int { size, signed, big }
+}
+
+/// Rows of the panel
+pub const FIELDS: [(&str, Kind); 29] = [
+    ("i8", int(1, true, false)),
+    ("u8", int(1, false, false)),
+    ("i16 le", int(2, true, false)),
+    ("i16 be", int(2, true, true)),
+    ("u16 le", int(2, false, false)),
+    ("u16 be", int(2, false, true)),
+    ("i32 le", int(4, true, false)),
+    ("i32 be", int(4, true, true)),
+    ("u32 le", int(4, false, false)),
+    ("u32 be", int(4, false, true)),
+    ("i64 le", int(8, true, false)),
+    ("i64 be", int(8, true, true)),
+    ("u64 le", int(8, false, false)),
+    ("u64 be", int(8, false, true)),
+    (
+        "f32 le",
+        Kind::Float {
+            size: 4,
+            big: false,
+        },
+    ),
+    ("f32 be", Kind::Float { size: 4, big: true }),
+    (
+        "f64 le",
+        Kind::Float {
+            size: 8,
+            big: false,
+        },
+    ),
+    ("f64 be", Kind::Float { size: 8, big: true }),
+    ("uleb128", Kind::Uleb128),
+    ("sleb128", Kind::Sleb128),
+    ("unix", Kind::Unix),
+    ("filetime", Kind::Filetime),
+    ("dos time", Kind::Dos),
+    ("guid", Kind::Guid),
+    ("ipv4", Kind::Ipv4),
+    ("ipv6", Kind::Ipv6),
+    ("utf-8", Kind::Utf8),
+    ("utf-16le", Kind::Utf16 { big: false }),
+    ("utf-16be", Kind::Utf16 { big: true }),
+];
+
+#[derive(Debug, Default)]
+pub struct Inspector {
+    pub visible: bool,
+    pub table_state: TableState,
+    // editing the selected value
+    pub editing: bool,
+    pub input: Input,
+}
+
+impl Inspector {
+    /// Columns taken by the panel
+    pub fn width(&self) -> u16 {
+        if self.visible { INSPECTOR_WIDTH } else { 0 }
+    }
+
+    /// Whether the panel fits next to the hex view in `screen_width` columns
+    pub fn fits(screen_width: u16) -> bool {
+        screen_width >= INSPECTOR_WIDTH + MIN_HEX_WIDTH
+    }
+
+    /// Bytes per line that fit in `screen_width` columns, beside the panel if
+    /// it's shown
+    pub fn bytes_per_line(&self, screen_width: u16) -> usize {
+        let width = screen_width.saturating_sub(self.width());
+        ((width.saturating_sub(9) / 4) as usize)
+            .saturating_sub(1)
+            .max(1)
+    }
+}
+
+/// A decoded value: `text` is what editing starts from, `note` is shown
+/// after it
+#[derive(Debug, PartialEq)]
+pub struct Value {
+    pub text: String,
+    pub note: Option<String>,
+}
+
+impl Value {
+    fn new(text: String) -> Self {
+        Value { text, note: None }
+    }
+}
+
+fn uint(bytes: &[u8], size: usize, big: bool) -> Option<u64> {
+    let bytes = bytes.get(..size)?;
+    let fold = |v: u64, b: &u8| v << 8 | *b as u64;
+    Some(if big {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    })
+}
+
+fn uint_bytes(v: u64, size: usize, big: bool) -> Vec<u8> {
+    let mut bytes = v.to_le_bytes()[..size].to_vec();
+    if big {
+        bytes.reverse();
+    }
+    bytes
+}
+
+// days since 1970-01-01 of a date, and back (Howard Hinnant's algorithms)
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + (m <= 2) as i64, m, d)
+}
+
+fn format_time(secs: i64) -> Option<String> {
+    let (y, m, d) = civil_from_days(secs.div_euclid(86400));
+    let t = secs.rem_euclid(86400);
+    (1..=9999).contains(&y).then(|| {
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            y,
+            m,
+            d,
+            t / 3600,
+            t / 60 % 60,
+            t % 60
+        )
+    })
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// "YYYY-MM-DD[ HH:MM[:SS]]", with years that format_time() shows
+fn parse_time(text: &str) -> Option<(i64, i64, i64, i64, i64, i64)> {
+    let (date, time) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    let mut time: Vec<i64> = if time.trim().is_empty() {
+        vec![]
+    } else {
+        time.trim()
+            .split(':')
+            .map(|s| s.parse().ok())
+            .collect::<Option<_>>()?
+    };
+    time.resize(3, 0);
+    let [y, m, d] = date[..] else {
+        return None;
+    };
+    let [h, min, s] = time[..] else {
+        return None;
+    };
+    let valid = (1..=9999).contains(&y)
+        && (1..=12).contains(&m)
+        && (1..=days_in_month(y, m)).contains(&d)
+        && (0..24).contains(&h)
+        && (0..60).contains(&min)
+        && (0..60).contains(&s);
+    valid.then_some((y, m, d, h, min, s))
+}
+
+fn time_to_secs(text: &str) -> Option<i64> {
+    let (y, m, d, h, min, s) = parse_time(text)?;
+    days_from_civil(y, m, d)
+        .checked_mul(86400)?
+        .checked_add(h * 3600 + min * 60 + s)
+}
+
+fn format_float<T>(v: T) -> String
+where
+    T: Copy + Into<f64> + std::fmt::Display + std::fmt::LowerExp,
+{
+    let f: f64 = v.into();
+    if f != 0.0 && f.is_finite() && (f.abs() >= 1e16 || f.abs() < 1e-6) {
+        format!("{:e}", v)
+    } else {
+        format!("{}", v)
+    }
+}
+
+fn leb128(bytes: &[u8], signed: bool) -> Option<(i128, usize)> {
+    let mut value: i128 = 0;
+    for (i, b) in bytes.iter().take(10).enumerate() {
+        value |= ((b & 0x7f) as i128) << (7 * i);
+        if b & 0x80 == 0 {
+            let bits = 7 * (i + 1);
+            if signed && b & 0x40 != 0 {
+                value -= 1 << bits;
+            }
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn leb128_bytes(mut value: i128, signed: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = if signed {
+            (value == 0 && b & 0x40 == 0) || (value == -1 && b & 0x40 != 0)
+        } else {
+            value == 0
+        };
+        if done {
+            bytes.push(b);
+            return bytes;
+        }
+        bytes.push(b | 0x80);
+    }
+}
+
+fn char_value(c: char) -> Value {
+    let shown = if c.is_control() {
+        None
+    } else {
+        Some(format!("'{}'", c))
+    };
+    Value {
+        text: format!("U+{:04X}", c as u32),
+        note: shown,
+    }
+}
+
+// a character, written as itself or as U+XXXX
+fn parse_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => {
+            let hex = text
+                .strip_prefix("U+")
+                .or_else(|| text.strip_prefix("u+"))?;
+            char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+        }
+    }
+}
+
+fn parse_int(text: &str) -> Option<i128> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => text.parse::<i128>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Decode the value of kind `kind` at the start of `bytes`
+pub fn decode(kind: Kind, bytes: &[u8]) -> Option<Value> {
+    let value = match kind {
+        Kind::Int { size, signed, big } => {
+            let v = uint(bytes, size, big)?;
+            let text = if signed {
+                let shift = 64 - size * 8;
+                (((v << shift) as i64) >> shift).to_string()
+            } else {
+                v.to_string()
+            };
+            Value::new(text)
+        }
+        Kind::Float { size: 4, big } => {
+            Value::new(format_float(f32::from_bits(uint(bytes, 4, big)? as u32)))
+        }
+        Kind::Float { big, .. } => Value::new(format_float(f64::from_bits(uint(bytes, 8, big)?))),
+        Kind::Uleb128 | Kind::Sleb128 => {
+            let (v, len) = leb128(bytes, kind == Kind::Sleb128)?;
+            Value {
+                text: v.to_string(),
+                note: Some(if len == 1 {
+                    "(1 byte)".to_string()
+                } else {
+                    format!("({} bytes)", len)
+                }),
+            }
+        }
+        Kind::Unix => Value::new(format_time(uint(bytes, 4, false)? as i64)?),
+        Kind::Filetime => {
+            let secs = (uint(bytes, 8, false)? / 10_000_000) as i64 - FILETIME_EPOCH;
+            Value::new(format_time(secs)?)
+        }
+        Kind::Dos => {
+            let (time, date) = (uint(bytes, 2, false)?, uint(bytes.get(2..)?, 2, false)?);
+            let (y, m, d) = (1980 + (date >> 9), date >> 5 & 0xf, date & 0x1f);
+            let (h, min, s) = (time >> 11, time >> 5 & 0x3f, (time & 0x1f) * 2);
+            let text = format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, m, d, h, min, s);
+            parse_time(&text)?;
+            Value::new(text)
+        }
+        Kind::Guid => {
+            let b = bytes.get(..16)?;
+            Value::new(format!(
+                "{:08X}-{:04X}-{:04X}-{}-{}",
+                uint(b, 4, false)?,
+                uint(&b[4..], 2, false)?,
+                uint(&b[6..], 2, false)?,
+                ::hex::encode_upper(&b[8..10]),
+                ::hex::encode_upper(&b[10..16])
+            ))
+        }
+        Kind::Ipv4 => {
+            Value::new(Ipv4Addr::from(<[u8; 4]>::try_from(bytes.get(..4)?).ok()?).to_string())
+        }
+        Kind::Ipv6 => {
+            Value::new(Ipv6Addr::from(<[u8; 16]>::try_from(bytes.get(..16)?).ok()?).to_string())
+        }
+        Kind::Utf8 => {
+            let len = match bytes.first()? {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            let s = std::str::from_utf8(bytes.get(..len)?).ok()?;
+            char_value(s.chars().next()?)
+        }
+        Kind::Utf16 { big } => {
+            let units = [
+                uint(bytes, 2, big)? as u16,
+                uint(bytes.get(2..).unwrap_or(&[]), 2, big).unwrap_or(0) as u16,
+            ];
+            char_value(char::decode_utf16(units).next()?.ok()?)
+        }
+    };
+    Some(value)
+}
+
+/// Bytes for `text` read as a value of kind `kind`
+pub fn encode(kind: Kind, text: &str) -> std::result::Result<Vec<u8>, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid value: {}", text);
+    let bytes = match kind {
+        Kind::Int { size, signed, big } => {
+            let v = parse_int(text).ok_or_else(invalid)?;
+            let bits = size as u32 * 8;
+            let (min, max) = if signed {
+                (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+            } else {
+                (0, (1i128 << bits) - 1)
+            };
+            if v < min || v > max {
+                return Err(format!("Out of range: {}", text));
+            }
+            uint_bytes(v as u64, size, big)
+        }
+        Kind::Float { size, big } => {
+            let bits = if size == 4 {
+                text.parse::<f32>().map_err(|_| invalid())?.to_bits() as u64
+            } else {
+                text.parse::<f64>().map_err(|_| invalid())?.to_bits()
+            };
+            uint_bytes(bits, size, big)
+        }
+        Kind::Uleb128 | Kind::Sleb128 => {
+            let signed = kind == Kind::Sleb128;
+            let v = parse_int(text).ok_or_else(invalid)?;
+            if !signed && v < 0 {
+                return Err(format!("Out of range: {}", text));
+            }
+            leb128_bytes(v, signed)
+        }
+        Kind::Unix => {
+            let secs = time_to_secs(text).ok_or_else(invalid)?;
+            let secs = u32::try_from(secs).map_err(|_| format!("Out of range: {}", text))?;
+            secs.to_le_bytes().to_vec()
+        }
+        Kind::Filetime => {
+            let out_of_range = || format!("Out of range: {}", text);
+            let secs = time_to_secs(text).ok_or_else(invalid)?;
+            let ticks = secs
+                .checked_add(FILETIME_EPOCH)
+                .and_then(|secs| u64::try_from(secs).ok())
+                .and_then(|secs| secs.checked_mul(10_000_000))
+                .ok_or_else(out_of_range)?;
+            ticks.to_le_bytes().to_vec()
+        }
+        Kind::Dos => {
+            let (y, m, d, h, min, s) = parse_time(text).ok_or_else(invalid)?;
+            if !(1980..=2107).contains(&y) {
+                return Err(format!("Out of range: {}", text));
+            }
+            let date = (((y - 1980) << 9) | (m << 5) | d) as u16;
+            let time = ((h << 11) | (min << 5) | (s / 2)) as u16;
+            [time.to_le_bytes(), date.to_le_bytes()].concat()
+        }
+        Kind::Guid => {
+            let hex: String = text
+                .trim_matches(['{', '}'])
+                .chars()
+                .filter(|c| *c != '-')
+                .collect();
+            let b = ::hex::decode(&hex).map_err(|_| invalid())?;
+            if b.len() != 16 {
+                return Err(invalid());
+            }
+            let mut bytes = b.clone();
+            bytes[..4].reverse();
+            bytes[4..6].reverse();
+            bytes[6..8].reverse();
+            bytes
+        }
+        Kind::Ipv4 => text
+            .parse::<Ipv4Addr>()
+            .map_err(|_| invalid())?
+            .octets()
+            .to_vec(),
+        Kind::Ipv6 => text
+            .parse::<Ipv6Addr>()
+            .map_err(|_| invalid())?
+            .octets()
+            .to_vec(),
+        Kind::Utf8 => parse_char(text)
+            .ok_or_else(invalid)?
+            .to_string()
+            .into_bytes(),
+        Kind::Utf16 { big } => {
+            let c = parse_char(text).ok_or_else(invalid)?;
+            let mut units = [0; 2];
+            c.encode_utf16(&mut units)
+                .iter()
+                .flat_map(|u| uint_bytes(*u as u64, 2, big))
+                .collect()
+        }
+    };
+    Ok(bytes)
+}
+
+impl App {
+    // bytes at the cursor, pending changes included, enough for any field
+    fn inspected_bytes(&mut self) -> Vec<u8> {
+        let offset = self.hex_view.offset;
+        (offset..offset + 16)
+            .map_while(|ofs| self.read_changed_u8(ofs))
+            .collect()
+    }
+}
+
+pub fn inspector_draw(app: &mut App, frame: &mut Frame, area: Rect) {
+    let bytes = app.inspected_bytes();
+    let rows: Vec<Row> = FIELDS
+        .iter()
+        .map(|(name, kind)| {
+            let value = match decode(*kind, &bytes) {
+                Some(Value {
+                    text,
+                    note: Some(note),
+                }) => format!("{} {}", text, note),
+                Some(value) => value.text,
+                None => "-".to_string(),
+            };
+            Row::new([
+                Cell::from(*name).style(app.config.theme.offsets),
+                Cell::from(value),
+            ])
+        })
+        .collect();
+
+    // the selection is only shown while the panel has the focus
+    let focused = app.state == UIState::DialogInspector;
+    let table = Table::new(rows, [Constraint::Length(8), Constraint::Fill(1)])
+        .block(Block::new().borders(Borders::LEFT))
+        .style(app.config.theme.main)
+        .row_highlight_style(if focused {
+            app.config.theme.highlight
+        } else {
+            app.config.theme.main
+        });
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(table, area, &mut app.hex_view.inspector.table_state);
+}
+
+/// Show or hide the panel
+pub fn toggle_inspector(app: &mut App) {
+    let inspector = &mut app.hex_view.inspector;
+    if !inspector.visible && app.screen.width > 0 && !Inspector::fits(app.screen.width) {
+        crate::beep!();
+        App::log(app, "inspector: the window is too narrow".to_string());
+        return;
+    }
+    inspector.visible = !inspector.visible;
+    if inspector.table_state.selected().is_none() {
+        inspector.table_state.select_first();
+    }
+
+    // make room for it
+    if app.config.hex_mode_bytes_per_line_auto && app.screen.width > 0 {
+        app.config.hex_mode_bytes_per_line =
+            app.hex_view.inspector.bytes_per_line(app.screen.width);
+    }
+}
+
+/// Move the focus to the panel, showing it if needed
+pub fn focus_inspector(app: &mut App) {
+    if !app.hex_view.inspector.visible {
+        toggle_inspector(app);
+    }
+    app.state = UIState::DialogInspector;
+    app.dialog_renderer = Some(dialog_inspector_draw);
+}
+
+// the value being edited, in the command bar
+pub fn dialog_inspector_draw(app: &mut App, frame: &mut Frame) {
+    let inspector = &app.hex_view.inspector;
+    if !inspector.editing {
+        return;
+    }
+    let i = inspector.table_state.selected().unwrap_or(0);
+    let prompt = format!("{}: ", FIELDS[i].0);
+    let para = Paragraph::new(format!("{}{}", prompt, inspector.input.value()));
+
+    frame.render_widget(Clear, app.command_area);
+    frame.render_widget(para, app.command_area);
+    let x = prompt.len() + inspector.input.visual_cursor();
+    frame.set_cursor_position((app.command_area.x + x as u16, app.command_area.y));
+}
+
+// write the edited value over the bytes at the cursor
+fn write_value(app: &mut App) {
+    let i = app.hex_view.inspector.table_state.selected().unwrap_or(0);
+    let text = app.hex_view.inspector.input.value().to_string();
+    let offset = app.hex_view.offset;
+
+    let bytes = match encode(FIELDS[i].1, &text) {
+        Ok(bytes) if offset + bytes.len() <= app.file_info.size => bytes,
+        Ok(bytes) => {
+            crate::beep!();
+            App::log(
+                app,
+                format!("no room for {} bytes at {:x}", bytes.len(), offset),
+            );
+            return;
+        }
+        Err(e) => {
+            crate::beep!();
+            App::log(app, e);
+            return;
+        }
+    };
+
+    app.hex_view.history.begin();
+    for (i, b) in bytes.iter().enumerate() {
+        set_change(app, offset + i, format!("{:02X}", b));
+    }
+    app.hex_view.history.commit();
+    app.hex_view.inspector.editing = false;
+}
+
+pub fn dialog_inspector_events(app: &mut App, event: &Event) -> Result<bool> {
+    let Event::Key(key) = event else {
+        return Ok(false);
+    };
+
+    if app.hex_view.inspector.editing {
+        match key.code {
+            KeyCode::Esc => app.hex_view.inspector.editing = false,
+            KeyCode::Enter => write_value(app),
+            _ => {
+                app.hex_view.inspector.input.handle_event(event);
+            }
+        }
+        return Ok(false);
+    }
+
+    let state = &mut app.hex_view.inspector.table_state;
+    match key.code {
+        KeyCode::Esc | KeyCode::Tab => {
+            app.dialog_renderer = None;
+            app.state = UIState::Normal;
+        }
+        KeyCode::Down | KeyCode::Char('j')
+            if state.selected().is_some_and(|i| i + 1 < FIELDS.len()) =>
+        {
+            state.select_next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+        KeyCode::Home => state.select_first(),
+        KeyCode::End => state.select(Some(FIELDS.len() - 1)),
+        // edit the selected value, starting from the current one
+        KeyCode::Enter => {
+            if app.file_info.is_read_only {
+                crate::beep!();
+                return Ok(false);
+            }
+            let i = state.selected().unwrap_or(0);
+            let bytes = app.inspected_bytes();
+            let text = decode(FIELDS[i].1, &bytes)
+                .map(|v| v.text)
+                .unwrap_or_default();
+            app.hex_view.inspector.input = Input::new(text);
+            app.hex_view.inspector.editing = true;
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str) -> Kind {
+        FIELDS.iter().find(|(n, _)| *n == name).unwrap().1
+    }
+
+    fn text(name: &str, bytes: &[u8]) -> String {
+        decode(field(name), bytes)
+            .map(|v| v.text)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn decode_test() {
+        let bytes = [0xfe, 0xff, 0x00, 0x01];
+        assert_eq!(text("i8", &bytes), "-2");
+        assert_eq!(text("u8", &bytes), "254");
+        assert_eq!(text("i16 le", &bytes), "-2");
+        assert_eq!(text("u16 be", &bytes), "65279");
+        assert_eq!(text("u32 le", &bytes), "16842750");
+        assert_eq!(text("u64 le", &bytes), "");
+        assert_eq!(text("f32 le", &1.5f32.to_le_bytes()), "1.5");
+        assert_eq!(text("f64 be", &(-2.0f64).to_be_bytes()), "-2");
+        assert_eq!(text("uleb128", &[0xe5, 0x8e, 0x26]), "624485");
+        assert_eq!(text("sleb128", &[0xc0, 0xbb, 0x78]), "-123456");
+        assert_eq!(text("unix", &[0, 0, 0, 0]), "1970-01-01 00:00:00");
+        assert_eq!(
+            text("filetime", &0x01da_3c45_7689_c000u64.to_le_bytes()),
+            "2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            text("dos time", &[0x00, 0x60, 0x21, 0x58]),
+            "2024-01-01 12:00:00"
+        );
+        let guid = [
+            0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        assert_eq!(text("guid", &guid), "00112233-4455-6677-8899-AABBCCDDEEFF");
+        assert_eq!(text("ipv4", &[127, 0, 0, 1]), "127.0.0.1");
+        assert_eq!(
+            text("ipv6", &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+            "::1"
+        );
+        assert_eq!(text("utf-8", "é".as_bytes()), "U+00E9");
+        assert_eq!(text("utf-16le", &[0x3d, 0xd8, 0x00, 0xde]), "U+1F600");
+        assert_eq!(text("utf-8", &[0xff]), "");
+    }
+
+    #[test]
+    fn encode_test() {
+        // what is decoded encodes back to the same bytes
+        let bytes: Vec<u8> = (0x10..0x20).collect();
+        for (name, kind) in FIELDS {
+            // FILETIME is shown to the second
+            if kind == Kind::Filetime {
+                continue;
+            }
+            if let Some(value) = decode(kind, &bytes) {
+                let encoded = encode(kind, &value.text).unwrap();
+                assert_eq!(encoded, bytes[..encoded.len()], "{}", name);
+            }
+        }
+        assert_eq!(encode(field("i16 be"), "-2"), Ok(vec![0xff, 0xfe]));
+        assert_eq!(encode(field("u32 le"), "0x10"), Ok(vec![0x10, 0, 0, 0]));
+        assert_eq!(
+            encode(field("uleb128"), "624485"),
+            Ok(vec![0xe5, 0x8e, 0x26])
+        );
+        assert_eq!(
+            encode(field("sleb128"), "-123456"),
+            Ok(vec![0xc0, 0xbb, 0x78])
+        );
+        assert_eq!(encode(field("utf-8"), "é"), Ok("é".as_bytes().to_vec()));
+        assert!(encode(field("u8"), "256").is_err());
+        assert!(encode(field("i8"), "-129").is_err());
+        assert!(encode(field("unix"), "1969-12-31").is_err());
+        assert!(encode(field("unix"), "2023-02-31").is_err());
+        assert!(encode(field("unix"), "2024-02-29").is_ok());
+        assert!(encode(field("filetime"), "100000-01-01").is_err());
+        assert!(encode(field("filetime"), "1600-12-31").is_err());
+        assert!(encode(field("filetime"), "9999-12-31 23:59:59").is_ok());
+        assert!(encode(field("ipv4"), "1.2.3").is_err());
+    }
+}
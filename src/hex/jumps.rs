@@ -0,0 +1,270 @@
+use ratatui::{
+    Frame,
+    crossterm::event::KeyModifiers,
+    layout::Alignment,
+    widgets::{Block, Clear, List, ListItem, Padding},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode};
+use serde::{Deserialize, Serialize};
+use std::io::Result;
+
+use crate::{app::App, config::JUMP_LIST_SIZE, editor::UIState, util::center_widget};
+
+/// Offsets jumped from, oldest first, walked back and forth like Vim's
+/// `Ctrl+o` and `Ctrl+i`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JumpList {
+    pub entries: Vec<usize>,
+    // entry reached by going back; None when past the newest one
+    #[serde(skip)]
+    index: Option<usize>,
+}
+
+impl JumpList {
+    /// Record `offset` as the newest entry, dropping an older copy of it
+    pub fn record(&mut self, offset: usize) {
+        self.entries.retain(|&o| o != offset);
+        self.entries.push(offset);
+        if self.entries.len() > JUMP_LIST_SIZE {
+            self.entries.remove(0);
+        }
+        self.index = None;
+    }
+
+    /// Entry before the current one. Leaving the newest end records
+    /// `current`, so forward() can return to it.
+    pub fn back(&mut self, current: usize) -> Option<usize> {
+        let index = match self.index {
+            Some(i) => i,
+            // there's nowhere to go back to, so nothing is recorded
+            None if self.entries.iter().all(|&o| o == current) => return None,
+            None => {
+                self.record(current);
+                self.entries.len() - 1
+            }
+        };
+        let index = index.checked_sub(1)?;
+        self.index = Some(index);
+        self.entries.get(index).copied()
+    }
+
+    /// Entry after the current one
+    pub fn forward(&mut self) -> Option<usize> {
+        let index = self.index? + 1;
+        let offset = *self.entries.get(index)?;
+        self.index = Some(index);
+        Some(offset)
+    }
+
+    /// The entry back() goes to
+    pub fn previous(&self) -> Option<usize> {
+        let index = self.index.unwrap_or(self.entries.len()).checked_sub(1)?;
+        self.entries.get(index).copied()
+    }
+
+    /// Index of the entry reached by going back, if any
+    pub fn current(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = None;
+    }
+}
+
+impl App {
+    /// Go to `offset`, remembering where the cursor was in the jump list
+    pub fn jump(&mut self, offset: usize) {
+        if offset >= self.file_info.size {
+            return;
+        }
+        if offset != self.hex_view.offset {
+            self.hex_view.jumps.record(self.hex_view.offset);
+        }
+        self.goto(offset);
+    }
+
+    /// Go back in the jump list. Beeps and returns false if there's nowhere
+    /// to go.
+    pub fn jump_back(&mut self) -> bool {
+        let current = self.hex_view.offset;
+        match self.hex_view.jumps.back(current) {
+            Some(offset) => {
+                self.goto(offset);
+                true
+            }
+            None => {
+                crate::beep!();
+                false
+            }
+        }
+    }
+
+    /// Go forward in the jump list. Beeps and returns false if there's
+    /// nowhere to go.
+    pub fn jump_forward(&mut self) -> bool {
+        match self.hex_view.jumps.forward() {
+            Some(offset) => {
+                self.goto(offset);
+                true
+            }
+            None => {
+                crate::beep!();
+                false
+            }
+        }
+    }
+}
+
+pub fn dialog_jumps_draw(app: &mut App, frame: &mut Frame) {
+    let jumps = &app.hex_view.jumps;
+    let bin = app.file_info.binary.as_ref();
+    let mut items = Vec::with_capacity(jumps.entries.len());
+
+    // newest first, numbered by how many steps back they are
+    let current = jumps.current().unwrap_or(jumps.entries.len());
+    for (i, &offset) in jumps.entries.iter().enumerate().rev() {
+        let marker = if i == current { '>' } else { ' ' };
+        let steps = (current as isize - i as isize).unsigned_abs();
+        let section = bin.and_then(|b| b.section_at(offset)).unwrap_or("");
+        let name = app
+            .hex_view
+            .comments
+            .get(&offset)
+            .map(String::as_str)
+            .or_else(|| bin.and_then(|b| b.symbol_at(offset)))
+            .unwrap_or("");
+        items.push(ListItem::from(format!(
+            "{}{:>3}  {}  {:<10} {}",
+            marker,
+            steps,
+            app.format_addr(offset),
+            section,
+            name
+        )));
+    }
+
+    let list = List::new(items)
+        .style(app.config.theme.dialog)
+        .block(
+            Block::bordered()
+                .title(format!(" Jumps ({}) ", jumps.entries.len()))
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        )
+        .highlight_style(app.config.theme.highlight)
+        .repeat_highlight_symbol(true);
+
+    let width = (frame.area().width * 3 / 4).max(80.min(frame.area().width));
+    let height = frame.area().height / 2 + 4;
+    let dialog_area = center_widget(width, height, frame.area());
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_stateful_widget(list, dialog_area, &mut app.hex_view.jumps_list_state);
+}
+
+/// Open the jump list
+pub fn open_jumps(app: &mut App) {
+    app.state = UIState::DialogJumps;
+    app.dialog_renderer = Some(dialog_jumps_draw);
+    app.hex_view.jumps_list_state.select_first();
+}
+
+pub fn dialog_jumps_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => {
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.hex_view.jumps_list_state.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.hex_view.jumps_list_state.select_previous();
+            }
+            KeyCode::PageDown => {
+                app.hex_view.jumps_list_state.scroll_down_by(30);
+            }
+            KeyCode::PageUp => {
+                app.hex_view.jumps_list_state.scroll_up_by(30);
+            }
+            KeyCode::Home => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.jumps_list_state.select_first();
+                } else if let Some(n) = app.hex_view.jumps_list_state.selected() {
+                    let new_index = n.saturating_sub(29);
+                    app.hex_view.jumps_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::End => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.jumps_list_state.select_last();
+                } else if let Some(n) = app.hex_view.jumps_list_state.selected() {
+                    let new_index = n + 29;
+                    app.hex_view.jumps_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::Enter => {
+                // the list is shown newest first
+                let jumps = &app.hex_view.jumps.entries;
+                if let Some(&offset) = app
+                    .hex_view
+                    .jumps_list_state
+                    .selected()
+                    .and_then(|i| jumps.len().checked_sub(i + 1))
+                    .and_then(|i| jumps.get(i))
+                {
+                    app.jump(offset);
+                }
+                app.state = UIState::Normal;
+                app.dialog_renderer = None;
+            }
+            // forget every entry
+            KeyCode::Char('D') => {
+                app.hex_view.jumps.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_list_test() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(0x10), None);
+        assert!(jumps.entries.is_empty());
+
+        jumps.record(0x10);
+        assert_eq!(jumps.back(0x10), None);
+        assert_eq!(jumps.forward(), None);
+        jumps.record(0x20);
+        assert_eq!(jumps.previous(), Some(0x20));
+
+        // going back from 0x30 remembers it for forward()
+        assert_eq!(jumps.back(0x30), Some(0x20));
+        assert_eq!(jumps.back(0x20), Some(0x10));
+        assert_eq!(jumps.back(0x10), None);
+        assert_eq!(jumps.forward(), Some(0x20));
+        assert_eq!(jumps.forward(), Some(0x30));
+        assert_eq!(jumps.forward(), None);
+
+        // a new jump moves an existing entry to the end
+        jumps.record(0x10);
+        assert_eq!(jumps.entries, vec![0x20, 0x30, 0x10]);
+        assert_eq!(jumps.forward(), None);
+
+        for i in 0..JUMP_LIST_SIZE + 5 {
+            jumps.record(i);
+        }
+        assert_eq!(jumps.entries.len(), JUMP_LIST_SIZE);
+        assert_eq!(jumps.entries[0], 5);
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories_next::UserDirs;
+use ratatui::{
+    Frame,
+    crossterm::event::KeyModifiers,
+    layout::Alignment,
+    widgets::{Block, Clear, List, ListItem, Padding},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode};
+use serde::{Deserialize, Serialize};
+use std::io::Result;
+
+use crate::{
+    app::{App, Dz6Error},
+    commands::command_error_draw,
+    editor::UIState,
+    hex::changes,
+    util::center_widget,
+};
+
+/// An uppercase mark, which also remembers the file it was set in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalMark {
+    pub path: String,
+    pub offset: usize,
+}
+
+/// A row of the Marks list
+#[derive(Debug, Clone)]
+pub struct MarkEntry {
+    pub name: char,
+    pub offset: usize,
+    /// file of an uppercase mark set in another file
+    pub path: Option<String>,
+}
+
+// uppercase marks are kept in the user's home directory, so every file sees them
+fn global_marks_path() -> Option<PathBuf> {
+    UserDirs::new().map(|home| home.home_dir().join(".dz6marks"))
+}
+
+fn read_global_marks() -> BTreeMap<char, GlobalMark> {
+    global_marks_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| toml::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_global_marks(
+    marks: &BTreeMap<char, GlobalMark>,
+) -> std::result::Result<(), Box<dyn Error>> {
+    let path = global_marks_path().ok_or("Home directory not found")?;
+    fs::write(path, toml::to_string_pretty(marks)?)?;
+    Ok(())
+}
+
+// the same file can be opened through different paths
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn show_error(app: &mut App, message: String) {
+    app.last_error = Dz6Error { message };
+    app.dialog_renderer = Some(command_error_draw);
+}
+
+/// Set mark `name` at the cursor
+pub fn set_mark(app: &mut App, name: char) {
+    let offset = app.hex_view.offset;
+    if name.is_ascii_lowercase() {
+        app.hex_view.marks.insert(name, offset);
+    } else if name.is_ascii_uppercase() {
+        let path = fs::canonicalize(&app.file_info.path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| app.file_info.path.clone());
+        let mut marks = read_global_marks();
+        marks.insert(name, GlobalMark { path, offset });
+        if let Err(e) = write_global_marks(&marks) {
+            show_error(app, format!("Could not save mark {}: {}", name, e));
+            return;
+        }
+    } else {
+        crate::beep!();
+        return;
+    }
+    App::log(app, format!("mark {}: {:x}", name, offset));
+}
+
+/// Go to mark `name`, opening the file of an uppercase mark if needed
+pub fn goto_mark(app: &mut App, name: char) {
+    if name.is_ascii_lowercase() {
+        match app.hex_view.marks.get(&name) {
+            Some(&offset) if offset < app.file_info.size => app.jump(offset),
+            Some(_) => show_error(app, format!("Mark {} is out of the file", name)),
+            None => show_error(app, format!("Mark not set: {}", name)),
+        }
+    } else if name.is_ascii_uppercase() {
+        match read_global_marks().remove(&name) {
+            Some(mark) if same_file(&mark.path, &app.file_info.path) => {
+                if mark.offset < app.file_info.size {
+                    app.jump(mark.offset);
+                } else {
+                    show_error(app, format!("Mark {} is out of the file", name));
+                }
+            }
+            Some(mark) => {
+                if let Err(message) = app.switch_file(&mark.path, mark.offset) {
+                    show_error(app, message);
+                }
+            }
+            None => show_error(app, format!("Mark not set: {}", name)),
+        }
+    } else {
+        crate::beep!();
+    }
+}
+
+impl App {
+    /// Open another file in place of the current one, which must have no
+    /// pending changes
+    pub fn switch_file(&mut self, path: &str, offset: usize) -> std::result::Result<(), String> {
+        if changes::count(self) > 0 {
+            return Err("No write since last change".to_string());
+        }
+        if !Path::new(path).exists() {
+            return Err(format!("{}: file not found", path));
+        }
+        if self.config.database {
+            let _ = self.save_database();
+        }
+
+        // everything tied to the offsets of the previous file goes away
+        self.hex_view.bookmarks.clear();
+        self.hex_view.comments.clear();
+        self.hex_view.comment_name_list.clear();
+        self.hex_view.marks.clear();
+        self.hex_view.jumps.clear();
+        self.hex_view.history.clear();
+        self.hex_view.find_all.clear();
+        self.hex_view.selection = Default::default();
+        self.strings.clear();
+
+        let read_only = self.file_info.is_read_only;
+        self.load_file(path, 0, read_only)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        self.goto(offset.min(self.file_info.size.saturating_sub(1)));
+        self.disasm_view.top = self.hex_view.offset;
+        Ok(())
+    }
+}
+
+/// Lowercase marks of this file, then the uppercase ones
+pub fn mark_entries(app: &App) -> Vec<MarkEntry> {
+    let mut entries: Vec<MarkEntry> = app
+        .hex_view
+        .marks
+        .iter()
+        .map(|(&name, &offset)| MarkEntry {
+            name,
+            offset,
+            path: None,
+        })
+        .collect();
+
+    for (name, mark) in read_global_marks() {
+        let path = (!same_file(&mark.path, &app.file_info.path)).then_some(mark.path);
+        entries.push(MarkEntry {
+            name,
+            offset: mark.offset,
+            path,
+        });
+    }
+    entries
+}
+
+pub fn dialog_marks_draw(app: &mut App, frame: &mut Frame) {
+    let bin = app.file_info.binary.as_ref();
+    let mut items = Vec::with_capacity(app.hex_view.mark_list.len());
+
+    for mark in &app.hex_view.mark_list {
+        let text = match &mark.path {
+            Some(path) => format!("{}  {:08X}  {}", mark.name, mark.offset, path),
+            None => {
+                let section = bin.and_then(|b| b.section_at(mark.offset)).unwrap_or("");
+                let name = app
+                    .hex_view
+                    .comments
+                    .get(&mark.offset)
+                    .map(String::as_str)
+                    .or_else(|| bin.and_then(|b| b.symbol_at(mark.offset)))
+                    .unwrap_or("");
+                format!(
+                    "{}  {}  {:<10} {}",
+                    mark.name,
+                    app.format_addr(mark.offset),
+                    section,
+                    name
+                )
+            }
+        };
+        items.push(ListItem::from(text));
+    }
+
+    let list = List::new(items)
+        .style(app.config.theme.dialog)
+        .block(
+            Block::bordered()
+                .title(format!(" Marks ({}) ", app.hex_view.mark_list.len()))
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        )
+        .highlight_style(app.config.theme.highlight)
+        .repeat_highlight_symbol(true);
+
+    let width = (frame.area().width * 3 / 4).max(80.min(frame.area().width));
+    let height = frame.area().height / 2 + 4;
+    let dialog_area = center_widget(width, height, frame.area());
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_stateful_widget(list, dialog_area, &mut app.hex_view.marks_list_state);
+}
+
+/// Open the marks list
+pub fn open_marks(app: &mut App) {
+    app.hex_view.mark_list = mark_entries(app);
+    app.state = UIState::DialogMarks;
+    app.dialog_renderer = Some(dialog_marks_draw);
+    if app.hex_view.marks_list_state.selected().is_none() {
+        app.hex_view.marks_list_state.select_first();
+    }
+}
+
+pub fn dialog_marks_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        match key.code {
+            KeyCode::Esc => {
+                app.hex_view.mark_list.clear();
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.hex_view.marks_list_state.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.hex_view.marks_list_state.select_previous();
+            }
+            KeyCode::PageDown => {
+                app.hex_view.marks_list_state.scroll_down_by(30);
+            }
+            KeyCode::PageUp => {
+                app.hex_view.marks_list_state.scroll_up_by(30);
+            }
+            KeyCode::Home => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.marks_list_state.select_first();
+                } else if let Some(n) = app.hex_view.marks_list_state.selected() {
+                    let new_index = n.saturating_sub(29);
+                    app.hex_view.marks_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::End => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    app.hex_view.marks_list_state.select_last();
+                } else if let Some(n) = app.hex_view.marks_list_state.selected() {
+                    let new_index = n + 29;
+                    app.hex_view.marks_list_state.select(Some(new_index));
+                }
+            }
+            KeyCode::Enter => {
+                let name = app
+                    .hex_view
+                    .marks_list_state
+                    .selected()
+                    .and_then(|i| app.hex_view.mark_list.get(i))
+                    .map(|m| m.name);
+                app.hex_view.mark_list.clear();
+                app.state = UIState::Normal;
+                app.dialog_renderer = None;
+                if let Some(name) = name {
+                    goto_mark(app, name);
+                }
+            }
+            // delete the selected mark
+            KeyCode::Char('d') => {
+                if let Some(name) = app
+                    .hex_view
+                    .marks_list_state
+                    .selected()
+                    .and_then(|i| app.hex_view.mark_list.get(i))
+                    .map(|m| m.name)
+                {
+                    delete_mark(app, name);
+                    app.hex_view.mark_list = mark_entries(app);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+fn delete_mark(app: &mut App, name: char) {
+    if name.is_ascii_lowercase() {
+        app.hex_view.marks.remove(&name);
+    } else {
+        let mut marks = read_global_marks();
+        if marks.remove(&name).is_some()
+            && let Err(e) = write_global_marks(&marks)
+        {
+            App::log(app, format!("Could not delete mark {}: {}", name, e));
+        }
+    }
+}
@@ -1,10 +1,21 @@
+pub mod address;
+pub mod changes;
+pub mod clipboard;
 pub mod comment;
 pub mod draw;
 pub mod edit;
 pub mod events;
+pub mod find_all;
 mod help;
 pub mod hex_view;
+pub mod history;
+pub mod inspector;
+pub mod jumps;
+pub mod marks;
 pub mod names;
+pub mod number;
+pub mod pattern;
 pub mod search;
 pub mod selection;
 pub mod strings;
+pub mod structure;
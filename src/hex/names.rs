@@ -91,7 +91,7 @@ pub fn dialog_names_events(app: &mut App, event: &Event) -> Result<bool> {
                         return Ok(true);
                     }
                     // Vec<Comment>
-                    app.goto(app.hex_view.comment_name_list[choice].offset);
+                    app.jump(app.hex_view.comment_name_list[choice].offset);
                 }
                 app.state = UIState::Normal;
                 app.dialog_renderer = None;
@@ -0,0 +1,202 @@
+use crate::{config::Endian, util::parse_offset};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Range {
+    Int(u64, u64),
+    Float(f64, f64),
+}
+
+/// A number to search for, like `d 1337t`, or a range of them, like
+/// `f32 0.99..1.01`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberPattern {
+    kind: Kind,
+    endian: Endian,
+    range: Range,
+}
+
+impl Kind {
+    fn size(self) -> usize {
+        match self {
+            Kind::U16 => 2,
+            Kind::U32 | Kind::F32 => 4,
+            Kind::U64 | Kind::F64 => 8,
+        }
+    }
+}
+
+// a value of `kind`; integers are read like offsets, so hex unless they end
+// with `t`
+fn parse_value(kind: Kind, s: &str) -> Result<Range, String> {
+    let invalid = || format!("Invalid number: {}", s);
+    match kind {
+        Kind::F32 | Kind::F64 => {
+            let value: f64 = s.parse().map_err(|_| invalid())?;
+            Ok(Range::Float(value, value))
+        }
+        _ => {
+            let value = parse_offset(s).map_err(|_| invalid())? as u64;
+            let max = u64::MAX >> (64 - 8 * kind.size());
+            if value > max {
+                return Err(format!("{} doesn't fit in {} bytes", s, kind.size()));
+            }
+            Ok(Range::Int(value, value))
+        }
+    }
+}
+
+impl NumberPattern {
+    /// Parse a type, optionally followed by `le` or `be`, and a value or an
+    /// inclusive range like `10..20`. Types are `u16`, `u32`, `u64`, `f32`
+    /// and `f64`, or `w`, `d` and `q` for the integers. Numbers without
+    /// `le` or `be` use `endian`.
+    pub fn parse(s: &str, endian: Endian) -> Result<NumberPattern, String> {
+        let s = s.trim();
+        let (kind, value) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let value: String = value.split_whitespace().collect();
+
+        let (kind, endian) = match kind {
+            _ if kind.ends_with("le") => (&kind[..kind.len() - 2], Endian::Little),
+            _ if kind.ends_with("be") => (&kind[..kind.len() - 2], Endian::Big),
+            _ => (kind, endian),
+        };
+        let kind = match kind {
+            "w" | "u16" => Kind::U16,
+            "d" | "u32" => Kind::U32,
+            "q" | "u64" => Kind::U64,
+            "f32" => Kind::F32,
+            "f64" => Kind::F64,
+            "" => return Err("Type a number type, like d or f32".to_string()),
+            _ => return Err(format!("Invalid number type: {}", kind)),
+        };
+        if value.is_empty() {
+            return Err("Type a value".to_string());
+        }
+
+        let range = match value.split_once("..") {
+            Some((min, max)) => match (parse_value(kind, min)?, parse_value(kind, max)?) {
+                (Range::Int(min, _), Range::Int(max, _)) if min <= max => Range::Int(min, max),
+                (Range::Float(min, _), Range::Float(max, _)) if min <= max => {
+                    Range::Float(min, max)
+                }
+                _ => return Err(format!("Invalid range: {}", value)),
+            },
+            None => parse_value(kind, &value)?,
+        };
+
+        Ok(NumberPattern {
+            kind,
+            endian,
+            range,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.kind.size()
+    }
+
+    /// The bytes to look for if there's a single value
+    pub fn exact(&self) -> Option<Vec<u8>> {
+        let mut bytes = match (self.kind, self.range) {
+            (_, Range::Int(min, max)) if min == max => min.to_le_bytes()[..self.size()].to_vec(),
+            (Kind::F32, Range::Float(min, max)) if min == max => {
+                (min as f32).to_le_bytes().to_vec()
+            }
+            (_, Range::Float(min, max)) if min == max => min.to_le_bytes().to_vec(),
+            _ => return None,
+        };
+        if self.endian == Endian::Big {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+
+    /// Whether the number at the start of `hay` is in the range
+    pub fn match_at(&self, hay: &[u8]) -> bool {
+        let Some(bytes) = hay.get(..self.size()) else {
+            return false;
+        };
+        let mut le = [0u8; 8];
+        le[..bytes.len()].copy_from_slice(bytes);
+        if self.endian == Endian::Big {
+            le[..bytes.len()].reverse();
+        }
+
+        match (self.kind, self.range) {
+            (Kind::F32, Range::Float(min, max)) => {
+                let value = f32::from_le_bytes([le[0], le[1], le[2], le[3]]) as f64;
+                min <= value && value <= max
+            }
+            (_, Range::Float(min, max)) => {
+                let value = f64::from_le_bytes(le);
+                min <= value && value <= max
+            }
+            (_, Range::Int(min, max)) => (min..=max).contains(&u64::from_le_bytes(le)),
+        }
+    }
+
+    /// First match in `hay`, as its offset and length
+    pub fn find(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        (0..hay.len())
+            .find(|&pos| self.match_at(&hay[pos..]))
+            .map(|pos| (pos, self.size()))
+    }
+
+    /// Last match in `hay`, as its offset and length
+    pub fn rfind(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        (0..hay.len())
+            .rev()
+            .find(|&pos| self.match_at(&hay[pos..]))
+            .map(|pos| (pos, self.size()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let exact = |s| NumberPattern::parse(s, Endian::Little).unwrap().exact();
+        assert_eq!(exact("d 1337t"), Some(vec![0x39, 0x05, 0, 0]));
+        assert_eq!(exact("u16be 1337t"), Some(vec![0x05, 0x39]));
+        assert_eq!(exact("q ff"), Some(vec![0xff, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(exact("f32 1.5"), Some(vec![0, 0, 0xc0, 0x3f]));
+        assert_eq!(exact("d 10..20"), None);
+
+        let p = NumberPattern::parse("w 1337t", Endian::Big).unwrap();
+        assert_eq!(p.exact(), Some(vec![0x05, 0x39]));
+
+        assert!(NumberPattern::parse("w 10000", Endian::Little).is_err());
+        assert!(NumberPattern::parse("x 10", Endian::Little).is_err());
+        assert!(NumberPattern::parse("d", Endian::Little).is_err());
+        assert!(NumberPattern::parse("d 20..10", Endian::Little).is_err());
+        assert!(NumberPattern::parse("f64 1.5.", Endian::Little).is_err());
+    }
+
+    #[test]
+    fn find_test() {
+        let hay = [0x00, 0x10, 0x00, 0x30, 0x00, 0x00, 0x00, 0xc0, 0x3f];
+
+        let p = NumberPattern::parse("w 20..40", Endian::Little).unwrap();
+        assert_eq!(p.find(&hay), Some((3, 2)));
+        let p = NumberPattern::parse("w 10..30", Endian::Little).unwrap();
+        assert_eq!(p.find(&hay), Some((1, 2)));
+        assert_eq!(p.rfind(&hay), Some((3, 2)));
+        let p = NumberPattern::parse("wbe 10..30", Endian::Little).unwrap();
+        assert_eq!(p.find(&hay), Some((0, 2)));
+
+        let p = NumberPattern::parse("f32 1.4..1.6", Endian::Little).unwrap();
+        assert_eq!(p.find(&hay), Some((5, 4)));
+        assert_eq!(p.find(&hay[..8]), None);
+    }
+}
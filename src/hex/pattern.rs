@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+
+use memchr::memmem;
+
+// longest gap allowed between two bytes, to keep backtracking cheap
+const MAX_GAP: usize = 1024;
+
+// most failed (token, offset) pairs remembered while matching
+const MAX_FAILED: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    /// a byte whose bits set in `mask` must be equal to `value`'s
+    Byte { value: u8, mask: u8 },
+    /// `min` to `max` bytes of any value
+    Gap { min: usize, max: usize },
+}
+
+/// A hex search pattern with wildcards: `??` matches any byte, `4?` and `?4`
+/// any byte with the given high or low nibble, and `[2-8]` (or `[4]`) a gap
+/// of 2 to 8 (or 4) bytes of any value
+#[derive(Debug, Clone, PartialEq)]
+pub struct HexPattern {
+    tokens: Vec<Token>,
+    // longest run of exact bytes before any gap, and where it is in a match
+    literal: Vec<u8>,
+    literal_at: usize,
+}
+
+fn nibble(c: char) -> Result<Option<u8>, String> {
+    match c {
+        '?' => Ok(None),
+        _ => c
+            .to_digit(16)
+            .map(|d| Some(d as u8))
+            .ok_or_else(|| format!("Invalid hex digit: {}", c)),
+    }
+}
+
+fn parse_gap(s: &str) -> Result<Token, String> {
+    let invalid = || format!("Invalid gap: [{}]", s);
+    let (min, max) = match s.split_once('-') {
+        Some((min, max)) => (min.trim(), max.trim()),
+        None => (s.trim(), s.trim()),
+    };
+    let min: usize = min.parse().map_err(|_| invalid())?;
+    let max: usize = max.parse().map_err(|_| invalid())?;
+    if min > max || max == 0 {
+        return Err(invalid());
+    }
+    if max > MAX_GAP {
+        return Err(format!("Gaps are limited to {} bytes", MAX_GAP));
+    }
+    Ok(Token::Gap { min, max })
+}
+
+impl HexPattern {
+    /// Parse a pattern like `55 8b ec ?? [2-8] e8`; spaces are optional
+    pub fn parse(s: &str) -> Result<HexPattern, String> {
+        let mut tokens = Vec::new();
+        let mut chars = s.chars().filter(|c| !c.is_whitespace());
+
+        while let Some(c) = chars.next() {
+            if c == '[' {
+                let gap: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                tokens.push(parse_gap(&gap)?);
+                continue;
+            }
+            let Some(low) = chars.next() else {
+                return Err("Hex bytes need two digits".to_string());
+            };
+            let (high, low) = (nibble(c)?, nibble(low)?);
+            tokens.push(Token::Byte {
+                value: (high.unwrap_or(0) << 4) | low.unwrap_or(0),
+                mask: (high.map_or(0, |_| 0xf0)) | low.map_or(0, |_| 0x0f),
+            });
+        }
+
+        match (tokens.first(), tokens.last()) {
+            (None, _) => return Err("Empty pattern".to_string()),
+            (Some(Token::Gap { .. }), _) | (_, Some(Token::Gap { .. })) => {
+                return Err("Patterns can't start or end with a gap".to_string());
+            }
+            _ => {}
+        }
+
+        // exact bytes are found with memchr, then the rest of the pattern is
+        // checked; they must be before any gap to know where a match starts
+        let fixed = tokens
+            .iter()
+            .position(|t| matches!(t, Token::Gap { .. }))
+            .unwrap_or(tokens.len());
+        let mut literal = Vec::new();
+        let mut literal_at = 0;
+        let mut run = Vec::new();
+        for (i, token) in tokens[..fixed].iter().enumerate() {
+            match *token {
+                Token::Byte { value, mask: 0xff } => run.push(value),
+                _ => run.clear(),
+            }
+            if run.len() > literal.len() {
+                literal = run.clone();
+                literal_at = i + 1 - run.len();
+            }
+        }
+
+        Ok(HexPattern {
+            tokens,
+            literal,
+            literal_at,
+        })
+    }
+
+    /// The bytes to look for if there are no wildcards at all
+    pub fn exact(&self) -> Option<Vec<u8>> {
+        (self.literal.len() == self.tokens.len()).then(|| self.literal.clone())
+    }
+
+    // end of the match of the tokens from `i` on, starting at `pos` in
+    // `hay`, trying the shortest gaps first. Gaps reach the same token and
+    // position in many ways, so the ones that failed are remembered, even
+    // across the candidates of a search, to keep this from backtracking for
+    // ages.
+    fn match_tokens(
+        &self,
+        i: usize,
+        pos: usize,
+        hay: &[u8],
+        failed: &mut HashSet<(usize, usize)>,
+    ) -> Option<usize> {
+        let Some(&token) = self.tokens.get(i) else {
+            return Some(pos);
+        };
+        if failed.contains(&(i, pos)) {
+            return None;
+        }
+        let end = match token {
+            Token::Byte { value, mask } => hay
+                .get(pos)
+                .filter(|&&b| b & mask == value & mask)
+                .and_then(|_| self.match_tokens(i + 1, pos + 1, hay, failed)),
+            Token::Gap { min, max } => (min..=max.min(hay.len().saturating_sub(pos)))
+                .find_map(|gap| self.match_tokens(i + 1, pos + gap, hay, failed)),
+        };
+        if end.is_none() {
+            failed.insert((i, pos));
+        }
+        end
+    }
+
+    // length of the match starting at `pos` in `hay`, if any
+    fn match_from(
+        &self,
+        hay: &[u8],
+        pos: usize,
+        failed: &mut HashSet<(usize, usize)>,
+    ) -> Option<usize> {
+        // it's only a cache, so it's dropped before it gets too big
+        if failed.len() > MAX_FAILED {
+            failed.clear();
+        }
+        self.match_tokens(0, pos, hay, failed).map(|end| end - pos)
+    }
+
+    /// First match in `hay`, as its offset and length
+    pub fn find(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        let mut failed = HashSet::new();
+        if self.literal.is_empty() {
+            return (0..hay.len())
+                .find_map(|pos| self.match_from(hay, pos, &mut failed).map(|len| (pos, len)));
+        }
+        // candidates may overlap, so the search goes on from the next byte
+        let finder = memmem::Finder::new(&self.literal);
+        let mut from = 0;
+        while let Some(found) = hay.get(from..).and_then(|h| finder.find(h)) {
+            let literal_pos = from + found;
+            if let Some(pos) = literal_pos.checked_sub(self.literal_at)
+                && let Some(len) = self.match_from(hay, pos, &mut failed)
+            {
+                return Some((pos, len));
+            }
+            from = literal_pos + 1;
+        }
+        None
+    }
+
+    /// Last match starting in `hay` and fitting in it, as its offset and length
+    pub fn rfind(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        let mut failed = HashSet::new();
+        if self.literal.is_empty() {
+            return (0..hay.len())
+                .rev()
+                .find_map(|pos| self.match_from(hay, pos, &mut failed).map(|len| (pos, len)));
+        }
+        let finder = memmem::FinderRev::new(&self.literal);
+        let mut end = hay.len();
+        while let Some(literal_pos) = finder.rfind(&hay[..end]) {
+            if let Some(pos) = literal_pos.checked_sub(self.literal_at)
+                && let Some(len) = self.match_from(hay, pos, &mut failed)
+            {
+                return Some((pos, len));
+            }
+            end = literal_pos + self.literal.len() - 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let p = HexPattern::parse("4c 8b").unwrap();
+        assert_eq!(p.exact(), Some(vec![0x4c, 0x8b]));
+        assert_eq!(HexPattern::parse("4c8b??").unwrap().exact(), None);
+
+        assert!(HexPattern::parse("4").is_err());
+        assert!(HexPattern::parse("4g").is_err());
+        assert!(HexPattern::parse("[2] 4c").is_err());
+        assert!(HexPattern::parse("4c [8-2] 4c").is_err());
+        assert!(HexPattern::parse("4c [x] 4c").is_err());
+        assert!(HexPattern::parse("").is_err());
+    }
+
+    #[test]
+    fn find_test() {
+        let hay = [
+            0x00, 0x55, 0x8b, 0xec, 0x90, 0x90, 0xe8, 0x55, 0x8b, 0xec, 0xe8,
+        ];
+
+        let p = HexPattern::parse("55 8b ec [0-4] e8").unwrap();
+        assert_eq!(p.find(&hay), Some((1, 6)));
+        assert_eq!(p.rfind(&hay), Some((7, 4)));
+        assert_eq!(p.find(&hay[2..]), Some((5, 4)));
+
+        // no literal at all
+        let p = HexPattern::parse("5? ?b").unwrap();
+        assert_eq!(p.find(&hay), Some((1, 2)));
+        assert_eq!(p.rfind(&hay), Some((7, 2)));
+
+        // the literal comes after a wildcard
+        let p = HexPattern::parse("?? ec 90").unwrap();
+        assert_eq!(p.find(&hay), Some((2, 3)));
+        assert_eq!(p.find(&hay[3..]), None);
+
+        // overlapping candidates
+        let p = HexPattern::parse("90 90 e8").unwrap();
+        assert_eq!(p.find(&[0x90, 0x90, 0x90, 0xe8]), Some((1, 3)));
+        let p = HexPattern::parse("90 90 ?? e8").unwrap();
+        assert_eq!(p.rfind(&[0x90, 0x90, 0x90, 0xe8, 0x90, 0x90]), Some((0, 4)));
+
+        let p = HexPattern::parse("ec [3] 55").unwrap();
+        assert_eq!(p.find(&hay), Some((3, 5)));
+        assert_eq!(HexPattern::parse("ec [4] 55").unwrap().find(&hay), None);
+
+        // many gaps over bytes they all match don't backtrack for ages
+        let p = HexPattern::parse("41 [0-200] 41 [0-200] 41 [0-200] 42").unwrap();
+        assert_eq!(p.find(&[0x41; 400]), None);
+        assert_eq!(p.rfind(&[0x41; 400]), None);
+    }
+}
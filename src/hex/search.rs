@@ -1,8 +1,13 @@
+use crate::config::Endian;
+use crate::hex::number::NumberPattern;
+use crate::hex::pattern::HexPattern;
 use crate::widgets::{Message, MessageType};
 use crate::{app::App, editor::UIState};
 use ratatui::Frame;
 use ratatui::crossterm::event::{Event, KeyCode};
+use ratatui::layout::Alignment;
 use ratatui::widgets::Paragraph;
+use regex::bytes::{Regex, RegexBuilder};
 use std::io::Result;
 use tui_input::Input;
 use tui_input::backend::crossterm::EventHandler;
@@ -13,27 +18,65 @@ pub struct Search {
     pub mode: SearchMode,
     pub direction: SearchDirection,
     pub input_hex: Input,
+    pub input_regex: Input,
+    pub input_number: Input,
 }
 
 #[derive(Default, Debug, PartialEq)]
 pub enum SearchMode {
     #[default]
     Utf8,
-    // UTF_16,
-    // UTF_16_LE,
+    Utf16Le,
+    Utf16Be,
     Hex,
+    Regex,
+    Number,
 }
 
 impl SearchMode {
     pub fn next(&mut self) {
-        if *self == SearchMode::Utf8 {
-            *self = SearchMode::Hex;
-        } else {
-            *self = SearchMode::Utf8
+        *self = match self {
+            SearchMode::Utf8 => SearchMode::Utf16Le,
+            SearchMode::Utf16Le => SearchMode::Utf16Be,
+            SearchMode::Utf16Be => SearchMode::Hex,
+            SearchMode::Hex => SearchMode::Regex,
+            SearchMode::Regex => SearchMode::Number,
+            SearchMode::Number => SearchMode::Utf8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchMode::Utf8 => "UTF-8",
+            SearchMode::Utf16Le => "UTF-16LE",
+            SearchMode::Utf16Be => "UTF-16BE",
+            SearchMode::Hex => "HEX",
+            SearchMode::Regex => "REGEX",
+            SearchMode::Number => "NUMBER",
+        }
+    }
+
+    /// Whether the text typed is encoded, as opposed to being a pattern
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            SearchMode::Utf8 | SearchMode::Utf16Le | SearchMode::Utf16Be
+        )
+    }
+
+    /// Bytes of `text` in the encoding of a text mode
+    fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            SearchMode::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            SearchMode::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            _ => text.as_bytes().to_vec(),
         }
     }
 }
 
+// text ending with this is searched ignoring case, like in Vim
+const IGNORE_CASE: &str = "\\c";
+
 #[derive(Default, PartialEq, Debug)]
 pub enum SearchDirection {
     #[default]
@@ -41,34 +84,213 @@ pub enum SearchDirection {
     Backward,
 }
 
-pub fn hex_string_to_u8(hex_string: &str) -> Option<Vec<u8>> {
-    if hex_string.is_empty() || !hex_string.len().is_multiple_of(2) {
-        return None;
+/// What a search looks for
+#[derive(Debug, Clone)]
+pub enum Needle {
+    Bytes(Vec<u8>),
+    Pattern(HexPattern),
+    Regex(Regex),
+    Number(NumberPattern),
+}
+
+// size of the first block searched backwards by a regex; it doubles until
+// a match is found
+const REGEX_RFIND_BLOCK: usize = 64 * 1024;
+
+// last match starting in `hay`. Regexes only search forward, so the end of
+// `hay` is searched first, in blocks growing towards its start.
+fn regex_rfind(re: &Regex, hay: &[u8]) -> Option<(usize, usize)> {
+    let mut block = REGEX_RFIND_BLOCK;
+    loop {
+        let start = hay.len().saturating_sub(block);
+        let mut last = None;
+        let mut pos = start;
+        // find_at() sees the bytes before `pos`, so anchors and \b still work
+        while let Some(m) = re.find_at(hay, pos) {
+            last = Some((m.start(), m.len()));
+            pos = m.start() + 1;
+            if pos > hay.len() {
+                break;
+            }
+        }
+        if last.is_some() || start == 0 {
+            return last;
+        }
+        block = block.saturating_mul(2);
+    }
+}
+
+impl Needle {
+    /// First match in `hay`, as its offset and length
+    pub fn find(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        match self {
+            Needle::Bytes(bytes) => memchr::memmem::find(hay, bytes).map(|pos| (pos, bytes.len())),
+            Needle::Pattern(pattern) => pattern.find(hay),
+            Needle::Regex(re) => re.find(hay).map(|m| (m.start(), m.len())),
+            Needle::Number(number) => number.find(hay),
+        }
+    }
+
+    /// First match in `hay` starting at `start` or after, as its offset in
+    /// `hay` and length. Regexes see the bytes before `start`, so anchors
+    /// and \b work as they would on the whole of `hay`.
+    pub fn find_at(&self, hay: &[u8], start: usize) -> Option<(usize, usize)> {
+        match self {
+            Needle::Regex(re) => re.find_at(hay, start).map(|m| (m.start(), m.len())),
+            _ => self
+                .find(hay.get(start..)?)
+                .map(|(pos, len)| (start + pos, len)),
+        }
+    }
+
+    /// Last match in `hay`, as its offset and length
+    pub fn rfind(&self, hay: &[u8]) -> Option<(usize, usize)> {
+        match self {
+            Needle::Bytes(bytes) => memchr::memmem::rfind(hay, bytes).map(|pos| (pos, bytes.len())),
+            Needle::Pattern(pattern) => pattern.rfind(hay),
+            Needle::Regex(re) => regex_rfind(re, hay),
+            Needle::Number(number) => number.rfind(hay),
+        }
     }
-    let bytes = hex::decode(hex_string).unwrap();
-    Some(bytes)
 }
 
-pub fn search<T: AsRef<[u8]>>(app: &mut App, needle: T) -> Option<usize> {
-    let text = needle.as_ref();
+impl Search {
+    /// The input of the current mode; text modes share theirs
+    pub fn input(&self) -> &Input {
+        match self.mode {
+            SearchMode::Utf8 | SearchMode::Utf16Le | SearchMode::Utf16Be => &self.input_text,
+            SearchMode::Hex => &self.input_hex,
+            SearchMode::Regex => &self.input_regex,
+            SearchMode::Number => &self.input_number,
+        }
+    }
+
+    pub fn input_mut(&mut self) -> &mut Input {
+        match self.mode {
+            SearchMode::Utf8 | SearchMode::Utf16Le | SearchMode::Utf16Be => &mut self.input_text,
+            SearchMode::Hex => &mut self.input_hex,
+            SearchMode::Regex => &mut self.input_regex,
+            SearchMode::Number => &mut self.input_number,
+        }
+    }
+
+    /// Whether a text search ignores case
+    pub fn ignore_case(&self) -> bool {
+        self.mode.is_text() && self.input_text.value().ends_with(IGNORE_CASE)
+    }
+
+    /// The needle for the text typed in the current mode, or None if there's
+    /// nothing typed. Numbers are in `endian` unless the text says otherwise.
+    pub fn needle(&self, endian: Endian) -> Option<std::result::Result<Needle, String>> {
+        match self.mode {
+            SearchMode::Utf8 | SearchMode::Utf16Le | SearchMode::Utf16Be => {
+                let text = self.input_text.value();
+                let (text, ignore_case) = match text.strip_suffix(IGNORE_CASE) {
+                    Some(text) => (text, true),
+                    None => (text, false),
+                };
+                (!text.is_empty()).then(|| text_needle(text, &self.mode, ignore_case))
+            }
+            SearchMode::Hex => {
+                let text = self.input_hex.value();
+                if text.trim().is_empty() {
+                    return None;
+                }
+                // patterns without wildcards are plain bytes
+                Some(HexPattern::parse(text).map(|p| match p.exact() {
+                    Some(bytes) => Needle::Bytes(bytes),
+                    None => Needle::Pattern(p),
+                }))
+            }
+            SearchMode::Regex => {
+                let text = self.input_regex.value();
+                if text.is_empty() {
+                    return None;
+                }
+                // `.` and escapes like \x8b match any byte, not UTF-8 characters
+                let re = RegexBuilder::new(text)
+                    .unicode(false)
+                    .dot_matches_new_line(true)
+                    .build()
+                    // the last line tells what's wrong; the others quote the regex
+                    .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_string());
+                Some(re.map(Needle::Regex))
+            }
+            SearchMode::Number => {
+                // the prompt shows a `#`, which may be typed too
+                let text = self.input_number.value();
+                let text = text.strip_prefix('#').unwrap_or(text);
+                if text.trim().is_empty() {
+                    return None;
+                }
+                // single values are plain bytes
+                Some(NumberPattern::parse(text, endian).map(|p| match p.exact() {
+                    Some(bytes) => Needle::Bytes(bytes),
+                    None => Needle::Number(p),
+                }))
+            }
+        }
+    }
+}
+
+// needle for `text` in `mode`, where ASCII letters match either case if
+// `ignore_case` is set
+fn text_needle(
+    text: &str,
+    mode: &SearchMode,
+    ignore_case: bool,
+) -> std::result::Result<Needle, String> {
+    if !ignore_case || !text.chars().any(|c| c.is_ascii_alphabetic()) {
+        return Ok(Needle::Bytes(mode.encode(text)));
+    }
+
+    // only the byte holding the letter is folded, not those of other
+    // characters that happen to look like one
+    let mut re = String::new();
+    for c in text.chars() {
+        for b in mode.encode(c.encode_utf8(&mut [0; 4])) {
+            if c.is_ascii_alphabetic() && b == c as u8 {
+                re.push_str(&format!(
+                    "[{}{}]",
+                    c.to_ascii_lowercase(),
+                    c.to_ascii_uppercase()
+                ));
+            } else {
+                re.push_str(&format!("\\x{:02x}", b));
+            }
+        }
+    }
+    RegexBuilder::new(&re)
+        .unicode(false)
+        .build()
+        .map(Needle::Regex)
+        .map_err(|e| e.to_string())
+}
+
+pub fn search(app: &mut App, needle: &Needle) -> Option<usize> {
+    search_from(app, needle, app.hex_view.offset)
+}
+
+/// Search as if the cursor was at `from`
+pub fn search_from(app: &mut App, needle: &Needle, from: usize) -> Option<usize> {
     let filesize = app.file_info.size;
-    let buffer = app.file_info.get_buffer();
+    let buffer = app.file_info.contents();
 
-    if filesize == 0 || text.is_empty() {
+    if filesize == 0 {
         return None;
     }
 
     let ofs = if app.hex_view.search.direction == SearchDirection::Forward {
-        let start = app.hex_view.offset.checked_add(1)?;
+        let start = from.checked_add(1)?;
         if start < filesize {
-            memchr::memmem::find(buffer.get(start..)?, text).map(|pos| start + pos)
+            needle.find_at(&buffer, start).map(|(pos, _)| pos)
         } else {
             None
         }
     } else {
-        let end = app.hex_view.offset;
+        let end = from;
         if end > 0 {
-            memchr::memmem::rfind(buffer.get(..end)?, text)
+            needle.rfind(buffer.get(..end)?).map(|(pos, _)| pos)
         } else {
             None
         }
@@ -81,9 +303,9 @@ pub fn search<T: AsRef<[u8]>>(app: &mut App, needle: T) -> Option<usize> {
     // ofs is None, check wrap setting
     if app.config.search_wrap {
         let ofs = if app.hex_view.search.direction == SearchDirection::Forward {
-            memchr::memmem::find(buffer, text)
+            needle.find(&buffer).map(|(pos, _)| pos)
         } else {
-            memchr::memmem::rfind(buffer, text)
+            needle.rfind(&buffer).map(|(pos, _)| pos)
         };
 
         if ofs.is_some() {
@@ -98,36 +320,32 @@ pub fn search<T: AsRef<[u8]>>(app: &mut App, needle: T) -> Option<usize> {
 // string
 // hex
 pub fn dialog_search_draw(app: &mut App, frame: &mut Frame) {
-    let x;
-    let para;
-
     let prompt_char = if app.hex_view.search.direction == SearchDirection::Forward {
         '/'
     } else {
         '?'
     };
 
-    match app.hex_view.search.mode {
-        SearchMode::Utf8 => {
-            para = Paragraph::new(format!(
-                "{}{}",
-                prompt_char,
-                app.hex_view.search.input_text.value()
-            ));
-            x = app.hex_view.search.input_text.visual_cursor();
-        }
-        SearchMode::Hex => {
-            para = Paragraph::new(format!(
-                "{}{}",
-                prompt_char,
-                app.hex_view.search.input_hex.value()
-            ));
-            x = app.hex_view.search.input_hex.visual_cursor();
-        }
+    // numbers are typed after a `#`, as in `/#d 1337t`
+    let prompt = if app.hex_view.search.mode == SearchMode::Number {
+        format!("{}#", prompt_char)
+    } else {
+        prompt_char.to_string()
     };
+    let input = app.hex_view.search.input();
+    let para = Paragraph::new(format!("{}{}", prompt, input.value()));
+    let x = prompt.len() + input.visual_cursor();
 
     frame.render_widget(para, app.command_area);
-    frame.set_cursor_position((app.command_area.x + 1 + x as u16, app.command_area.y));
+
+    // patterns and regexes are checked as they are typed
+    if let Some(Err(e)) = app.hex_view.search.needle(app.config.endian) {
+        let error = Paragraph::new(e)
+            .style(app.config.theme.error)
+            .alignment(Alignment::Right);
+        frame.render_widget(error, app.command_area);
+    }
+    frame.set_cursor_position((app.command_area.x + x as u16, app.command_area.y));
 }
 
 pub fn dialog_search_events(app: &mut App, event: &Event) -> Result<bool> {
@@ -138,78 +356,49 @@ pub fn dialog_search_events(app: &mut App, event: &Event) -> Result<bool> {
                 app.state = UIState::Normal;
             }
             // if input is empty, backspace works like Esc; otherwise it's handled by tui-input
-            KeyCode::Backspace => match app.hex_view.search.mode {
-                SearchMode::Utf8 => {
-                    if app.hex_view.search.input_text.value().is_empty() {
-                        app.dialog_renderer = None;
-                        app.state = UIState::Normal;
-                    } else {
-                        app.hex_view.search.input_text.handle_event(event);
-                    }
+            KeyCode::Backspace => {
+                let input = app.hex_view.search.input_mut();
+                if input.value().is_empty() {
+                    app.dialog_renderer = None;
+                    app.state = UIState::Normal;
+                } else {
+                    input.handle_event(event);
+                }
+            }
+            KeyCode::Enter => match app.hex_view.search.needle(app.config.endian) {
+                None => {
+                    app.state = UIState::Normal;
+                    app.dialog_renderer = None;
                 }
-                SearchMode::Hex => {
-                    if app.hex_view.search.input_hex.value().is_empty() {
+                // keep the prompt open to fix the pattern
+                Some(Err(e)) => {
+                    crate::beep!();
+                    App::log(app, e);
+                }
+                Some(Ok(needle)) => {
+                    app.state = UIState::Normal;
+                    if let Some(ofs) = search(app, &needle) {
+                        app.jump(ofs);
                         app.dialog_renderer = None;
-                        app.state = UIState::Normal;
                     } else {
-                        app.hex_view.search.input_hex.handle_event(event);
+                        app.dialog_renderer = Some(dialog_search_error_draw);
                     }
                 }
             },
-            KeyCode::Enter => {
-                match app.hex_view.search.mode {
-                    SearchMode::Utf8 => {
-                        let text = app.hex_view.search.input_text.value().to_string();
-                        app.state = UIState::Normal;
-
-                        if text.is_empty() {
-                            app.dialog_renderer = None;
-                            return Ok(false);
-                        }
-
-                        if let Some(ofs) = search(app, &text) {
-                            app.goto(ofs);
-                            app.dialog_renderer = None;
-                        } else {
-                            app.dialog_renderer = Some(dialog_search_error_draw);
-                        }
-                    }
-                    SearchMode::Hex => {
-                        let hex_string = app.hex_view.search.input_hex.value().to_string();
-
-                        if let Some(bytes) = hex_string_to_u8(&hex_string) {
-                            if let Some(ofs) = search(app, &bytes) {
-                                app.goto(ofs);
-                                app.dialog_renderer = None;
-                            } else {
-                                app.dialog_renderer = Some(dialog_search_error_draw);
-                            }
-                            app.state = UIState::Normal;
-                        }
-                    }
-                };
-            }
             KeyCode::Tab => {
                 app.hex_view.search.mode.next();
             }
-
-            KeyCode::Char(c) => {
-                match app.hex_view.search.mode {
-                    SearchMode::Utf8 => app.hex_view.search.input_text.handle_event(event),
-                    SearchMode::Hex => {
-                        if c.is_ascii_hexdigit() {
-                            app.hex_view.search.input_hex.handle_event(event)
-                        } else {
-                            None
-                        }
-                    }
-                };
+            // `#` starts a number search, like `/#d 1337t`
+            KeyCode::Char('#') if app.hex_view.search.input().value().is_empty() => {
+                app.hex_view.search.mode = SearchMode::Number;
             }
+
+            // only hex digits and the wildcards of a pattern are taken in hex mode
+            KeyCode::Char(c)
+                if app.hex_view.search.mode == SearchMode::Hex
+                    && !(c.is_ascii_hexdigit() || "?[]- ".contains(c)) => {}
             _ => {
-                match app.hex_view.search.mode {
-                    SearchMode::Utf8 => app.hex_view.search.input_text.handle_event(event),
-                    SearchMode::Hex => app.hex_view.search.input_hex.handle_event(event),
-                };
+                app.hex_view.search.input_mut().handle_event(event);
             }
         }
     }
@@ -221,3 +410,50 @@ pub fn dialog_search_error_draw(app: &mut App, frame: &mut Frame) {
     dialog.kind = MessageType::Error;
     dialog.render(app, frame);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_rfind_test() {
+        let re = Regex::new(r"(?-u)\x55\x8b.{0,2}\xe8").unwrap();
+        let mut hay = vec![0u8; REGEX_RFIND_BLOCK * 3];
+        hay[10..14].copy_from_slice(&[0x55, 0x8b, 0x90, 0xe8]);
+        hay[20..23].copy_from_slice(&[0x55, 0x8b, 0xe8]);
+
+        // the match is way before the first block searched
+        assert_eq!(regex_rfind(&re, &hay), Some((20, 3)));
+        assert_eq!(regex_rfind(&re, &hay[..22]), Some((10, 4)));
+        assert_eq!(regex_rfind(&re, &hay[..10]), None);
+    }
+
+    #[test]
+    fn find_at_test() {
+        let hay = b"abc abd";
+        let re = Needle::Regex(Regex::new(r"\bab.").unwrap());
+        assert_eq!(re.find_at(hay, 1), Some((4, 3)));
+        let re = Needle::Regex(Regex::new(r"^ab.").unwrap());
+        assert_eq!(re.find_at(hay, 1), None);
+        let bytes = Needle::Bytes(b"ab".to_vec());
+        assert_eq!(bytes.find_at(hay, 1), Some((4, 2)));
+        assert_eq!(bytes.find_at(hay, 8), None);
+    }
+
+    #[test]
+    fn text_needle_test() {
+        let hay = b"\x00H\x00e\x00L\x00l\x00o\x00\x41\x01";
+        let find =
+            |text, mode, ignore_case| text_needle(text, &mode, ignore_case).unwrap().find(hay);
+
+        assert_eq!(find("Hello", SearchMode::Utf16Le, true), Some((1, 10)));
+        assert_eq!(find("Hello", SearchMode::Utf16Be, true), Some((0, 10)));
+        assert_eq!(find("Hello", SearchMode::Utf16Be, false), None);
+        assert_eq!(find("HeLlo", SearchMode::Utf16Be, false), Some((0, 10)));
+        assert_eq!(find("l", SearchMode::Utf8, true), Some((5, 1)));
+
+        // U+0141 is 41 01, but only ASCII letters are folded
+        assert_eq!(find("\u{141}", SearchMode::Utf16Le, true), Some((11, 2)));
+        assert_eq!(find("\u{161}", SearchMode::Utf16Le, true), None);
+    }
+}
@@ -3,6 +3,8 @@ use std::io::Result;
 
 use crate::app::App;
 use crate::editor::UIState;
+use crate::hex::clipboard::YankFormat;
+use crate::hex::edit::{delete_bytes, set_change};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Direction {
@@ -64,7 +66,51 @@ impl Selection {
     }
 }
 
+// move the cursor `count` times by `step` bytes, extending or shrinking the
+// selection; it stops where a single step would have been refused
+fn extend(app: &mut App, count: usize, direction: Direction, step: usize) {
+    let size = app.file_info.size;
+    let mut offset = app.hex_view.offset;
+
+    for _ in 0..count.min(size) {
+        let selection = &mut app.hex_view.selection;
+        let new_offset = match direction {
+            Direction::LeftOrUp => offset.saturating_sub(step),
+            Direction::RightOrDown => offset.saturating_add(step).min(size - 1),
+        };
+        let crosses = match direction {
+            Direction::LeftOrUp => {
+                selection.direction == Some(Direction::RightOrDown) && new_offset < selection.start
+            }
+            Direction::RightOrDown => {
+                selection.direction == Some(Direction::LeftOrUp) && new_offset > selection.end
+            }
+        };
+        if new_offset == offset || crosses {
+            break;
+        }
+
+        match direction {
+            Direction::LeftOrUp => selection.select_left_or_up(step),
+            Direction::RightOrDown => selection.select_right_or_down(size, step),
+        }
+        offset = new_offset;
+    }
+    app.goto(offset);
+}
+
 pub fn select_events(app: &mut App, key: KeyEvent) -> Result<bool> {
+    // a count typed before a movement, like `20j`
+    if let KeyCode::Char(c @ '0'..='9') = key.code
+        && (c != '0' || app.hex_view.count.is_some())
+    {
+        let digit = c as usize - '0' as usize;
+        let count = app.hex_view.count.unwrap_or(0);
+        app.hex_view.count = Some(count.saturating_mul(10).saturating_add(digit));
+        return Ok(false);
+    }
+    let count = app.hex_view.count.take().unwrap_or(1).max(1);
+
     match key.code {
         KeyCode::Esc | KeyCode::Enter => {
             app.state = UIState::Normal;
@@ -74,59 +120,15 @@ pub fn select_events(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
 
         // Navigation
-        KeyCode::Left | KeyCode::Char('h') => {
-            let new_offset = app.hex_view.offset.saturating_sub(1);
-
-            app.hex_view.selection.select_left_or_up(1);
-            app.goto(new_offset);
-        }
-        KeyCode::Right | KeyCode::Char('l') => {
-            let new_offset = app.hex_view.offset + 1;
-
-            // return if at the last offset
-            if new_offset >= app.file_info.size {
-                return Ok(true);
-            }
-
-            app.hex_view
-                .selection
-                .select_right_or_down(app.file_info.size, 1);
-            app.goto(new_offset);
-        }
+        KeyCode::Left | KeyCode::Char('h') => extend(app, count, Direction::LeftOrUp, 1),
+        KeyCode::Right | KeyCode::Char('l') => extend(app, count, Direction::RightOrDown, 1),
         KeyCode::Up | KeyCode::Char('k') => {
-            let new_offset = app
-                .hex_view
-                .offset
-                .saturating_sub(app.config.hex_mode_bytes_per_line);
-
-            if app.hex_view.selection.direction == Some(Direction::RightOrDown)
-                && new_offset < app.hex_view.selection.start
-            {
-                return Ok(true);
-            }
-
-            app.hex_view
-                .selection
-                .select_left_or_up(app.config.hex_mode_bytes_per_line);
-            app.goto(new_offset);
+            let step = app.config.hex_mode_bytes_per_line;
+            extend(app, count, Direction::LeftOrUp, step);
         }
         KeyCode::Down | KeyCode::Char('j') => {
-            let new_offset = app
-                .hex_view
-                .offset
-                .saturating_add(app.config.hex_mode_bytes_per_line)
-                .min(app.file_info.size - 1);
-
-            if app.hex_view.selection.direction == Some(Direction::LeftOrUp)
-                && new_offset > app.hex_view.selection.end
-            {
-                return Ok(true);
-            }
-
-            app.hex_view
-                .selection
-                .select_right_or_down(app.file_info.size, app.config.hex_mode_bytes_per_line);
-            app.goto(new_offset);
+            let step = app.config.hex_mode_bytes_per_line;
+            extend(app, count, Direction::RightOrDown, step);
         }
 
         // Actions
@@ -138,10 +140,12 @@ pub fn select_events(app: &mut App, key: KeyEvent) -> Result<bool> {
 
             app.state = UIState::HexEditing;
             let s = format!("{:02X}", 0x00);
+            // the whole selection is undone at once
+            app.hex_view.history.begin();
             for offset in app.hex_view.selection {
-                app.hex_view.changed_bytes.insert(offset, s.clone());
-                app.hex_view.changed_history.push(offset);
+                set_change(app, offset, s.clone());
             }
+            app.hex_view.history.commit();
             app.hex_view.selection.clear();
         }
         // fill with NOPs
@@ -152,26 +156,45 @@ pub fn select_events(app: &mut App, key: KeyEvent) -> Result<bool> {
 
             app.state = UIState::HexEditing;
             let s = format!("{:02X}", 0x90);
+            // the whole selection is undone at once
+            app.hex_view.history.begin();
             for offset in app.hex_view.selection {
-                app.hex_view.changed_bytes.insert(offset, s.clone());
-                app.hex_view.changed_history.push(offset);
+                set_change(app, offset, s.clone());
             }
+            app.hex_view.history.commit();
             app.hex_view.selection.clear();
         }
-        // yank
-        KeyCode::Char('y') => {
-            let mut s = String::new();
-            for offset in app.hex_view.selection {
-                let b = app.read_u8(offset);
-                if let Some(byte) = b {
-                    s.push_str(&format!("{:02X}", byte));
-                }
-            }
-            if let Ok(clip) = app.clipboard.as_mut() {
-                let _ = clip.set_text(s);
+        // delete selected bytes
+        KeyCode::Char('d') | KeyCode::Char('x') => {
+            if app.file_info.is_read_only {
+                return Ok(true);
             }
+
+            let start = app.hex_view.selection.start;
+            let len = app.hex_view.selection.end - start + 1;
+            delete_bytes(app, start, len);
+            app.state = UIState::Normal;
+            app.hex_view.selection.clear();
+            app.goto(start.min(app.file_info.size.saturating_sub(1)));
+        }
+        // paste over the selection, or before it with `P`
+        KeyCode::Char(c @ ('p' | 'P')) => {
+            let start = app.hex_view.selection.start;
+            let len = app.hex_view.selection.end - start + 1;
+            crate::hex::clipboard::paste(app, start, c == 'P', Some(len));
             app.state = UIState::Normal;
             app.hex_view.selection.clear();
+            app.goto(start);
+        }
+        // yank
+        KeyCode::Char('y') => {
+            crate::hex::clipboard::yank(app, YankFormat::Hex);
+        }
+        // yank choosing the output format
+        KeyCode::Char('Y') => {
+            app.state = UIState::DialogYank;
+            app.list_state.select_first();
+            app.dialog_renderer = Some(crate::hex::clipboard::dialog_yank_draw);
         }
         _ => {}
     }
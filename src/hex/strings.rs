@@ -105,7 +105,7 @@ pub fn dialog_strings_events(app: &mut App, key: KeyEvent) -> Result<bool> {
                     );
                     return Ok(true);
                 }
-                app.goto(app.strings[choice].offset);
+                app.jump(app.strings[choice].offset);
                 app.state = UIState::Normal;
                 app.dialog_renderer = None;
             }
@@ -200,7 +200,7 @@ impl Commands {
             .build()
             .unwrap_or(default_regex);
 
-        let buffer = app.file_info.get_buffer();
+        let buffer = app.file_info.contents();
         for (offset, byte) in buffer.iter().enumerate() {
             if byte.is_ascii_graphic() || *byte == b' ' {
                 candidate.push(*byte as char);
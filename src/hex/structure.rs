@@ -0,0 +1,204 @@
+use ratatui::{
+    Frame,
+    crossterm::event::KeyModifiers,
+    layout::Alignment,
+    widgets::{Block, Clear, List, ListItem, Padding},
+};
+
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent};
+use std::io::Result;
+
+use crate::{
+    app::{App, Dz6Error},
+    commands::command_error_draw,
+    editor::UIState,
+    util::center_widget,
+    widgets::ListChoice,
+};
+
+pub fn dialog_structure_draw(app: &mut App, frame: &mut Frame) {
+    let Some(bin) = &app.file_info.binary else {
+        return;
+    };
+
+    let items: Vec<ListItem> = bin
+        .fields
+        .iter()
+        .map(|f| {
+            let offset = match f.offset {
+                Some(ofs) => format!("{:08X}", ofs),
+                None => " ".repeat(8),
+            };
+            let name = format!("{}{}", "  ".repeat(f.depth), f.name);
+            ListItem::from(format!("{}  {:<28} {}", offset, name, f.value))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(app.config.theme.dialog)
+        .block(
+            Block::bordered()
+                .title(format!(" {} structure ", bin.format))
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        )
+        .highlight_style(app.config.theme.highlight)
+        .repeat_highlight_symbol(true);
+
+    let width = (frame.area().width * 3 / 4).max(100.min(frame.area().width));
+    let height = frame.area().height * 3 / 4;
+    let dialog_area = center_widget(width, height, frame.area());
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_stateful_widget(list, dialog_area, &mut app.hex_view.structure_list_state);
+}
+
+/// Open the structure browser, if the file format is known
+pub fn open_structure(app: &mut App) {
+    if app.file_info.binary.is_none() {
+        app.last_error = Dz6Error {
+            message: "Unknown file format".to_string(),
+        };
+        app.dialog_renderer = Some(command_error_draw);
+        return;
+    }
+
+    app.state = UIState::DialogStructure;
+    app.dialog_renderer = Some(dialog_structure_draw);
+    if app.hex_view.structure_list_state.selected().is_none() {
+        app.hex_view.structure_list_state.select_first();
+    }
+}
+
+pub fn dialog_structure_events(app: &mut App, event: &Event) -> Result<bool> {
+    if let Event::Key(key) = event {
+        let state = &mut app.hex_view.structure_list_state;
+        match key.code {
+            KeyCode::Esc => {
+                app.dialog_renderer = None;
+                app.state = UIState::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+            KeyCode::PageDown => state.scroll_down_by(30),
+            KeyCode::PageUp => state.scroll_up_by(30),
+            KeyCode::Home => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    state.select_first();
+                } else if let Some(n) = state.selected() {
+                    state.select(Some(n.saturating_sub(29)));
+                }
+            }
+            KeyCode::End => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    state.select_last();
+                } else if let Some(n) = state.selected() {
+                    state.select(Some(n + 29));
+                }
+            }
+            // pick another architecture of a fat binary
+            KeyCode::Char('a') => {
+                let slice = app
+                    .file_info
+                    .binary
+                    .as_ref()
+                    .map(|b| (b.slices.len(), b.slice));
+                match slice {
+                    Some((n, slice)) if n > 1 => {
+                        app.state = UIState::DialogSlices;
+                        app.list_state.select(Some(slice));
+                        app.dialog_renderer = Some(dialog_slices_draw);
+                    }
+                    _ => crate::beep!(),
+                }
+            }
+            // jump to the selected item; items without an offset are ignored
+            KeyCode::Enter => {
+                let offset = state
+                    .selected()
+                    .zip(app.file_info.binary.as_ref())
+                    .and_then(|(i, bin)| bin.fields.get(i))
+                    .and_then(|f| f.offset);
+
+                if let Some(offset) = offset {
+                    if offset < app.file_info.size {
+                        app.jump(offset);
+                    }
+                    app.state = UIState::Normal;
+                    app.dialog_renderer = None;
+                } else {
+                    crate::beep!();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+pub fn dialog_slices_draw(app: &mut App, frame: &mut Frame) {
+    let Some(bin) = &app.file_info.binary else {
+        return;
+    };
+
+    let mut dialog = ListChoice::new();
+    dialog.set_title(" Architecture ".to_string());
+    dialog.choices = bin
+        .slices
+        .iter()
+        .map(|s| format!("{:<10} {:08X}", s.name, s.offset))
+        .collect();
+    dialog.render(app, frame);
+}
+
+fn close_slices(app: &mut App) {
+    app.state = UIState::DialogStructure;
+    app.dialog_renderer = Some(dialog_structure_draw);
+}
+
+pub fn dialog_slices_events(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let count = app.file_info.binary.as_ref().map_or(0, |b| b.slices.len());
+
+    match key.code {
+        // back to the structure browser
+        KeyCode::Esc => close_slices(app),
+        // parse the chosen slice and go to its start
+        KeyCode::Enter => {
+            let slice = app.list_state.selected().unwrap_or(0);
+            app.select_slice(slice);
+            app.hex_view.structure_list_state.select_first();
+            if let Some(s) = app
+                .file_info
+                .binary
+                .as_ref()
+                .and_then(|b| b.slices.get(slice))
+            {
+                let offset = s.offset;
+                app.jump(offset);
+            }
+            close_slices(app);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            if app.list_state.selected() == Some(count.saturating_sub(1)) {
+                app.list_state.select_first();
+            } else {
+                app.list_state.select_next();
+            }
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            if app.list_state.selected() == Some(0) {
+                app.list_state.select_last();
+            } else {
+                app.list_state.select_previous();
+            }
+        }
+        KeyCode::PageUp | KeyCode::Home => {
+            app.list_state.select_first();
+        }
+        KeyCode::PageDown | KeyCode::End => {
+            app.list_state.select_last();
+        }
+        _ => {}
+    }
+    Ok(false)
+}
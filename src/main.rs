@@ -2,15 +2,20 @@ mod app;
 mod commands;
 mod config;
 mod database;
+mod disasm;
 mod draw;
 mod editor;
 mod events;
+mod formats;
 mod global;
 mod hex;
 mod initfile;
 mod input_history;
+mod patch;
+mod piece_table;
 mod reader;
 mod ruler;
+mod save;
 mod text;
 mod themes;
 mod util;
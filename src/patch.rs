@@ -0,0 +1,593 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    app::App,
+    hex::edit::{delete_bytes, insert_bytes, set_change},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Text,
+}
+
+impl PatchFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ips" => Some(PatchFormat::Ips),
+            "bps" => Some(PatchFormat::Bps),
+            "text" | "txt" => Some(PatchFormat::Text),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of a patch from its first bytes
+    fn detect(patch: &[u8]) -> Self {
+        if patch.starts_with(IPS_MAGIC) {
+            PatchFormat::Ips
+        } else if patch.starts_with(BPS_MAGIC) {
+            PatchFormat::Bps
+        } else {
+            PatchFormat::Text
+        }
+    }
+}
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+// offsets are 24-bit and record sizes 16-bit
+const IPS_MAX_OFFSET: usize = 0xFFFFFF;
+const IPS_MAX_RECORD: usize = 0xFFFF;
+const BPS_MAGIC: &[u8] = b"BPS1";
+
+// BPS actions
+const SOURCE_READ: u64 = 0;
+const TARGET_READ: u64 = 1;
+const SOURCE_COPY: u64 = 2;
+const TARGET_COPY: u64 = 3;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0u32, |c, &b| {
+        TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+/// Ranges of `target` that differ from `source`, as (offset, length). Bytes
+/// past the end of `source` always differ.
+fn diff_runs(source: &[u8], target: &[u8]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+
+    for (i, b) in target.iter().enumerate() {
+        let differs = source.get(i) != Some(b);
+        match (differs, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push((s, i - s));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push((s, target.len() - s));
+    }
+    runs
+}
+
+fn encode_ips(source: &[u8], target: &[u8]) -> io::Result<Vec<u8>> {
+    if target.len() > IPS_MAX_OFFSET {
+        return Err(invalid("IPS patches are limited to files up to 16 MiB"));
+    }
+
+    let mut patch = IPS_MAGIC.to_vec();
+    for (start, len) in diff_runs(source, target) {
+        let (mut start, end) = (start, start + len);
+        while start < end {
+            // a record at offset 0x454f46 would be read as "EOF", so it
+            // starts one byte earlier, even when a long run is split there
+            if start == 0x454F46 {
+                start -= 1;
+            }
+            let size = (end - start).min(IPS_MAX_RECORD);
+            patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+            patch.extend_from_slice(&(size as u16).to_be_bytes());
+            patch.extend_from_slice(&target[start..start + size]);
+            start += size;
+        }
+    }
+    patch.extend_from_slice(IPS_EOF);
+
+    // truncation extension
+    if target.len() < source.len() {
+        patch.extend_from_slice(&(target.len() as u32).to_be_bytes()[1..]);
+    }
+    Ok(patch)
+}
+
+fn apply_ips(patch: &[u8], source: &[u8]) -> io::Result<Vec<u8>> {
+    let truncated = || invalid("truncated IPS patch");
+    let mut target = source.to_vec();
+    let mut pos = IPS_MAGIC.len();
+
+    let read = |pos: &mut usize, n: usize| -> io::Result<usize> {
+        let bytes = patch.get(*pos..*pos + n).ok_or_else(truncated)?;
+        *pos += n;
+        Ok(bytes.iter().fold(0, |v, &b| v << 8 | b as usize))
+    };
+
+    loop {
+        if patch.get(pos..pos + 3) == Some(IPS_EOF) {
+            pos += 3;
+            break;
+        }
+        let offset = read(&mut pos, 3)?;
+        let size = read(&mut pos, 2)?;
+
+        let data = if size == 0 {
+            // run-length encoded record
+            let count = read(&mut pos, 2)?;
+            let value = read(&mut pos, 1)? as u8;
+            vec![value; count]
+        } else {
+            let data = patch.get(pos..pos + size).ok_or_else(truncated)?;
+            pos += size;
+            data.to_vec()
+        };
+
+        if target.len() < offset + data.len() {
+            target.resize(offset + data.len(), 0);
+        }
+        target[offset..offset + data.len()].copy_from_slice(&data);
+    }
+
+    if patch.len() >= pos + 3 {
+        let len = read(&mut pos, 3)?;
+        target.truncate(len);
+    }
+    Ok(target)
+}
+
+fn write_varint(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let x = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            patch.push(0x80 | x);
+            break;
+        }
+        patch.push(x);
+        value -= 1;
+    }
+}
+
+fn read_varint(patch: &[u8], pos: &mut usize) -> io::Result<u64> {
+    let mut value = 0u64;
+    let mut shift = 1u64;
+    loop {
+        let x = *patch
+            .get(*pos)
+            .ok_or_else(|| invalid("truncated BPS patch"))?;
+        *pos += 1;
+        value = ((x & 0x7f) as u64)
+            .checked_mul(shift)
+            .and_then(|v| value.checked_add(v))
+            .ok_or_else(|| invalid("invalid BPS patch"))?;
+        if x & 0x80 != 0 {
+            return Ok(value);
+        }
+        shift = shift
+            .checked_mul(0x80)
+            .ok_or_else(|| invalid("invalid BPS patch"))?;
+        value = value
+            .checked_add(shift)
+            .ok_or_else(|| invalid("invalid BPS patch"))?;
+    }
+}
+
+fn encode_bps(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = BPS_MAGIC.to_vec();
+    write_varint(&mut patch, source.len() as u64);
+    write_varint(&mut patch, target.len() as u64);
+    // no metadata
+    write_varint(&mut patch, 0);
+
+    // unchanged bytes are read from the source and changed ones are
+    // stored in the patch
+    let mut offset = 0;
+    for (start, len) in diff_runs(source, target) {
+        if start > offset {
+            write_varint(&mut patch, ((start - offset - 1) as u64) << 2 | SOURCE_READ);
+        }
+        write_varint(&mut patch, ((len - 1) as u64) << 2 | TARGET_READ);
+        patch.extend_from_slice(&target[start..start + len]);
+        offset = start + len;
+    }
+    if target.len() > offset {
+        write_varint(
+            &mut patch,
+            ((target.len() - offset - 1) as u64) << 2 | SOURCE_READ,
+        );
+    }
+
+    patch.extend_from_slice(&crc32(source).to_le_bytes());
+    patch.extend_from_slice(&crc32(target).to_le_bytes());
+    let checksum = crc32(&patch);
+    patch.extend_from_slice(&checksum.to_le_bytes());
+    patch
+}
+
+fn apply_bps(patch: &[u8], source: &[u8]) -> io::Result<Vec<u8>> {
+    if patch.len() < BPS_MAGIC.len() + 12 {
+        return Err(invalid("truncated BPS patch"));
+    }
+    let footer = patch.len() - 12;
+    let crc = |pos: usize| u32::from_le_bytes(patch[pos..pos + 4].try_into().unwrap());
+
+    if crc32(&patch[..footer + 8]) != crc(footer + 8) {
+        return Err(invalid("BPS patch is corrupted"));
+    }
+    if crc32(source) != crc(footer) {
+        return Err(invalid("BPS patch was made for a different file"));
+    }
+
+    let mut pos = BPS_MAGIC.len();
+    let source_size = read_varint(patch, &mut pos)? as usize;
+    let target_size = read_varint(patch, &mut pos)? as usize;
+    let metadata_size = read_varint(patch, &mut pos)? as usize;
+    pos = pos.saturating_add(metadata_size);
+
+    if source_size != source.len() {
+        return Err(invalid("BPS patch was made for a different file"));
+    }
+
+    // the sizes come from the patch, so only so much is trusted up front
+    let capacity = source.len().saturating_mul(2).saturating_add(patch.len());
+    let mut target = Vec::with_capacity(target_size.min(capacity));
+    let mut source_rel = 0i64;
+    let mut target_rel = 0i64;
+
+    let relative = |patch: &[u8], pos: &mut usize| -> io::Result<i64> {
+        let data = read_varint(patch, pos)?;
+        let delta = (data >> 1) as i64;
+        Ok(if data & 1 != 0 { -delta } else { delta })
+    };
+
+    while pos < footer {
+        let data = read_varint(patch, &mut pos)?;
+        let len = (data >> 2) as usize + 1;
+        let out = target.len();
+        let bad = || invalid("invalid BPS patch");
+
+        // no action may write past the target size
+        out.checked_add(len)
+            .filter(|&end| end <= target_size)
+            .ok_or_else(bad)?;
+
+        match data & 3 {
+            SOURCE_READ => {
+                let bytes = source.get(out..out + len).ok_or_else(bad)?;
+                target.extend_from_slice(bytes);
+            }
+            TARGET_READ => {
+                let end = pos
+                    .checked_add(len)
+                    .filter(|&end| end <= footer)
+                    .ok_or_else(|| invalid("truncated BPS patch"))?;
+                target.extend_from_slice(&patch[pos..end]);
+                pos = end;
+            }
+            SOURCE_COPY => {
+                source_rel = source_rel
+                    .checked_add(relative(patch, &mut pos)?)
+                    .ok_or_else(bad)?;
+                let start = usize::try_from(source_rel).map_err(|_| bad())?;
+                let end = start.checked_add(len).ok_or_else(bad)?;
+                let bytes = source.get(start..end).ok_or_else(bad)?;
+                target.extend_from_slice(bytes);
+                source_rel = end as i64;
+            }
+            TARGET_COPY => {
+                // the range may overlap the bytes being written
+                target_rel = target_rel
+                    .checked_add(relative(patch, &mut pos)?)
+                    .ok_or_else(bad)?;
+                let start = usize::try_from(target_rel).map_err(|_| bad())?;
+                let end = start.checked_add(len).ok_or_else(bad)?;
+                if start >= out {
+                    return Err(bad());
+                }
+                for i in start..end {
+                    target.push(target[i]);
+                }
+                target_rel = end as i64;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if target.len() != target_size {
+        return Err(invalid(
+            "BPS patch ended before the target size was reached",
+        ));
+    }
+    if crc32(&target) != crc(footer + 4) {
+        return Err(invalid("BPS patch produced a different file than expected"));
+    }
+    Ok(target)
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    hex::decode(s.split_whitespace().collect::<String>()).ok()
+}
+
+/// One line per changed range: `offset: old -> new`, in hex. Bytes added at
+/// the end of the file have no old value and truncated ones have no new value.
+fn encode_text(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut lines = vec!["# dz6 patch: offset: old -> new".to_string()];
+    let line = |offset: usize, old: &[u8], new: &[u8]| {
+        [
+            format!("{:08X}:", offset),
+            hex_bytes(old),
+            "->".to_string(),
+            hex_bytes(new),
+        ]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+    };
+
+    let common = source.len().min(target.len());
+    for (start, len) in diff_runs(source, &target[..common]) {
+        lines.push(line(
+            start,
+            &source[start..start + len],
+            &target[start..start + len],
+        ));
+    }
+    if target.len() > common {
+        lines.push(line(common, &[], &target[common..]));
+    } else if source.len() > common {
+        lines.push(line(common, &source[common..], &[]));
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text.into_bytes()
+}
+
+fn apply_text(patch: &[u8], source: &[u8]) -> io::Result<Vec<u8>> {
+    let text = std::str::from_utf8(patch).map_err(|_| invalid("unknown patch format"))?;
+    let mut target = Vec::with_capacity(source.len());
+    // source offset up to which bytes were copied to the target
+    let mut copied = 0;
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad_line = || invalid(&format!("invalid patch line {}: {}", n + 1, line));
+
+        let (offset, change) = line.split_once(':').ok_or_else(bad_line)?;
+        let (old, new) = change.split_once("->").ok_or_else(bad_line)?;
+        let offset = usize::from_str_radix(offset.trim(), 16).map_err(|_| bad_line())?;
+        let old = parse_hex_bytes(old).ok_or_else(bad_line)?;
+        let new = parse_hex_bytes(new).ok_or_else(bad_line)?;
+
+        if offset < copied {
+            return Err(invalid(&format!(
+                "patch line {} overlaps a previous one",
+                n + 1
+            )));
+        }
+        let end = offset.checked_add(old.len()).ok_or_else(bad_line)?;
+        if source.get(offset..end) != Some(old.as_slice()) {
+            return Err(invalid(&format!(
+                "patch does not match the file at offset {:X}",
+                offset
+            )));
+        }
+
+        target.extend_from_slice(&source[copied..offset]);
+        target.extend_from_slice(&new);
+        copied = end;
+    }
+    target.extend_from_slice(&source[copied..]);
+    Ok(target)
+}
+
+/// Create a patch that turns `source` into `target`
+pub fn encode(format: PatchFormat, source: &[u8], target: &[u8]) -> io::Result<Vec<u8>> {
+    match format {
+        PatchFormat::Ips => encode_ips(source, target),
+        PatchFormat::Bps => Ok(encode_bps(source, target)),
+        PatchFormat::Text => Ok(encode_text(source, target)),
+    }
+}
+
+/// Apply a patch of any supported format to `source` and return the result
+pub fn decode(patch: &[u8], source: &[u8]) -> io::Result<Vec<u8>> {
+    match PatchFormat::detect(patch) {
+        PatchFormat::Ips => apply_ips(patch, source),
+        PatchFormat::Bps => apply_bps(patch, source),
+        PatchFormat::Text => apply_text(patch, source),
+    }
+}
+
+/// Write the pending changes, insertions and deletions included, as a patch
+/// against the file on disk
+pub fn export(app: &App, format: PatchFormat, path: &str) -> io::Result<()> {
+    let target = app.edited_contents();
+    let patch = encode(format, app.file_info.get_buffer(), &target)?;
+    fs::write(Path::new(path), patch)
+}
+
+/// Load a patch into the pending changes, so it can be reviewed before
+/// being written. The patch is applied over the buffer with its insertions
+/// and deletions, and is undone at once. Returns the number of bytes changed.
+pub fn apply(app: &mut App, path: &str) -> io::Result<usize> {
+    if app.file_info.is_read_only {
+        return Err(io::Error::other("file is open in read-only mode"));
+    }
+
+    let patch = fs::read(Path::new(path))?;
+    let source = app.file_info.contents().into_owned();
+    let target = decode(&patch, &source)?;
+    let common = source.len().min(target.len());
+
+    app.hex_view.history.begin();
+    let mut changed = 0;
+    for (start, len) in diff_runs(&source, &target[..common]) {
+        for (i, b) in target[start..start + len].iter().enumerate() {
+            set_change(app, start + i, format!("{:02X}", b));
+        }
+        changed += len;
+    }
+    if target.len() > source.len() {
+        insert_bytes(app, source.len(), &target[source.len()..]);
+    } else if source.len() > target.len() {
+        delete_bytes(app, target.len(), source.len() - target.len());
+    }
+    app.hex_view.history.commit();
+
+    Ok(changed + target.len().abs_diff(source.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &[u8] = b"0123456789abcdef";
+
+    fn round_trip(format: PatchFormat, target: &[u8]) {
+        let patch = encode(format, SOURCE, target).unwrap();
+        assert_eq!(PatchFormat::detect(&patch), format);
+        assert_eq!(decode(&patch, SOURCE).unwrap(), target);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        for format in [PatchFormat::Ips, PatchFormat::Bps, PatchFormat::Text] {
+            round_trip(format, SOURCE);
+            round_trip(format, b"0X23456YY9abcdeZ");
+            round_trip(format, b"0123456789abcdef+tail");
+            round_trip(format, b"01234");
+            round_trip(format, b"");
+        }
+    }
+
+    #[test]
+    fn text_patch_test() {
+        let patch = encode(PatchFormat::Text, SOURCE, b"0X23456789abcdef!").unwrap();
+        assert_eq!(
+            String::from_utf8(patch).unwrap(),
+            "# dz6 patch: offset: old -> new\n00000001: 31 -> 58\n00000010: -> 21\n"
+        );
+        assert!(decode(b"00000001: 32 -> 58\n", SOURCE).is_err());
+        assert!(decode(b"garbage\n", SOURCE).is_err());
+    }
+
+    #[test]
+    fn ips_rle_test() {
+        // a run-length encoded record writing 4 'z' at offset 2, then a
+        // truncation to 8 bytes
+        let patch = b"PATCH\x00\x00\x02\x00\x00\x00\x04zEOF\x00\x00\x08";
+        assert_eq!(decode(patch, SOURCE).unwrap(), b"01zzzz67");
+    }
+
+    #[test]
+    fn bps_checks_source_test() {
+        let patch = encode(PatchFormat::Bps, SOURCE, b"0X23").unwrap();
+        assert!(decode(&patch, b"something else").is_err());
+    }
+
+    // a BPS patch for SOURCE with the given target size and actions
+    fn bps(target_size: u64, actions: &[u64]) -> Vec<u8> {
+        let mut patch = BPS_MAGIC.to_vec();
+        for value in [SOURCE.len() as u64, target_size, 0].iter().chain(actions) {
+            write_varint(&mut patch, *value);
+        }
+        patch.extend_from_slice(&crc32(SOURCE).to_le_bytes());
+        patch.extend_from_slice(&crc32(b"").to_le_bytes());
+        let crc = crc32(&patch);
+        patch.extend_from_slice(&crc.to_le_bytes());
+        patch
+    }
+
+    #[test]
+    fn hostile_patch_test() {
+        // huge target sizes and lengths are refused, not allocated
+        assert!(decode(&bps(u64::MAX >> 8, &[]), SOURCE).is_err());
+        assert!(decode(&bps(4, &[(1000 << 2) | SOURCE_READ]), SOURCE).is_err());
+        assert!(decode(&bps(4, &[(u64::MAX >> 3 << 2) | TARGET_COPY, 0]), SOURCE).is_err());
+        assert!(decode(&bps(8, &[SOURCE_COPY, u64::MAX - 1]), SOURCE).is_err());
+        assert!(decode(&bps(8, &[(3 << 2) | SOURCE_READ]), SOURCE).is_err());
+
+        assert!(decode(b"FFFFFFFFFFFFFFFF: 30 -> 31\n", SOURCE).is_err());
+
+        // varints too big for 64 bits
+        for n in 10..=18 {
+            let mut varint = vec![0x7f; n];
+            varint.push(0x80);
+            assert!(read_varint(&varint, &mut 0).is_err());
+            let mut patch = BPS_MAGIC.to_vec();
+            patch.extend(&varint);
+            patch.extend([0; 12]);
+            assert!(decode(&patch, SOURCE).is_err());
+        }
+        let mut patch = Vec::new();
+        write_varint(&mut patch, u64::MAX);
+        assert_eq!(read_varint(&patch, &mut 0).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn ips_eof_offset_test() {
+        // a run split at 0x454f46 must not start a record there
+        let source = vec![0; 0x454F46 + 16];
+        let mut target = source.clone();
+        target[0x454F46 - IPS_MAX_RECORD..0x454F46 + 8].fill(1);
+        let patch = encode(PatchFormat::Ips, &source, &target).unwrap();
+        assert_eq!(decode(&patch, &source).unwrap(), target);
+    }
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+}
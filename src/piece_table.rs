@@ -0,0 +1,284 @@
+use std::borrow::Cow;
+
+/// Where the bytes of a piece come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Original,
+    Added,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Piece {
+    pub source: Source,
+    pub start: usize,
+    pub len: usize,
+}
+
+/// PieceTable keeps track of bytes inserted into and deleted from the
+/// memory mapped file without touching it. The original buffer is never
+/// copied: each piece points either to a range of it or to a range of
+/// `added`, an append-only buffer that holds every inserted byte.
+#[derive(Default, Debug)]
+pub struct PieceTable {
+    pub pieces: Vec<Piece>,
+    pub added: Vec<u8>,
+    len: usize,
+    original_len: usize,
+}
+
+impl PieceTable {
+    pub fn new(original_len: usize) -> Self {
+        let pieces = if original_len > 0 {
+            vec![Piece {
+                source: Source::Original,
+                start: 0,
+                len: original_len,
+            }]
+        } else {
+            Vec::new()
+        };
+
+        PieceTable {
+            pieces,
+            added: Vec::new(),
+            len: original_len,
+            original_len,
+        }
+    }
+
+    /// Size of the buffer as seen by the editor
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if bytes were inserted or deleted, meaning the buffer
+    /// no longer maps 1:1 to the original file
+    pub fn is_modified(&self) -> bool {
+        match self.pieces.as_slice() {
+            [] => self.original_len != 0,
+            [p] => {
+                *p != Piece {
+                    source: Source::Original,
+                    start: 0,
+                    len: self.original_len,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the piece index and the position inside it for `offset`
+    fn locate(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut piece_start = 0;
+        for (i, p) in self.pieces.iter().enumerate() {
+            if offset < piece_start + p.len {
+                return Some((i, offset - piece_start));
+            }
+            piece_start += p.len;
+        }
+        None
+    }
+
+    /// Makes sure a piece starts exactly at `offset` and returns its index.
+    /// If `offset` is the end of the buffer, the number of pieces is returned.
+    fn split(&mut self, offset: usize) -> usize {
+        match self.locate(offset) {
+            None => self.pieces.len(),
+            Some((i, 0)) => i,
+            Some((i, pos)) => {
+                let p = self.pieces[i];
+                self.pieces[i].len = pos;
+                self.pieces.insert(
+                    i + 1,
+                    Piece {
+                        source: p.source,
+                        start: p.start + pos,
+                        len: p.len - pos,
+                    },
+                );
+                i + 1
+            }
+        }
+    }
+
+    fn piece_bytes<'a>(&'a self, original: &'a [u8], p: &Piece) -> &'a [u8] {
+        match p.source {
+            Source::Original => &original[p.start..p.start + p.len],
+            Source::Added => &self.added[p.start..p.start + p.len],
+        }
+    }
+
+    pub fn byte_at(&self, original: &[u8], offset: usize) -> Option<u8> {
+        let (i, pos) = self.locate(offset)?;
+        let p = &self.pieces[i];
+        self.piece_bytes(original, p).get(pos).copied()
+    }
+
+    /// Reads up to `len` bytes starting at `offset`
+    pub fn read(&self, original: &[u8], offset: usize, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len.min(self.len.saturating_sub(offset)));
+
+        if let Some((first, pos)) = self.locate(offset) {
+            let mut skip = pos;
+            for p in &self.pieces[first..] {
+                let chunk = &self.piece_bytes(original, p)[skip..];
+                let wanted = len - bytes.len();
+                bytes.extend_from_slice(&chunk[..chunk.len().min(wanted)]);
+                if bytes.len() == len {
+                    break;
+                }
+                skip = 0;
+            }
+        }
+
+        bytes
+    }
+
+    /// Reads up to `len` bytes starting at `offset`, borrowing them from the
+    /// original buffer if no bytes were inserted or deleted
+    pub fn slice<'a>(&self, original: &'a [u8], offset: usize, len: usize) -> Cow<'a, [u8]> {
+        if self.is_modified() {
+            Cow::Owned(self.read(original, offset, len))
+        } else {
+            let end = self.len.min(original.len());
+            let start = offset.min(end);
+            Cow::Borrowed(&original[start..start.saturating_add(len).min(end)])
+        }
+    }
+
+    /// Returns the whole buffer. The original one is borrowed as is
+    /// if no bytes were inserted or deleted.
+    pub fn contents<'a>(&self, original: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.is_modified() {
+            Cow::Owned(self.read(original, 0, self.len))
+        } else {
+            Cow::Borrowed(&original[..self.len.min(original.len())])
+        }
+    }
+
+    /// Returns true if the byte at `offset` was inserted by the user
+    pub fn is_inserted(&self, offset: usize) -> bool {
+        self.locate(offset)
+            .is_some_and(|(i, _)| self.pieces[i].source == Source::Added)
+    }
+
+    pub fn insert(&mut self, offset: usize, bytes: &[u8]) {
+        if bytes.is_empty() || offset > self.len {
+            return;
+        }
+
+        let i = self.split(offset);
+        let start = self.added.len();
+        self.added.extend_from_slice(bytes);
+
+        // consecutive typing ends up in a single piece
+        if i > 0 {
+            let prev = &mut self.pieces[i - 1];
+            if prev.source == Source::Added && prev.start + prev.len == start {
+                prev.len += bytes.len();
+                self.len += bytes.len();
+                return;
+            }
+        }
+
+        self.pieces.insert(
+            i,
+            Piece {
+                source: Source::Added,
+                start,
+                len: bytes.len(),
+            },
+        );
+        self.len += bytes.len();
+    }
+
+    /// Deletes `len` bytes from `offset` and returns the pieces that held
+    /// them, so they can be restored later
+    pub fn delete(&mut self, offset: usize, len: usize) -> Vec<Piece> {
+        let len = len.min(self.len.saturating_sub(offset));
+        if len == 0 {
+            return Vec::new();
+        }
+
+        let first = self.split(offset);
+        let last = self.split(offset + len);
+        let removed = self.pieces.drain(first..last).collect();
+        self.len -= len;
+        self.merge();
+        removed
+    }
+
+    /// Puts pieces returned by delete() back at `offset`
+    pub fn restore(&mut self, offset: usize, pieces: &[Piece]) {
+        if offset > self.len {
+            return;
+        }
+
+        let i = self.split(offset);
+        self.pieces.splice(i..i, pieces.iter().copied());
+        self.len += pieces.iter().map(|p| p.len).sum::<usize>();
+        self.merge();
+    }
+
+    /// Joins neighbouring pieces that point to contiguous ranges, so a buffer
+    /// whose deletions were all restored is no longer seen as modified
+    fn merge(&mut self) {
+        self.pieces.dedup_by(|next, prev| {
+            if prev.source == next.source && prev.start + prev.len == next.start {
+                prev.len += next.len;
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_delete_test() {
+        let original = b"0123456789";
+        let mut pt = PieceTable::new(original.len());
+        assert!(!pt.is_modified());
+        assert_eq!(pt.contents(original).as_ref(), original);
+        assert_eq!(pt.slice(original, 8, 100).as_ref(), b"89");
+
+        pt.insert(2, b"ab");
+        pt.insert(4, b"c");
+        assert_eq!(pt.contents(original).as_ref(), b"01abc23456789");
+        assert_eq!(pt.len(), 13);
+        assert!(pt.is_inserted(3));
+        assert!(!pt.is_inserted(5));
+
+        // appending at the end is allowed
+        pt.insert(13, b"Z");
+        assert_eq!(pt.byte_at(original, 13), Some(b'Z'));
+
+        let removed = pt.delete(1, 5);
+        assert_eq!(pt.contents(original).as_ref(), b"03456789Z");
+        assert_eq!(pt.read(original, 7, 100), b"9Z");
+        assert_eq!(pt.slice(original, 1, 3).as_ref(), b"345");
+        assert!(pt.is_modified());
+
+        // deleted bytes are restored as they were
+        pt.restore(1, &removed);
+        assert_eq!(pt.contents(original).as_ref(), b"01abc23456789Z");
+        assert!(!pt.is_inserted(1));
+        assert!(pt.is_inserted(2));
+
+        // deleting past the end is clamped
+        let removed = pt.delete(13, 10);
+        assert_eq!(removed.iter().map(|p| p.len).sum::<usize>(), 1);
+        assert_eq!(pt.len(), 13);
+        assert_eq!(pt.byte_at(original, 13), None);
+
+        // a buffer whose deletions were restored is no longer modified
+        let mut pt = PieceTable::new(original.len());
+        let removed = pt.delete(3, 4);
+        pt.restore(3, &removed);
+        assert!(!pt.is_modified());
+    }
+}
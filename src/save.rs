@@ -0,0 +1,100 @@
+use std::{
+    fs::{self, File},
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// files up to this size are entirely hashed; bigger ones are sampled
+const FULL_HASH_LIMIT: u64 = 16 * 1024 * 1024;
+const SAMPLE_SIZE: usize = 4096;
+const SAMPLES: u64 = 256;
+
+/// FileStamp is what we know about a file on disk when it's read, so we can
+/// tell whether another process changed it before we write to it.
+#[derive(Debug, PartialEq)]
+pub struct FileStamp {
+    mtime: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let meta = path.metadata()?;
+        let len = meta.len();
+        let mut f = File::open(path)?;
+        let mut hasher = DefaultHasher::new();
+
+        if len <= FULL_HASH_LIMIT {
+            let mut data = Vec::with_capacity(len as usize);
+            f.read_to_end(&mut data)?;
+            hasher.write(&data);
+        } else {
+            // hash evenly spaced blocks, including the last one
+            let mut block = vec![0; SAMPLE_SIZE];
+            let step = (len - SAMPLE_SIZE as u64) / (SAMPLES - 1);
+            for i in 0..SAMPLES {
+                f.seek(SeekFrom::Start(i * step))?;
+                f.read_exact(&mut block)?;
+                hasher.write(&block);
+            }
+        }
+
+        Ok(FileStamp {
+            mtime: meta.modified().ok(),
+            len,
+            hash: hasher.finish(),
+        })
+    }
+}
+
+/// Temporary file used to write `path` before renaming it
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".dz6tmp");
+    path.with_file_name(name)
+}
+
+/// Write a whole file to a temporary one and then rename it over `path`, so
+/// a failed write never leaves a half-written file behind. If `copy_from` is
+/// given, the temporary file starts as a copy of it and `write` changes it.
+pub fn atomic_write<F>(path: &Path, copy_from: Option<&Path>, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let tmp = temp_path(path);
+
+    let result = (|| {
+        if let Some(src) = copy_from {
+            fs::copy(src, &tmp)?;
+        }
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(copy_from.is_none())
+            .open(&tmp)?;
+        write(&mut f)?;
+        f.sync_all()?;
+
+        // keep the permissions of the file being replaced
+        if let Ok(meta) = path.metadata() {
+            fs::set_permissions(&tmp, meta.permissions())?;
+        }
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Copy `path` to `path.bak`
+pub fn backup(path: &Path) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    fs::copy(path, path.with_file_name(name))?;
+    Ok(())
+}
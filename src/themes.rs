@@ -7,6 +7,7 @@ pub struct Theme {
     pub changed_bytes: Style,
     pub highlight: Style,
     pub byte_highlight: Style,
+    pub search_match: Style,
     pub topbar: Style,
     pub error: Style,
     pub editing: Style,
@@ -37,6 +38,9 @@ pub const DARK: Theme = Theme {
         .fg(Color::Rgb(255, 255, 255))
         .bg(Color::Rgb(38, 79, 120)),
     byte_highlight: Style::new().fg(Color::White).bg(Color::Red),
+    search_match: Style::new()
+        .fg(Color::from_u32(0x1e1e1e))
+        .bg(Color::from_u32(0xce9178)),
     topbar: Style::new()
         .fg(Color::Rgb(204, 204, 204))
         .bg(Color::from_u32(0x3c3c3c)),
@@ -80,6 +84,10 @@ pub const LIGHT: Theme = Theme {
 
     byte_highlight: Style::new().fg(Color::Black).bg(Color::from_u32(0xffb3b3)),
 
+    search_match: Style::new()
+        .fg(Color::from_u32(0x000000))
+        .bg(Color::from_u32(0xf8c9a0)),
+
     topbar: Style::new()
         .fg(Color::from_u32(0x333333))
         .bg(Color::from_u32(0xf3f3f3)),